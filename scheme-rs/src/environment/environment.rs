@@ -1,7 +1,14 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
-use wasm_bindgen::prelude::*;
-
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+};
+
+use crate::library::Libraries;
 use crate::lisp_val::LispVal;
+use crate::port::{Port, Signal};
+use crate::source::Sources;
 
 pub type Bindings = HashMap<String, LispVal>;
 
@@ -10,47 +17,76 @@ pub struct Environment {
     parent: Option<Env>,
 }
 
-pub type Signal = Box<dyn FnMut(&mut Vec<LispVal>)>;
-
-#[wasm_bindgen]
-pub struct Port {
-    _signal: Signal,
-    data: Vec<LispVal>,
-}
-
-impl Port {
-    pub fn push(&mut self, data: LispVal) {
-        self.data.push(data)
-    }
-    pub fn signal(&mut self) {
-        (self._signal)(&mut self.data)
-    }
-    pub fn flush(&mut self) -> Vec<LispVal> {
-        self.data.drain(..).collect()
-    }
+struct PortsInner {
+    // Ports the embedder can look up by name, e.g. "default" for the console
+    named: HashMap<String, Port>,
+    input: RefCell<Port>,
+    output: RefCell<Port>,
+    error: RefCell<Port>,
 }
 
+/// The standard ports of an interpreter, shared by every frame of its environment.
 #[derive(Clone)]
-pub struct Ports(Rc<HashMap<String, Rc<RefCell<Port>>>>);
+pub struct Ports(Rc<PortsInner>);
 
 impl Ports {
+    /// Ports whose output goes to `default_signal`. There's no standard
+    /// input, so the current input port starts out empty.
     pub fn new(default_signal: Signal) -> Self {
-        Ports(Rc::new(HashMap::from([(
-            "default".to_string(),
-            Rc::new(RefCell::new(Port {
-                _signal: default_signal,
-                data: vec![],
-            })),
-        )])))
-    }
-    pub fn get(&self, port: &str) -> Option<Rc<RefCell<Port>>> {
-        self.0.get(port).cloned()
-    }
-    pub fn signal(&self, port: &str) {
-        if let Some(port) = self.get(port) {
-            let mut port = port.as_ref().borrow_mut();
-            port.signal()
-        }
+        let console = Port::signalling(default_signal);
+        Ports(Rc::new(PortsInner {
+            named: HashMap::from([("default".to_string(), console.clone())]),
+            input: RefCell::new(Port::input_string("")),
+            output: RefCell::new(console.clone()),
+            error: RefCell::new(console),
+        }))
+    }
+    pub fn get(&self, port: &str) -> Option<Port> {
+        self.0.named.get(port).cloned()
+    }
+    pub fn current_input(&self) -> Port {
+        self.0.input.borrow().clone()
+    }
+    pub fn current_output(&self) -> Port {
+        self.0.output.borrow().clone()
+    }
+    pub fn current_error(&self) -> Port {
+        self.0.error.borrow().clone()
+    }
+    /// Replaces the current input port, e.g. with an interactive port the
+    /// embedder feeds
+    pub fn set_input(&self, port: Port) {
+        self.0.input.replace(port);
+    }
+    /// Runs `f` with `port` as the current output port, e.g. for `with-output-to-string`
+    pub fn with_output<T>(&self, port: Port, f: impl FnOnce() -> T) -> T {
+        let previous = self.0.output.replace(port);
+        let result = f();
+        self.0.output.replace(previous);
+        result
+    }
+    /// Runs `f` with `port` as the current input port
+    pub fn with_input<T>(&self, port: Port, f: impl FnOnce() -> T) -> T {
+        let previous = self.0.input.replace(port);
+        let result = f();
+        self.0.input.replace(previous);
+        result
+    }
+}
+
+/// Counts the side effects an interpreter has had that can't be undone, e.g.
+/// a top-level definition or a `vector-set!`. A form that pauses for input is
+/// re-run from the start when the input arrives, which is only safe if it
+/// hadn't had any of these before it paused (see `Thingus::resume`).
+#[derive(Clone, Default)]
+pub struct Effects(Rc<Cell<usize>>);
+
+impl Effects {
+    pub fn record(&self) {
+        self.0.set(self.0.get() + 1)
+    }
+    pub fn count(&self) -> usize {
+        self.0.get()
     }
 }
 
@@ -58,10 +94,28 @@ impl Ports {
 pub struct Env {
     pub env: Rc<Environment>,
     pub ports: Ports,
+    pub libraries: Libraries,
+    pub sources: Sources,
+    pub effects: Effects,
     // TODO: ... input port, output port, events/callbacks for in/out
 }
 
-fn noop(_port: &mut Vec<LispVal>) {}
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Env")
+            .field("bindings", &self.env.bindings.borrow().keys())
+            .finish()
+    }
+}
+
+// Environments are compared by identity
+impl PartialEq for Env {
+    fn eq(&self, other: &Env) -> bool {
+        Rc::ptr_eq(&self.env, &other.env)
+    }
+}
+
+fn noop(_output: &mut String) {}
 
 impl Default for Env {
     fn default() -> Self {
@@ -71,6 +125,9 @@ impl Default for Env {
                 parent: None,
             }),
             ports: Ports::new(Box::new(noop)),
+            libraries: Libraries::default(),
+            sources: Sources::default(),
+            effects: Effects::default(),
         }
     }
 }
@@ -87,6 +144,24 @@ impl Env {
                 parent: None,
             }),
             ports,
+            libraries: Libraries::default(),
+            sources: Sources::default(),
+            effects: Effects::default(),
+        }
+    }
+
+    /// A new top-level environment which shares its ports, libraries, sources
+    /// and effects with `env`
+    pub fn new_sharing(env: &Env, bindings: Bindings) -> Self {
+        Env {
+            env: Rc::new(Environment {
+                bindings: RefCell::new(bindings),
+                parent: None,
+            }),
+            ports: env.ports.clone(),
+            libraries: env.libraries.clone(),
+            sources: env.sources.clone(),
+            effects: env.effects.clone(),
         }
     }
 
@@ -102,9 +177,21 @@ impl Env {
                 parent,
             }),
             ports: self.ports.clone(),
+            libraries: self.libraries.clone(),
+            sources: self.sources.clone(),
+            effects: self.effects.clone(),
         }
     }
 
+    /// The outermost frame of this environment
+    pub fn global(&self) -> Env {
+        let mut curr = self;
+        while let Some(parent) = &curr.env.parent {
+            curr = parent;
+        }
+        curr.clone()
+    }
+
     pub fn lookup_local(&self, key: &str) -> Option<LispVal> {
         self.env.bindings.borrow().get(key).cloned()
     }
@@ -133,6 +220,10 @@ impl Env {
     }
 
     pub fn bind(&self, key: &str, val: LispVal) {
+        // Frames below the top level are made afresh each time their code runs
+        if self.env.parent.is_none() {
+            self.effects.record();
+        }
         self.env.bindings.borrow_mut().insert(key.to_string(), val);
     }
 
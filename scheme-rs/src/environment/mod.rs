@@ -1,3 +1,3 @@
 mod environment;
 mod tests;
-pub use environment::{Bindings, Env, Environment, Port, Ports, Signal};
+pub use environment::{Bindings, Effects, Env, Environment, Ports};
@@ -23,6 +23,9 @@ pub enum LispError {
     UnboundVar(String, String),
     // TODO: Change this?
     GenericError(String),
+    /// Raised by the named procedure when an interactive input port runs dry,
+    /// so the embedder can pause evaluation until more input arrives
+    InputPending(String),
 }
 
 impl fmt::Display for LispError {
@@ -36,6 +39,7 @@ impl fmt::Display for LispError {
             LispError::NotFunction(message, func) => write!(f, "{}: {}", message, func),
             LispError::UnboundVar(message, varname) => write!(f, "{}: {}", message, varname),
             LispError::GenericError(message) => write!(f, "{}", message),
+            LispError::InputPending(name) => write!(f, "{}: waiting for input", name),
             LispError::NumArgs(expected, found, args) => {
                 let args_error = match args[..] {
                     [] => "".to_string(),
@@ -3,10 +3,40 @@ use std::rc::Rc;
 
 use crate::environment::Env;
 use crate::error::{LispError, LispResult};
-use crate::lisp_val::{Func, LispVal};
+use crate::library::{define_library, import};
+use crate::lisp_val::{Func, LispVal, RecordProc, RecordProcKind, RecordType};
+use crate::source::include;
 
+use super::quasiquote::quasiquote;
 use super::util::{bind_vars, define_var, ensure_atoms, get_heads, get_tails};
 
+/// The names `eval` treats as special forms rather than procedure calls,
+/// unless they are shadowed by a binding.
+pub const SYNTACTIC_KEYWORDS: &[&str] = &[
+    "quote",
+    "quasiquote",
+    "unquote",
+    "unquote-splicing",
+    "or",
+    "and",
+    "if",
+    "cond",
+    "define",
+    "include",
+    "include-ci",
+    "import",
+    "define-library",
+    "define-record-type",
+    "lambda",
+    "let",
+    "let*",
+    "letrec",
+];
+
+pub fn is_syntactic_keyword(env: &Env, name: &str) -> bool {
+    SYNTACTIC_KEYWORDS.contains(&name) && !env.is_bound(name)
+}
+
 // TODO: Could eval consume val?
 pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
     let val_clone = val.clone();
@@ -15,11 +45,15 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
         v @ LispVal::String(_) => Ok(v.clone()),
         v @ LispVal::Char(_) => Ok(v.clone()),
         v @ LispVal::Integer(_) => Ok(v.clone()),
+        v @ LispVal::BigInteger(_) => Ok(v.clone()),
         v @ LispVal::Float(_) => Ok(v.clone()),
         v @ LispVal::Rational(_) => Ok(v.clone()),
         v @ LispVal::Complex(_) => Ok(v.clone()),
+        v @ LispVal::ExactComplex(_) => Ok(v.clone()),
         v @ LispVal::Vector(_) => Ok(v.clone()),
+        v @ LispVal::Bytevector(_) => Ok(v.clone()),
         v @ LispVal::Bool(_) => Ok(v.clone()),
+        v @ LispVal::Environment(_) => Ok(v.clone()),
         LispVal::Atom(ident) => match env.lookup(ident) {
             None => Err(LispError::UnboundVar(
                 "Getting an unbound variable".to_string(),
@@ -29,22 +63,22 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
         },
         // TODO: Clone... gross :(
         LispVal::Quote(v) => Ok(LispVal::clone(v)),
-        // TODO: Unquote
-        LispVal::Unquote(v) => unquote(env, v),
-        LispVal::QuasiQuote(v) => unquote(env, v),
+        LispVal::QuasiQuote(v) => quasiquote(env, v, 1),
+        LispVal::Unquote(_) | LispVal::UnquoteSplicing(_) => Err(LispError::BadSpecialForm(
+            "unquote: not in quasiquote".to_string(),
+            val.clone(),
+        )),
         LispVal::List(xs) => match &xs[..] {
-            [LispVal::Atom(ref s), ref xs] if s == "quote" => {
-                eval(env, &LispVal::Quote(Rc::new(xs.clone())))
+            // Anything other than a syntactic keyword, including a keyword
+            // shadowed by a binding, is an ordinary procedure call
+            [function @ LispVal::Atom(ref s), args @ ..] if !is_syntactic_keyword(env, s) => {
+                eval_application(env, function, args)
             }
-            [LispVal::Atom(ref s), ref xs] if s == "quasiquote" => {
-                eval(env, &LispVal::QuasiQuote(Rc::new(xs.clone())))
-            }
-            // [LispVal::Atom(ref s), ref xs] if s == "unquote" => {
-            //     Ok(LispVal::Unquote(Rc::new(xs.clone())))
-            // }
-            // [LispVal::Atom(ref s), ref xs] if s == "unquote-splicing" => {
-            //     Ok(LispVal::UnquoteSplicing(Rc::new(xs.clone())))
-            // }
+            [LispVal::Atom(ref s), ref xs] if s == "quote" => Ok(xs.clone()),
+            [LispVal::Atom(ref s), ref xs] if s == "quasiquote" => quasiquote(env, xs, 1),
+            [LispVal::Atom(ref s), _] if s == "unquote" || s == "unquote-splicing" => Err(
+                LispError::BadSpecialForm(format!("{}: not in quasiquote", s), val.clone()),
+            ),
             [LispVal::Atom(ref s), ref xs @ ..] if s == "or" => {
                 for x in xs.iter() {
                     let result = eval(&env.clone(), x)?;
@@ -79,11 +113,6 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
                 let value = eval(&env.clone(), form)?;
                 define_var(env.clone(), var, value)
             }
-            [LispVal::Atom(ref s), val] if s == "eval" => {
-                // TODO: Is that all there is?
-                let val = eval(&env.clone(), val)?;
-                eval(&env.clone(), &val)
-            }
 
             [LispVal::Atom(ref s), LispVal::List(params), body @ ..] if s == "define" => {
                 match &params[..] {
@@ -104,6 +133,20 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
                 }
             }
 
+            [LispVal::Atom(ref s), paths @ ..] if s == "include" => include(env, paths, false),
+            [LispVal::Atom(ref s), paths @ ..] if s == "include-ci" => include(env, paths, true),
+
+            [LispVal::Atom(ref s), sets @ ..] if s == "import" => import(env, sets),
+            [LispVal::Atom(ref s), name, declarations @ ..] if s == "define-library" => {
+                define_library(env, name, declarations)
+            }
+
+            [LispVal::Atom(ref s), LispVal::Atom(type_name), constructor, LispVal::Atom(predicate), fields @ ..]
+                if s == "define-record-type" =>
+            {
+                define_record_type(env, type_name, constructor, predicate, fields)
+            }
+
             [LispVal::Atom(ref s), LispVal::List(params), body @ ..] if s == "lambda" => {
                 Ok(LispVal::Func(Func::new(
                     "λ".to_string(),
@@ -181,74 +224,7 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
                 eval_list(&env, body)
             }
 
-            [LispVal::Atom(ref s), val] if s == "write" => {
-                let val = eval(env, val)?;
-                // TODO: This is supposed to take an optional third port param
-                // TODO: Ports probably shouldn't really be anything too special... maybe a normal LispVal?
-                let port = env
-                    .ports
-                    .get("default")
-                    .ok_or_else(|| LispError::GenericError("Port not found".to_string()))?;
-                {
-                    let mut port = port.borrow_mut();
-                    port.push(val);
-                    port.signal();
-                }
-                Ok(LispVal::Void)
-            }
-
-            // TODO: Better error handling
-            [LispVal::Atom(ref s), var, n, object] if s == "vector-set!" => {
-                let vec = match var {
-                    LispVal::Atom(var) => {
-                        if let Some(LispVal::Vector(ref vec)) = env.lookup(var) {
-                            // TODO: blech
-                            vec.clone()
-                        } else {
-                            return Err(LispError::GenericError(format!(
-                                "Unbound identifier: {}",
-                                var
-                            )))
-                        }
-                    }
-                    LispVal::Vector(vec) => vec.clone(),
-                    _ => return Err(LispError::GenericError(format!("vector-set!: contract violation\nexpected: (and/c vector? (not/c immutable?))\ngiven: {}\nargument position: 1st", var)))
-                };
-
-                let n = eval(env, n)?;
-                let n = match n {
-                    LispVal::Integer(n) => {
-                        usize::try_from(n).map_err(|_| {
-                            LispError::GenericError(format!("vector-set!: index is out of range\nindex: {}\nvalid range: [0, {}]\nvector: {}", n, s.len(), var))
-                        })?
-                    }
-                    arg => return Err(LispError::GenericError(format!("vector-set!: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}\nargument position: 2nd", arg)))
-                };
-                let mut vec = (*vec).clone();
-                let _ = std::mem::replace(&mut vec[n], object.clone());
-                if let LispVal::Atom(var) = var {
-                    env.bind(var, LispVal::Vector(Rc::new(vec)));
-                }
-                Ok(LispVal::Void)
-            }
-            // eval env (List (Atom "vector-set!":args)) =
-            //   case args of
-            //     [Atom var, Integer n, v] -> do
-            //       Vector vec <- getVar env var
-            //       if n < (fromIntegral $ length vec)
-            //         then setVar env var $ Vector $ vec // [(n, v)]
-            //         else throwError $ outOfBoundsError "vector-ref" n vec
-            //     [a, b, c] ->
-            //       throwError $ TypeMismatch "vector, integer, integer" $ List [a, b, c]
-            //     a -> throwError $ NumArgs (MinMax 3 3) (length args) a
-            [function, args @ ..] => {
-                let function = eval(env, function)?;
-                let args = args
-                    .iter()
-                    .map(|arg| eval(env, arg))
-                    .collect::<Result<Vec<LispVal>, LispError>>()?;
-                apply(function, args)
-            }
+            [function, args @ ..] => eval_application(env, function, args),
 
             _ => Err(LispError::BadSpecialForm(
                 "Unrecognized special form".to_string(),
@@ -264,34 +240,88 @@ pub fn eval(env: &Env, val: &LispVal) -> LispResult<LispVal> {
     }
 }
 
-fn unquote(env: &Env, val: &LispVal) -> LispResult<LispVal> {
-    match val {
-        LispVal::Unquote(v) => {
-            let evaled = eval(env, v)?;
-            Ok(evaled)
-        }
-        LispVal::List(xs) => {
-            let xs = &xs[..];
-            let unquoted = xs
-                .iter()
-                .map(|x| unquote(env, x))
-                .collect::<LispResult<Vec<LispVal>>>()?;
-            Ok(LispVal::List(Rc::new(unquoted)))
+fn define_record_type(
+    env: &Env,
+    type_name: &str,
+    constructor: &LispVal,
+    predicate: &str,
+    fields: &[LispVal],
+) -> LispResult<LispVal> {
+    let bad_syntax = |form: &LispVal| {
+        LispError::BadSpecialForm(
+            "define-record-type: bad field specification".to_string(),
+            form.clone(),
+        )
+    };
+
+    // Each field spec is `(field accessor)` or `(field accessor modifier)`
+    let mut field_names = vec![];
+    let mut procs = vec![];
+    for (index, field) in fields.iter().enumerate() {
+        match field {
+            LispVal::List(spec) => match &spec[..] {
+                [LispVal::Atom(name), LispVal::Atom(accessor)] => {
+                    field_names.push(name.to_string());
+                    procs.push((accessor.to_string(), RecordProcKind::Accessor(index)));
+                }
+                [LispVal::Atom(name), LispVal::Atom(accessor), LispVal::Atom(modifier)] => {
+                    field_names.push(name.to_string());
+                    procs.push((accessor.to_string(), RecordProcKind::Accessor(index)));
+                    procs.push((modifier.to_string(), RecordProcKind::Modifier(index)));
+                }
+                _ => return Err(bad_syntax(field)),
+            },
+            _ => return Err(bad_syntax(field)),
         }
-        LispVal::DottedList(xs, x) => {
-            let unqouted_xs = xs
-                .iter()
-                .map(|x| unquote(env, x))
-                .collect::<LispResult<Vec<LispVal>>>()?;
-            let unquoted_x = unquote(env, x)?;
-
-            Ok(LispVal::DottedList(
-                Rc::new(unqouted_xs),
-                Rc::new(unquoted_x),
-            ))
+    }
+
+    let record_type = Rc::new(RecordType::new(type_name.to_string(), field_names));
+
+    match constructor {
+        // A bare constructor name takes every field, in order
+        LispVal::Atom(name) => {
+            let indices = (0..record_type.fields.len()).collect();
+            procs.push((name.to_string(), RecordProcKind::Constructor(indices)));
         }
-        x => Ok(x.clone()),
+        LispVal::List(spec) => match &spec[..] {
+            [LispVal::Atom(name), args @ ..] => {
+                let indices = args
+                    .iter()
+                    .map(|arg| match arg {
+                        LispVal::Atom(arg) => record_type.field_index(arg).ok_or_else(|| {
+                            LispError::BadSpecialForm(
+                                "define-record-type: constructor argument is not a field"
+                                    .to_string(),
+                                LispVal::Atom(arg.to_string()),
+                            )
+                        }),
+                        _ => Err(bad_syntax(constructor)),
+                    })
+                    .collect::<LispResult<Vec<usize>>>()?;
+                procs.push((name.to_string(), RecordProcKind::Constructor(indices)));
+            }
+            _ => return Err(bad_syntax(constructor)),
+        },
+        // `#f` means no constructor is defined
+        LispVal::Bool(false) => {}
+        _ => return Err(bad_syntax(constructor)),
+    }
+    procs.push((predicate.to_string(), RecordProcKind::Predicate));
+
+    env.bind(type_name, LispVal::RecordType(record_type.clone()));
+    for (name, kind) in procs {
+        env.bind(
+            &name,
+            LispVal::RecordProc(RecordProc::new(name.to_string(), record_type.clone(), kind)),
+        );
     }
+    Ok(LispVal::Void)
+}
+
+fn eval_application(env: &Env, function: &LispVal, args: &[LispVal]) -> LispResult<LispVal> {
+    let function = eval(env, function)?;
+    let args = eval_args(env, args)?;
+    apply(env, function, args)
 }
 
 fn eval_list(env: &Env, val: &[LispVal]) -> LispResult<LispVal> {
@@ -355,18 +385,70 @@ pub fn eval_expression_list(env: &Env, vals: Vec<LispVal>) -> LispResult<Vec<Lis
         .collect::<Result<Vec<LispVal>, LispError>>()
 }
 
-fn apply(function: LispVal, args: Vec<LispVal>) -> LispResult<LispVal> {
+/// Procedures whose effects can't be undone, besides those named with a `!`
+const SIDE_EFFECTS: &[&str] = &[
+    "random",
+    "random-integer",
+    "random-real",
+    "delete-file",
+    "create-directory",
+    "open-output-file",
+    "open-binary-output-file",
+    "call-with-output-file",
+    "with-output-to-file",
+];
+
+/// Whether applying `function` to `args` has an effect that re-running it
+/// would repeat (see `Effects`). Reading or writing any port but the current
+/// input and output ports counts, since only those two are rewound.
+fn is_side_effect(env: &Env, function: &LispVal, args: &[LispVal]) -> bool {
+    let name = match function {
+        LispVal::PrimitiveFunc(function) => &function.name,
+        LispVal::IOFunc(function) => &function.name,
+        LispVal::RecordProc(function) => {
+            return matches!(function.kind, RecordProcKind::Modifier(_))
+        }
+        // Drawing a number advances the source
+        LispVal::RandomProc(_) => return true,
+        _ => return false,
+    };
+    name.ends_with('!')
+        || SIDE_EFFECTS.contains(&name.as_str())
+        || args.iter().any(|arg| {
+            matches!(arg, LispVal::Port(port)
+                if *port != env.ports.current_input() && *port != env.ports.current_output())
+        })
+}
+
+/// Applies a procedure to already evaluated arguments. `env` is the caller's
+/// environment, which is only visible to `IOFunc`s.
+pub fn apply(env: &Env, function: LispVal, args: Vec<LispVal>) -> LispResult<LispVal> {
+    if is_side_effect(env, &function, &args) {
+        env.effects.record();
+    }
     match function {
         LispVal::PrimitiveFunc(function) => {
             function.apply(args)
         }
+        LispVal::IOFunc(function) => {
+            function.apply(env, args)
+        }
+        LispVal::RecordProc(function) => {
+            function.apply(args)
+        }
+        LispVal::RandomProc(function) => {
+            function.apply(args)
+        }
         LispVal::Func(function) => {
             // TODO: Check arg lengths...
-            // TODO: check varargs?
             let mut bindings = HashMap::new();
-            for (param, value) in function.params.iter().zip(args) {
+            let mut args = args.into_iter();
+            for (param, value) in function.params.iter().zip(args.by_ref()) {
                 bindings.insert(param.to_owned(), value);
             }
+            if let Some(varargs) = &function.varargs {
+                bindings.insert(varargs.to_owned(), LispVal::List(Rc::new(args.collect())));
+            }
             let env = bind_vars(&function.closure, bindings);
 
             let body = function.body
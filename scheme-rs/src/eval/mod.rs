@@ -1,6 +1,7 @@
 mod eval;
+mod quasiquote;
 #[cfg(test)]
 mod tests;
 mod util;
 
-pub use eval::{eval, eval_expression_list};
+pub use eval::{apply, eval, eval_expression_list, is_syntactic_keyword, SYNTACTIC_KEYWORDS};
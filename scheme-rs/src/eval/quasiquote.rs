@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Env;
+use crate::error::{LispError, LispResult};
+use crate::lisp_val::LispVal;
+
+use super::eval::eval;
+
+// The quasiquote forms, in either their abbreviated or long form
+enum Quasi<'a> {
+    // `quasiquote`, nested inside another
+    Nested(&'a LispVal),
+    // `unquote`
+    Unquote(&'a LispVal),
+    // `unquote-splicing`
+    Splice(&'a LispVal),
+}
+
+fn quasi_form(val: &LispVal) -> Option<Quasi<'_>> {
+    match val {
+        LispVal::QuasiQuote(x) => Some(Quasi::Nested(x)),
+        LispVal::Unquote(x) => Some(Quasi::Unquote(x)),
+        LispVal::UnquoteSplicing(x) => Some(Quasi::Splice(x)),
+        LispVal::List(xs) => match &xs[..] {
+            [LispVal::Atom(s), x] if s == "quasiquote" => Some(Quasi::Nested(x)),
+            [LispVal::Atom(s), x] if s == "unquote" => Some(Quasi::Unquote(x)),
+            [LispVal::Atom(s), x] if s == "unquote-splicing" => Some(Quasi::Splice(x)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Rebuilds a nested quasiquote form in the same representation it was written in
+fn rebuild(original: &LispVal, x: LispVal) -> LispVal {
+    match original {
+        LispVal::QuasiQuote(_) => LispVal::QuasiQuote(Rc::new(x)),
+        LispVal::Unquote(_) => LispVal::Unquote(Rc::new(x)),
+        LispVal::UnquoteSplicing(_) => LispVal::UnquoteSplicing(Rc::new(x)),
+        LispVal::List(xs) => LispVal::List(Rc::new(vec![xs[0].clone(), x])),
+        _ => unreachable!(),
+    }
+}
+
+/// Evaluates the body of a quasiquote. `depth` is the quasiquote nesting
+/// level, and only unquotes at depth 1 are evaluated.
+pub fn quasiquote(env: &Env, val: &LispVal, depth: usize) -> LispResult<LispVal> {
+    if let Some(form) = quasi_form(val) {
+        return match form {
+            Quasi::Nested(x) => Ok(rebuild(val, quasiquote(env, x, depth + 1)?)),
+            Quasi::Unquote(x) if depth == 1 => eval(env, x),
+            Quasi::Unquote(x) => Ok(rebuild(val, quasiquote(env, x, depth - 1)?)),
+            Quasi::Splice(_) if depth == 1 => Err(LispError::BadSpecialForm(
+                "unquote-splicing: invalid context within quasiquote".to_string(),
+                val.clone(),
+            )),
+            Quasi::Splice(x) => Ok(rebuild(val, quasiquote(env, x, depth - 1)?)),
+        };
+    }
+
+    match val {
+        LispVal::List(xs) => {
+            // `(a unquote x)` is the long form of `(a . ,x)`
+            if let [init @ .., LispVal::Atom(s), _] = &xs[..] {
+                if !init.is_empty() && s == "unquote" {
+                    let unquote = LispVal::List(Rc::new(xs[xs.len() - 2..].to_vec()));
+                    return quasi_list(env, init, Some(&unquote), depth);
+                }
+            }
+            quasi_list(env, xs, None, depth)
+        }
+        LispVal::DottedList(xs, x) => quasi_list(env, xs, Some(x), depth),
+        LispVal::Vector(xs) => match quasi_list(env, &xs.borrow(), None, depth)? {
+            LispVal::List(xs) => Ok(LispVal::Vector(Rc::new(RefCell::new(xs.to_vec())))),
+            val => Err(LispError::BadSpecialForm(
+                "unquote-splicing: invalid context within quasiquote".to_string(),
+                val,
+            )),
+        },
+        x => Ok(x.clone()),
+    }
+}
+
+fn quasi_list(
+    env: &Env,
+    xs: &[LispVal],
+    tail: Option<&LispVal>,
+    depth: usize,
+) -> LispResult<LispVal> {
+    let mut result = vec![];
+    let mut result_tail = None;
+    for (i, x) in xs.iter().enumerate() {
+        match quasi_form(x) {
+            Some(Quasi::Splice(spliced)) if depth == 1 => {
+                match eval(env, spliced)? {
+                    LispVal::List(ys) => result.extend(ys.iter().cloned()),
+                    // An improper list may only be spliced in at the very end
+                    LispVal::DottedList(ys, y) if i == xs.len() - 1 && tail.is_none() => {
+                        result.extend(ys.iter().cloned());
+                        result_tail = Some((*y).clone());
+                    }
+                    val => {
+                        return Err(LispError::TypeMismatch(
+                            "unquote-splicing: list".to_string(),
+                            val,
+                        ))
+                    }
+                }
+            }
+            _ => result.push(quasiquote(env, x, depth)?),
+        }
+    }
+    if let Some(tail) = tail {
+        result_tail = Some(quasiquote(env, tail, depth)?);
+    }
+
+    Ok(match result_tail {
+        None => LispVal::List(Rc::new(result)),
+        Some(LispVal::List(ys)) => {
+            result.extend(ys.iter().cloned());
+            LispVal::List(Rc::new(result))
+        }
+        Some(LispVal::DottedList(ys, y)) => {
+            result.extend(ys.iter().cloned());
+            LispVal::DottedList(Rc::new(result), y)
+        }
+        Some(tail) if result.is_empty() => tail,
+        Some(tail) => LispVal::DottedList(Rc::new(result), Rc::new(tail)),
+    })
+}
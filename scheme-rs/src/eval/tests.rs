@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::*;
@@ -52,15 +53,15 @@ fn eval_vector() {
     assert_eq!(
         eval(
             &env,
-            &LispVal::Vector(Rc::new(vec![
+            &LispVal::Vector(Rc::new(RefCell::new(vec![
                 LispVal::Integer(1729),
                 LispVal::String("foo".to_string())
-            ]))
+            ])))
         ),
-        Ok(LispVal::Vector(Rc::new(vec![
+        Ok(LispVal::Vector(Rc::new(RefCell::new(vec![
             LispVal::Integer(1729),
             LispVal::String("foo".to_string())
-        ])))
+        ]))))
     )
 }
 
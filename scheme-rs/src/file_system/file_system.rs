@@ -0,0 +1,282 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+
+/// The files an interpreter can see, used by `load`, `include` and the
+/// procedures of `(scheme file)`. Paths passed to everything but `resolve`
+/// have already been resolved.
+pub trait FileSystem {
+    /// Resolves `path` to a canonical name. Relative paths are resolved
+    /// against the directory of `including`, the file doing the including
+    /// (if there is one).
+    fn resolve(&self, path: &str, including: Option<&str>) -> String;
+    fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+    /// Creates or truncates the file at `path`, then writes `contents` to it
+    fn write(&self, path: &str, contents: &[u8]) -> io::Result<()>;
+    /// Adds `contents` to the end of an existing file
+    fn append(&self, path: &str, contents: &[u8]) -> io::Result<()>;
+    fn exists(&self, path: &str) -> bool;
+    fn delete(&self, path: &str) -> io::Result<()>;
+    /// The names of the entries in a directory, sorted
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>>;
+    fn create_dir(&self, path: &str) -> io::Result<()>;
+    fn size(&self, path: &str) -> io::Result<u64>;
+}
+
+/// Joins `path` onto the directory containing `including` and normalises
+/// any `.` and `..` segments, using `/` as the separator.
+pub fn join_path(path: &str, including: Option<&str>) -> String {
+    let mut segments: Vec<&str> = vec![];
+    let absolute = path.starts_with('/');
+    if !absolute {
+        if let Some(including) = including {
+            segments.extend(including.split('/'));
+            // Drop the file name, keeping only its directory
+            segments.pop();
+        }
+    }
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if matches!(segments.last(), None | Some(&"..") | Some(&"")) {
+                    segments.push("..");
+                } else {
+                    segments.pop();
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let joined = segments.join("/");
+    if absolute && !joined.starts_with('/') {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no such file: {}", path))
+}
+
+// The directory part of a normalised path, "" for the top level
+fn parent(path: &str) -> &str {
+    path.rfind('/').map_or("", |i| &path[..i])
+}
+
+/// A file system held entirely in memory, for the wasm build, for tests,
+/// and for embedders who don't want scripts touching the disk. Directories
+/// exist implicitly once they contain a file.
+#[derive(Default)]
+pub struct MemoryFileSystem {
+    files: RefCell<HashMap<String, Vec<u8>>>,
+    dirs: RefCell<HashSet<String>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a file, e.g. to seed the file system before running a script
+    pub fn insert(&self, path: &str, contents: &str) {
+        self.files
+            .borrow_mut()
+            .insert(join_path(path, None), contents.as_bytes().to_vec());
+    }
+
+    /// The contents of a file, if it exists and is text
+    pub fn get(&self, path: &str) -> Option<String> {
+        let contents = self.files.borrow().get(&join_path(path, None)).cloned()?;
+        String::from_utf8(contents).ok()
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        let prefix = format!("{}/", path);
+        // The top level always exists
+        path.is_empty()
+            || self.dirs.borrow().contains(path)
+            || self
+                .files
+                .borrow()
+                .keys()
+                .any(|file| file.starts_with(&prefix))
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn resolve(&self, path: &str, including: Option<&str>) -> String {
+        join_path(path, including)
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        self.files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &str, contents: &[u8]) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .insert(path.to_string(), contents.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, path: &str, contents: &[u8]) -> io::Result<()> {
+        match self.files.borrow_mut().get_mut(path) {
+            Some(file) => {
+                file.extend_from_slice(contents);
+                Ok(())
+            }
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.borrow().contains_key(path) || self.is_dir(path)
+    }
+
+    fn delete(&self, path: &str) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        if !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        let files = self.files.borrow();
+        let dirs = self.dirs.borrow();
+        let mut names = BTreeSet::new();
+        for entry in files.keys().chain(dirs.iter()) {
+            // The entry itself, or the subdirectory it's in
+            let mut child = entry.as_str();
+            while !child.is_empty() && parent(child) != path {
+                child = parent(child);
+            }
+            if !child.is_empty() {
+                names.insert(child[child.rfind('/').map_or(0, |i| i + 1)..].to_string());
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn create_dir(&self, path: &str) -> io::Result<()> {
+        if self.exists(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("already exists: {}", path),
+            ));
+        }
+        self.dirs.borrow_mut().insert(path.to_string());
+        Ok(())
+    }
+
+    fn size(&self, path: &str) -> io::Result<u64> {
+        Ok(self.read(path)?.len() as u64)
+    }
+}
+
+/// The native file system. Top-level relative paths are resolved against
+/// `root`. A jailed file system treats `root` as `/` and refuses any path
+/// that would leave it.
+#[cfg(feature = "native")]
+pub struct OsFileSystem {
+    root: std::path::PathBuf,
+    jailed: bool,
+}
+
+#[cfg(feature = "native")]
+impl OsFileSystem {
+    pub fn new(root: std::path::PathBuf) -> Self {
+        Self {
+            root,
+            jailed: false,
+        }
+    }
+
+    /// A file system that can only see `root`. Paths are checked lexically,
+    /// so symlinks inside `root` can still point outside it.
+    pub fn jailed(root: std::path::PathBuf) -> Self {
+        Self { root, jailed: true }
+    }
+
+    fn real_path(&self, path: &str) -> io::Result<std::path::PathBuf> {
+        if !self.jailed {
+            return Ok(std::path::PathBuf::from(path));
+        }
+        if path.split('/').any(|segment| segment == "..") {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("outside of the file system root: {}", path),
+            ));
+        }
+        Ok(self.root.join(path.trim_start_matches('/')))
+    }
+}
+
+#[cfg(feature = "native")]
+impl FileSystem for OsFileSystem {
+    fn resolve(&self, path: &str, including: Option<&str>) -> String {
+        if self.jailed {
+            return join_path(path, Some(including.unwrap_or("/")));
+        }
+        let path = std::path::Path::new(path);
+        let resolved = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            match including.and_then(|including| std::path::Path::new(including).parent()) {
+                Some(dir) => dir.join(path),
+                None => self.root.join(path),
+            }
+        };
+        resolved.to_string_lossy().to_string()
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(self.real_path(path)?)
+    }
+
+    fn write(&self, path: &str, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(self.real_path(path)?, contents)
+    }
+
+    fn append(&self, path: &str, contents: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(self.real_path(path)?)?
+            .write_all(contents)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.real_path(path)
+            .is_ok_and(|path| std::fs::metadata(path).is_ok())
+    }
+
+    fn delete(&self, path: &str) -> io::Result<()> {
+        std::fs::remove_file(self.real_path(path)?)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let mut names = std::fs::read_dir(self.real_path(path)?)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<String>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    fn create_dir(&self, path: &str) -> io::Result<()> {
+        std::fs::create_dir(self.real_path(path)?)
+    }
+
+    fn size(&self, path: &str) -> io::Result<u64> {
+        Ok(std::fs::metadata(self.real_path(path)?)?.len())
+    }
+}
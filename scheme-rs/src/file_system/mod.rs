@@ -0,0 +1,7 @@
+mod file_system;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "native")]
+pub use file_system::OsFileSystem;
+pub use file_system::{join_path, FileSystem, MemoryFileSystem};
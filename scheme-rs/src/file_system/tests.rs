@@ -0,0 +1,75 @@
+use std::io::ErrorKind;
+
+use super::*;
+
+#[test]
+fn memory_files() {
+    let files = MemoryFileSystem::new();
+    files.insert("./lib/a.scm", "(a)");
+    assert_eq!(files.read("lib/a.scm").unwrap(), b"(a)");
+    assert_eq!(files.get("lib/a.scm"), Some("(a)".to_string()));
+    assert_eq!(files.size("lib/a.scm").unwrap(), 3);
+    assert!(files.exists("lib/a.scm"));
+    assert!(files.exists("lib"));
+    assert!(!files.exists("lib/b.scm"));
+
+    files.write("out.txt", b"one").unwrap();
+    files.append("out.txt", b" two").unwrap();
+    assert_eq!(files.get("out.txt"), Some("one two".to_string()));
+    files.write("out.txt", b"three").unwrap();
+    assert_eq!(files.get("out.txt"), Some("three".to_string()));
+    assert_eq!(
+        files.append("missing.txt", b"").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    files.delete("out.txt").unwrap();
+    assert!(!files.exists("out.txt"));
+    assert_eq!(
+        files.delete("out.txt").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn memory_directories() {
+    let files = MemoryFileSystem::new();
+    files.insert("lib/a.scm", "");
+    files.insert("lib/nested/b.scm", "");
+    files.insert("top.scm", "");
+    files.create_dir("empty").unwrap();
+    assert_eq!(files.read_dir("").unwrap(), ["empty", "lib", "top.scm"]);
+    assert_eq!(files.read_dir("lib").unwrap(), ["a.scm", "nested"]);
+    assert_eq!(files.read_dir("empty").unwrap(), Vec::<String>::new());
+    assert_eq!(
+        files.read_dir("missing").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert_eq!(
+        files.create_dir("lib").unwrap_err().kind(),
+        ErrorKind::AlreadyExists
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn jailed_files() {
+    let root = std::env::temp_dir().join(format!("scheme-rs-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir(&root).unwrap();
+    let files = OsFileSystem::jailed(root.clone());
+
+    let path = files.resolve("a.txt", None);
+    assert_eq!(path, "/a.txt");
+    files.write(&path, b"jailed").unwrap();
+    assert_eq!(std::fs::read(root.join("a.txt")).unwrap(), b"jailed");
+    assert_eq!(files.resolve("b.txt", Some("/lib/a.scm")), "/lib/b.txt");
+
+    let escape = files.resolve("../../etc/passwd", Some("/lib/a.scm"));
+    assert_eq!(
+        files.read(&escape).unwrap_err().kind(),
+        ErrorKind::PermissionDenied
+    );
+    assert!(!files.exists(&escape));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -1,5 +1,10 @@
-use environment::Signal;
-use error::LispResult;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use error::{LispError, LispResult};
+use file_system::FileSystem;
+use library::LibraryLoader;
+use port::Signal;
 
 use crate::{
     environment::{Env, Ports},
@@ -10,14 +15,30 @@ use crate::{
 pub mod environment;
 pub mod error;
 pub mod eval;
+pub mod file_system;
+pub mod library;
 pub mod lisp_val;
 pub mod numbers;
 pub mod parser;
+pub mod port;
 pub mod primitive_functions;
+pub mod printer;
+pub mod random;
+pub mod source;
+
+/// Where evaluation stopped to wait for input (see `LispError::InputPending`)
+struct Paused {
+    // The form that was waiting, followed by the forms after it
+    exprs: Vec<LispVal>,
+    // What the waiting form had written, which isn't written again when it's re-run
+    written: usize,
+}
 
 pub struct Thingus {
     env: Env,
     pub ports: Ports,
+    paused: RefCell<Option<Paused>>,
+    pretty_width: Cell<Option<usize>>,
 }
 
 impl Thingus {
@@ -25,34 +46,145 @@ impl Thingus {
         let primitive_bindings = primitive_functions();
         let ports = Ports::new(signal);
         let env = Env::with_bindings(primitive_bindings, ports.clone());
-        Thingus { env, ports }
+        Thingus {
+            env,
+            ports,
+            paused: RefCell::new(None),
+            pretty_width: Cell::new(None),
+        }
     }
+    /// Sets where `import` looks for libraries that aren't built in or already defined
+    pub fn set_library_loader(&self, loader: Rc<dyn LibraryLoader>) {
+        self.env.libraries.set_loader(loader)
+    }
+    /// Sets the files that `load`, `include` and the `(scheme file)`
+    /// procedures can see. By default this is an empty in-memory file system.
+    pub fn set_file_system(&self, file_system: Rc<dyn FileSystem>) {
+        self.env.sources.set_file_system(file_system)
+    }
+    /// Lays out each result over as many lines as it takes to fit in `width`
+    /// columns (see `printer::pretty_print`), or with `None`, the default,
+    /// writes each on one line.
+    pub fn set_pretty_width(&self, width: Option<usize>) {
+        self.pretty_width.set(width)
+    }
+    /// Evaluates `input`, returning the value of each expression. If an
+    /// interactive input port runs dry, evaluation stops there until `resume`
+    /// is called (see `is_waiting`).
     pub fn eval(&self, input: &str) -> String {
-        let parsed = parser::expression_list(input);
+        let parsed = parser::datum_list(input);
         match parsed {
-            // TODO: Consume all input
-            Ok((_input, exprs)) => match eval::eval_expression_list(&self.env, exprs) {
-                Ok(result) => result
-                    .iter()
-                    .filter(|&val| *val != LispVal::Void)
-                    .map(|val| format!("{}", val))
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-                Err(err) => format!("{}", err),
-            },
+            Ok((rest, exprs)) if rest.trim().is_empty() => {
+                self.paused.replace(None);
+                self.run(exprs)
+            }
+            // Nothing is run if any of the input can't be read
+            Ok((rest, _)) => format!(
+                "{}",
+                LispError::Parser(rest.lines().next().unwrap_or_default().to_string())
+            ),
             // TODO
             Err(err) => format!("{}", err),
         }
     }
+    /// Whether evaluation is paused, waiting for more input
+    pub fn is_waiting(&self) -> bool {
+        self.paused.borrow().is_some()
+    }
+    /// Carries on a paused evaluation, e.g. once more input has been pushed,
+    /// returning the values of the remaining expressions.
+    ///
+    /// There's no way to suspend the evaluator part way through a form, so
+    /// the form that was waiting is run again from the start. It reads the
+    /// same input again, and the output it had already written is dropped.
+    /// A form only pauses if it had no other side effects before it needed
+    /// input (see `Effects`), so nothing else happens twice.
+    pub fn resume(&self) -> String {
+        match self.paused.take() {
+            None => "".to_string(),
+            Some(paused) => {
+                self.ports.current_output().skip_output(paused.written);
+                self.run(paused.exprs)
+            }
+        }
+    }
+    fn run(&self, exprs: Vec<LispVal>) -> String {
+        let mut results = vec![];
+        for (i, expr) in exprs.iter().enumerate() {
+            let input = self.ports.current_input();
+            let output = self.ports.current_output();
+            input.checkpoint();
+            let written = output.written();
+            let effects = self.env.effects.count();
+            match eval::eval(&self.env, expr) {
+                Ok(val) => results.push(val),
+                Err(LispError::InputPending(name)) => {
+                    input.rewind();
+                    if self.env.effects.count() != effects {
+                        return self.format_results(Err(LispError::GenericError(format!(
+                            "{}: can't wait for input after side effects in the same top-level form",
+                            name
+                        ))));
+                    }
+                    self.paused.replace(Some(Paused {
+                        exprs: exprs[i..].to_vec(),
+                        written: output.written() - written,
+                    }));
+                    break;
+                }
+                Err(err) => return self.format_results(Err(err)),
+            }
+        }
+        self.format_results(Ok(results))
+    }
+    /// Evaluates the file at `path`, found through the file system, so
+    /// that anything it includes is resolved relative to it.
+    pub fn eval_file(&self, path: &str) -> String {
+        let results = self
+            .env
+            .sources
+            .read(path)
+            .and_then(|(resolved, contents)| {
+                let exprs = match parser::datum_list(&contents) {
+                    Ok((rest, exprs)) if rest.trim().is_empty() => exprs,
+                    Ok((rest, _)) => {
+                        return Err(LispError::Parser(format!(
+                            "{}: {}",
+                            resolved,
+                            rest.lines().next().unwrap_or_default()
+                        )))
+                    }
+                    Err(err) => return Err(LispError::Parser(format!("{}", err))),
+                };
+                self.env
+                    .sources
+                    .with_current(resolved, || eval::eval_expression_list(&self.env, exprs))
+            });
+        self.format_results(results)
+    }
+    fn format_results(&self, results: LispResult<Vec<LispVal>>) -> String {
+        match results {
+            Ok(result) => result
+                .iter()
+                .filter(|&val| *val != LispVal::Void)
+                .map(|val| match self.pretty_width.get() {
+                    Some(width) => printer::pretty_print(val, width),
+                    None => format!("{}", val),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Err(err) => format!("{}", err),
+        }
+    }
     pub fn eval_blah(&self, input: &str) -> LispResult<Vec<LispVal>> {
         // TODO: ??
-        let (_, parsed) = parser::expression_list(input).unwrap();
+        let (_, parsed) = parser::datum_list(input).unwrap();
         eval::eval_expression_list(&self.env, parsed)
     }
 }
 
 pub fn eval(input: &str, ports: Ports) -> String {
-    let parsed = parser::expression_list(input);
+    let parsed = parser::datum_list(input);
     match parsed {
         // TODO: Consume all input
         Ok((_input, exprs)) => {
@@ -77,11 +209,11 @@ pub fn eval(input: &str, ports: Ports) -> String {
 #[cfg(test)]
 mod tests {
 
-    use std::{cell::RefCell, fs::DirEntry, ops::Deref, rc::Rc};
+    use std::{cell::RefCell, fs::DirEntry, rc::Rc};
 
     use super::*;
 
-    fn noop(_port: &mut Vec<LispVal>) {}
+    fn noop(_output: &mut String) {}
 
     #[test]
     fn stuff_and_junk() {
@@ -126,6 +258,56 @@ mod tests {
 
         // Multi-level quasiquoting
         assert_eq!(t.eval("(define baz 1) ``(,baz . 2)"), "`(,baz . 2)");
+        assert_eq!(t.eval("`(1 `(2 ,(3 ,(+ 1 3))))"), "(1 `(2 ,(3 4)))");
+
+        // Splicing
+        assert_eq!(t.eval("(define xs '(2 3))"), "");
+        assert_eq!(t.eval("`(1 ,@xs 4)"), "(1 2 3 4)");
+        assert_eq!(t.eval("`(1 `(2 ,(3 ,@xs)))"), "(1 `(2 ,(3 2 3)))");
+        assert_eq!(t.eval("`(1 ,@xs)"), "(1 2 3)");
+        assert_eq!(t.eval("`(,@xs . 4)"), "(2 3 . 4)");
+        assert_eq!(t.eval("`(1 ,@'(2 . 3))"), "(1 2 . 3)");
+        assert_eq!(t.eval("`(,@'() . 4)"), "4");
+        assert_eq!(
+            t.eval("`(1 ,@2)"),
+            "Invalid type: expected unquote-splicing: list, found 2"
+        );
+
+        // Vectors
+        assert_eq!(t.eval("`#(1 ,(+ 1 1) ,@xs)"), "#(1 2 2 3)");
+
+        // Long forms
+        assert_eq!(
+            t.eval("(quasiquote (1 (unquote (+ 1 1)) (unquote-splicing xs)))"),
+            "(1 2 2 3)"
+        );
+        assert_eq!(t.eval("`(1 unquote xs)"), "(1 2 3)");
+        assert_eq!(t.eval("(unquote xs)"), "unquote: not in quasiquote: ,xs");
+        assert_eq!(t.eval(",xs"), "unquote: not in quasiquote: ,xs");
+    }
+
+    #[test]
+    fn canonical_quotes() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(t.eval("(car ''a)"), "quote");
+        assert_eq!(t.eval("(cadr ''a)"), "a");
+        assert_eq!(t.eval("(equal? ''x (list 'quote 'x))"), "#t");
+        assert_eq!(t.eval("(car '`(a ,b ,@c))"), "quasiquote");
+        assert_eq!(t.eval("(cadr '`(a ,b ,@c))"), "(a ,b ,@c)");
+        assert_eq!(t.eval("(list 'quote 'x)"), "'x");
+        assert_eq!(t.eval("''(1 . 2)"), "'(1 . 2)");
+        // Only two-element lists are abbreviated
+        assert_eq!(t.eval("'(quote a b)"), "(quote a b)");
+        assert_eq!(
+            parser::datum(",@xs"),
+            Ok((
+                "",
+                LispVal::List(Rc::new(vec![
+                    LispVal::Atom("unquote-splicing".to_string()),
+                    LispVal::Atom("xs".to_string()),
+                ]))
+            ))
+        );
     }
 
     #[test]
@@ -149,7 +331,7 @@ mod tests {
 
         // eq prim funcs
         let t = Thingus::new(Box::new(noop));
-        let thingy = concat!("(define foo eq?)", "(and (eq? eq? eq?) (eq? eq? foo)))",);
+        let thingy = concat!("(define foo eq?)", "(and (eq? eq? eq?) (eq? eq? foo))",);
 
         assert_eq!(t.eval(thingy), "#t");
 
@@ -160,6 +342,227 @@ mod tests {
         assert_eq!(t.eval(thingy), "#t");
     }
 
+    #[test]
+    fn records() {
+        let t = Thingus::new(Box::new(noop));
+        let input = concat!(
+            "(define-record-type <point>",
+            "    (make-point x y)",
+            "    point?",
+            "    (x point-x set-point-x!)",
+            "    (y point-y))",
+            "(define p (make-point 1 2))",
+            "(point? p)",
+            "(point? '(1 2))",
+            "(point-x p)",
+            "(set-point-x! p 10)",
+            "(point-x p)",
+            "p",
+            "(eq? p p)",
+            "(eq? p (make-point 10 2))",
+            "(record-type-name (record-type-descriptor p))",
+            "(record-type-field-names <point>)",
+        );
+        assert_eq!(
+            t.eval(input),
+            "#t\n#f\n1\n10\n#<record point 10 2>\n#t\n#f\n<point>\n(x y)"
+        );
+        assert_eq!(
+            t.eval("(point-y '(1 2))"),
+            "point-y: contract violation\nexpected: point?\ngiven: (1 2)"
+        );
+
+        // The reflection API is usable from Rust as well
+        match t.eval_blah("p").unwrap().last() {
+            Some(LispVal::Record(record)) => {
+                assert_eq!(record.record_type_descriptor().name(), "<point>");
+                assert_eq!(record.record_type_descriptor().field_names(), ["x", "y"]);
+                assert_eq!(record.field("y"), Some(LispVal::Integer(2)));
+            }
+            val => panic!("expected a record, got {:?}", val),
+        }
+    }
+
+    #[test]
+    fn libraries() {
+        let t = Thingus::new(Box::new(noop));
+        let input = concat!(
+            "(define-library (shapes square)",
+            "    (export area (rename perimeter square-perimeter))",
+            "    (import (scheme base))",
+            "    (begin",
+            "        (define (side-times s n) (* s n))",
+            "        (define (area s) (side-times s s))",
+            "        (define (perimeter s) (side-times s 4))))",
+            "(import (prefix (shapes square) sq:))",
+            "(sq:area 3)",
+            "(sq:square-perimeter 3)",
+            "(import (rename (only (scheme base) car cdr) (car first)))",
+            "(first '(1 2))",
+        );
+        assert_eq!(t.eval(input), "9\n12\n1");
+        assert_eq!(
+            t.eval("(import (except (shapes square) area)) (area 2)"),
+            "Getting an unbound variable: area"
+        );
+        assert_eq!(
+            t.eval("(import (only (shapes square) side-times))"),
+            "import: identifier side-times is not exported: (only (shapes square) side-times)"
+        );
+        assert_eq!(t.eval("(import (nope))"), "import: unknown library (nope)");
+
+        // Library bodies only see what they import
+        assert_eq!(
+            t.eval("(define-library (bad) (export f) (begin (define (f) (car '(1))))) (import (bad)) (f)"),
+            "Getting an unbound variable: car"
+        );
+
+        struct Loader;
+        impl library::LibraryLoader for Loader {
+            fn load(&self, name: &[String]) -> Option<String> {
+                (name == ["util", "math"]).then(|| "(define-library (util math) (export double) (import (scheme base)) (begin (define (double n) (+ n n))))".to_string())
+            }
+        }
+        t.set_library_loader(Rc::new(Loader));
+        assert_eq!(t.eval("(import (util math)) (double 21)"), "42");
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn load_outside_a_jail() {
+        let t = Thingus::new(Box::new(noop));
+        let root = std::env::temp_dir().join(format!("scheme-rs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&root).unwrap();
+        t.set_file_system(Rc::new(file_system::OsFileSystem::jailed(root.clone())));
+        assert_eq!(
+            t.eval("(load \"../../etc/passwd\")"),
+            "cannot open file: /../../etc/passwd\nsystem error: outside of the file system root: /../../etc/passwd"
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn load_and_include() {
+        let t = Thingus::new(Box::new(noop));
+        let files = Rc::new(file_system::MemoryFileSystem::new());
+        files.insert(
+            "lib/math.scm",
+            "(define (double n) (* n 2)) (include \"helpers.scm\")",
+        );
+        files.insert("lib/helpers.scm", "(define (triple n) (* n 3))");
+        files.insert("shouty.scm", "(DEFINE (Quadruple N) (* N 4)) (Quadruple 2)");
+        files.insert("body.scm", "(square 4)");
+        files.insert(
+            "lib/shapes.sld",
+            "(define-library (shapes) (export square) (import (scheme base)) (include \"square.scm\"))",
+        );
+        files.insert("lib/square.scm", "(define (square n) (* n n))");
+        t.set_file_system(files.clone());
+
+        assert_eq!(
+            t.eval("(load \"lib/math.scm\") (double 2) (triple 2)"),
+            "4\n6"
+        );
+        assert_eq!(t.eval("(include-ci \"shouty.scm\")"), "8");
+        assert_eq!(t.eval("(quadruple 3)"), "12");
+        assert_eq!(
+            t.eval("(load \"lib/missing.scm\")"),
+            "cannot open file: lib/missing.scm\nsystem error: no such file: lib/missing.scm"
+        );
+        assert_eq!(
+            t.eval("(include \"lib/shapes.sld\") (import (shapes)) (include \"body.scm\")"),
+            "16"
+        );
+
+        // A library that isn't defined yet is read from its conventional
+        // path, and can itself be loaded as a file
+        files.insert(
+            "geometry/circle.sld",
+            "(define-library (geometry circle) (export area) (import (scheme base)) (include \"pi.scm\") (begin (define (area r) (* pi r r))))",
+        );
+        files.insert("geometry/pi.scm", "(define pi 3)");
+        assert_eq!(t.eval("(import (geometry circle)) (area 2)"), "12");
+        assert_eq!(
+            t.eval(
+                "(load \"geometry/circle.sld\") (import (prefix (geometry circle) c:)) (c:area 1)"
+            ),
+            "3"
+        );
+
+        // Libraries that import each other can't be loaded
+        files.insert(
+            "even.sld",
+            "(define-library (even) (export even?) (import (scheme base) (odd)))",
+        );
+        files.insert(
+            "odd.sld",
+            "(define-library (odd) (export odd?) (import (scheme base) (even)))",
+        );
+        assert_eq!(
+            t.eval("(import (even))"),
+            "import: cycle in library imports: (even) -> (odd) -> (even)"
+        );
+        files.insert("self.sld", "(define-library (self) (import (self)))");
+        assert_eq!(
+            t.eval("(import (self))"),
+            "import: cycle in library imports: (self) -> (self)"
+        );
+        // Once the cycle is broken the libraries load
+        files.insert(
+            "odd.sld",
+            "(define-library (odd) (export one) (import (scheme base)) (begin (define one 1)))",
+        );
+        assert_eq!(t.eval("(import (odd)) one"), "1");
+    }
+
+    #[test]
+    fn join_paths() {
+        use file_system::join_path;
+        assert_eq!(join_path("b.scm", Some("lib/a.scm")), "lib/b.scm");
+        assert_eq!(join_path("../b.scm", Some("lib/a.scm")), "b.scm");
+        assert_eq!(join_path("./c/b.scm", None), "c/b.scm");
+        assert_eq!(join_path("/b.scm", Some("lib/a.scm")), "/b.scm");
+        assert_eq!(join_path("../../b.scm", Some("a.scm")), "../../b.scm");
+    }
+
+    #[test]
+    fn first_class_environments() {
+        let t = Thingus::new(Box::new(noop));
+        let input = concat!(
+            "(define x 10)",
+            "(eval '(+ x 1))",
+            "(eval '(+ x 1) (interaction-environment))",
+            "(define (f x) (eval 'x (interaction-environment)))",
+            "(f 2)",
+            "(define sandbox (scheme-report-environment 5))",
+            "(eval '(define x 1) sandbox)",
+            "(eval 'x sandbox)",
+            "x",
+            "(map-eval (list '(* 2 3) '(car '(a b))))",
+        );
+        t.eval("(define (map-eval exprs) (if (null? exprs) '() (cons (eval (car exprs)) (map-eval (cdr exprs)))))");
+        assert_eq!(t.eval(input), "11\n11\n10\n1\n10\n(6 a)");
+
+        assert_eq!(
+            t.eval("(define e eval) (e '(if #t 1 2) (null-environment 5))"),
+            "1"
+        );
+        assert_eq!(
+            t.eval("(eval '(car '(1)) (null-environment 5))"),
+            "Getting an unbound variable: car"
+        );
+        assert_eq!(
+            t.eval("(define env (environment '(only (scheme base) +))) (eval '(+ 1 2) env)"),
+            "3"
+        );
+        assert_eq!(
+            t.eval("(eval '(* 1 2) env)"),
+            "Getting an unbound variable: *"
+        );
+        assert_eq!(t.eval("(environment? env)"), "#t");
+        assert_eq!(t.eval("(procedure? eval)"), "#t");
+    }
+
     fn run_tests_in_file(test_path: &DirEntry) -> Result<(), String> {
         use std::fs::File;
         use std::io::prelude::*;
@@ -297,7 +700,7 @@ mod tests {
         assert_eq!(t.eval(input), "15\n(2 4 6 8 10)\n(2 4 6 8 10)\n35\n#t\n#f");
     }
 
-    // #[test]
+    #[test]
     fn stuff_vector_set() {
         let input = concat!(
             "(define temp (make-vector 5 'a))",
@@ -307,11 +710,12 @@ mod tests {
         let t = Thingus::new(Box::new(noop));
         assert_eq!(t.eval(input), "#(a a a 9 a)");
 
-        let s = Rc::new(RefCell::new(vec![]));
+        let s = Rc::new(RefCell::new(String::new()));
         let cs = s.clone();
-        let f = move |port: &mut Vec<LispVal>| {
+        let f = move |output: &mut String| {
             let mut s = cs.as_ref().borrow_mut();
-            s.extend(port.drain(0..));
+            s.push_str(output);
+            output.clear();
         };
 
         let input = "
@@ -319,30 +723,1093 @@ mod tests {
             (define (mut v i val) 
                 (vector-set! v i val)
                 (write temp)
+                (newline)
                 (write v)
+                (newline)
                 v)
-            (define (foo a b) (write a) (write b))
+            (define (foo a b) (write a) (newline) (write b) (newline))
             (foo '(1 2 3) 2)
             (define temp2 temp)
             (mut temp 4 6)
         ";
         let t = Thingus::new(Box::new(f));
         t.eval(input);
-        let s = s.borrow();
-        let s = s.deref();
-        let result = s
-            .iter()
-            .map(|val| format!("{}", val))
-            .collect::<Vec<String>>()
-            .join("\n");
-        assert_eq!(
-            result,
-            "(1 2 3)\n2\n#(a a a a 6)\n#(a a a a 6)\n#(a a a a 6)"
-        );
-
-        // (let ((vec (vector 0 '(2 2 2 2) "Anna")))
-        //   (vector-set! vec 1 '("Sue" "Sue"))
-        //   vec)
-        //      =>  #(0 ("Sue" "Sue") "Anna")
+        assert_eq!(*s.borrow(), "(1 2 3)\n2\n#(a a a a 6)\n#(a a a a 6)\n");
+
+        let t = Thingus::new(Box::new(noop));
+        let input = concat!(
+            "(let ((vec (vector 0 '(2 2 2 2) \"Anna\")))",
+            "  (vector-set! vec 1 '(\"Sue\" \"Sue\"))",
+            "  vec)",
+        );
+        assert_eq!(t.eval(input), "#(0 (\"Sue\" \"Sue\") \"Anna\")");
+        assert_eq!(
+            t.eval("(vector-set! (vector 1 2) 2 'x)"),
+            "vector-set!: index is out of range\nindex: 2\nvalid range: [0, 2]\nvector: #(1 2)"
+        );
+    }
+
+    #[test]
+    fn circular_equality() {
+        let t = Thingus::new(Box::new(noop));
+        let input = concat!(
+            "(define v (vector 1))",
+            "(vector-set! v 0 v)",
+            "(define w (vector 1))",
+            "(vector-set! w 0 w)",
+            "(define x (vector 1 2))",
+            "(vector-set! x 0 x)",
+            "(define y (vector 1 3))",
+            "(vector-set! y 0 y)",
+        );
+        t.eval(input);
+        assert_eq!(t.eval("(equal? v w)"), "#t");
+        assert_eq!(t.eval("(equal? v v)"), "#t");
+        assert_eq!(t.eval("(equal? x y)"), "#f");
+        assert_eq!(t.eval("(equal? (list v 1) (list w 1))"), "#t");
+        assert_eq!(t.eval("(equal? v x)"), "#f");
+        assert_eq!(t.eval("(length (member w (list 1 v 2)))"), "2");
+        assert_eq!(t.eval("(cdr (assoc w (list (cons v 'found))))"), "found");
+    }
+
+    #[test]
+    fn higher_order_primitives() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(t.eval("(apply + 1 2 '(3 4))"), "10");
+        assert_eq!(t.eval("(apply list '())"), "()");
+        assert_eq!(t.eval("(apply (lambda xs xs) 1 '(2))"), "(1 2)");
+        assert_eq!(t.eval("(map + '(1 2 3) '(10 20))"), "(11 22)");
+        assert_eq!(t.eval("(map (lambda (x) (* x x)) '(1 2 3))"), "(1 4 9)");
+        assert_eq!(
+            t.eval("(define v (make-vector 3 0)) (for-each (lambda (i x) (vector-set! v i x)) '(0 1 2) '(a b c)) v"),
+            "#(a b c)"
+        );
+        assert_eq!(t.eval("(vector-map * #(1 2 3) #(4 5 6))"), "#(4 10 18)");
+        assert_eq!(
+            t.eval("(vector-for-each (lambda (x i) (vector-set! v i x)) #(c b a) #(0 1)) v"),
+            "#(c b c)"
+        );
+        assert_eq!(
+            t.eval("(string-map (lambda (c) (if (eq? c #\\a) #\\o c)) \"banana\")"),
+            "\"bonono\""
+        );
+        assert_eq!(
+            t.eval("(string-map (lambda (c) 1) \"a\")"),
+            "string-map: contract violation\nexpected: char?\ngiven: 1"
+        );
+        assert_eq!(
+            t.eval("(define w (make-vector 2 0)) (string-for-each (lambda (a b) (vector-set! w 0 (list a b))) \"ab\" \"xyz\") w"),
+            "#((#\\b #\\y) 0)"
+        );
+        assert_eq!(t.eval("(member 2.0 '(1 2 3) =)"), "(2 3)");
+        assert_eq!(t.eval("(member '(a) '(b (a) c))"), "((a) c)");
+        assert_eq!(t.eval("(member 5 '(1 2 3))"), "#f");
+        assert_eq!(t.eval("(assoc 2.0 '((1 one) (2 two)) =)"), "(2 two)");
+        assert_eq!(
+            t.eval("(assoc \"b\" '((\"a\" . 1) (\"b\" . 2)))"),
+            "(\"b\" . 2)"
+        );
+        assert_eq!(t.eval("(sort '(3 1 2) <)"), "(1 2 3)");
+        assert_eq!(
+            t.eval("(sort '((1 a) (0 b) (1 c) (0 d)) (lambda (x y) (< (car x) (car y))))"),
+            "((0 b) (0 d) (1 a) (1 c))"
+        );
+        assert_eq!(
+            t.eval("(map car 5)"),
+            "map: contract violation\nexpected: list?\ngiven: 5"
+        );
+        assert_eq!(
+            t.eval("(sort '(1 2) 3)"),
+            "sort: contract violation\nexpected: procedure?\ngiven: 3"
+        );
+    }
+
+    #[test]
+    fn read() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(define p (open-input-string \"(a . b) #(1 \\\"two\\\") ; done\\n 'x\")) (read p) (read p) (read p) (eof-object? (read p))"),
+            "(a . b)\n#(1 \"two\")\n'x\n#t"
+        );
+        assert_eq!(t.eval("(car (read (open-input-string \"'x\")))"), "quote");
+        // The port is left just after the datum
+        assert_eq!(
+            t.eval("(define q (open-input-string \"42 rest\")) (read q) (read-line q)"),
+            "42\n\" rest\""
+        );
+        assert_eq!(
+            t.eval("(read (open-input-string \"(1 2\"))"),
+            "read: expected a `)` to close `(`"
+        );
+        assert_eq!(
+            t.eval("(read (open-output-string))"),
+            "read: contract violation\nexpected: input-port?\ngiven: #<output-port>\nargument position: 1st"
+        );
+        // Anything written can be read back, including non-ASCII characters in a list
+        assert_eq!(
+            t.eval("(define out (open-output-string)) (write '(#\\é \"ü\\\"λ\" #(#\\λ)) out) (equal? (read (open-input-string (get-output-string out))) '(#\\é \"ü\\\"λ\" #(#\\λ)))"),
+            "#t"
+        );
+        // Datum comments are skipped, along with any other comments inside a datum
+        assert_eq!(t.eval("(read (open-input-string \"#;1 2\"))"), "2");
+        assert_eq!(
+            t.eval("(read (open-input-string \"(1 #| x |# #;(y) 2) 3\"))"),
+            "(1 2)"
+        );
+        assert_eq!(t.eval("'(1 #| x |# 2)"), "(1 2)");
+        assert_eq!(
+            t.eval("(import (only (scheme read) read)) (read (open-input-string \"sym\"))"),
+            "sym"
+        );
+    }
+
+    #[test]
+    fn shadowed_syntax() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(t.eval("(let ((if list)) (if 1 2 3))"), "(1 2 3)");
+        assert_eq!(t.eval("((lambda (quote) (quote 1 2)) +)"), "3");
+        assert_eq!(
+            t.eval("(define and (lambda xs 'shadowed)) (and #f)"),
+            "shadowed"
+        );
+        assert_eq!(t.eval("(or #f 2)"), "2");
+    }
+
+    #[test]
+    fn procedures_as_values() {
+        let s = Rc::new(RefCell::new(String::new()));
+        let cs = s.clone();
+        let f = move |output: &mut String| {
+            cs.as_ref().borrow_mut().push_str(&std::mem::take(output));
+        };
+        let t = Thingus::new(Box::new(f));
+        let input = concat!(
+            "(define out write)",
+            "(out 'hello)",
+            "(define v (make-vector 2 0))",
+            "(define set vector-set!)",
+            "(set v 0 'x)",
+            "v",
+            "(define (write x) 'mine)",
+            "(write 1)",
+            "(define e eval)",
+            "(e '(+ 1 2))",
+        );
+        assert_eq!(t.eval(input), "#(x 0)\nmine\n3");
+        assert_eq!(*s.borrow(), "hello");
+    }
+
+    #[test]
+    fn ports() {
+        let s = Rc::new(RefCell::new(String::new()));
+        let cs = s.clone();
+        let f = move |output: &mut String| {
+            cs.as_ref().borrow_mut().push_str(&std::mem::take(output));
+        };
+        let t = Thingus::new(Box::new(f));
+        t.eval(concat!(
+            "(display \"a \\\"string\\\"\") (newline)",
+            "(write \"quoted\") (write-char #\\y) (newline)",
+            "(write-string \"abcdef\" (current-output-port) 1 3)",
+            "(display #\\z)",
+        ));
+        assert_eq!(*s.borrow(), "a \"string\"\n\"quoted\"y\nbcz");
+
+        assert_eq!(
+            t.eval("(define p (open-input-string \"ab\\ncd\")) (read-char p) (peek-char p) (read-line p) (read-string 5 p) (read-char p) (eof-object? (read-line p))"),
+            "#\\a\n#\\b\n\"b\"\n\"cd\"\n#<eof>\n#t"
+        );
+        assert_eq!(
+            t.eval("(define out (open-output-string)) (write 'sym out) (display \" \" out) (write 1.5 out) (get-output-string out)"),
+            "\"sym 1.5\""
+        );
+        assert_eq!(
+            t.eval("(with-output-to-string (lambda () (display \"inner\") (write 'x)))"),
+            "\"innerx\""
+        );
+        assert_eq!(
+            t.eval("(define q (open-input-string \"xyz\")) (call-with-port q read-char) (input-port-open? q)"),
+            "#\\x\n#f"
+        );
+        assert_eq!(
+            t.eval(
+                "(define r (open-input-string \"xyz\")) (call-with-port r (lambda (p) (car '())))"
+            ),
+            "car: contract violation\nexpected: pair?\ngiven: ()"
+        );
+        assert_eq!(t.eval("(input-port-open? r)"), "#f");
+        assert_eq!(
+            t.eval("(read-char q)"),
+            "read-char: port is closed\nport: #<input-port>"
+        );
+        assert_eq!(
+            t.eval("(list (input-port? q) (output-port? q) (port? out) (textual-port? 1) (char-ready? (open-input-string \"\")))"),
+            "(#t #f #t #f #t)"
+        );
+        assert_eq!(t.eval("(eof-object? (read-char))"), "#t");
+        assert_eq!(t.eval("(eof-object? (read))"), "#t");
+        assert_eq!(t.eval("(eq? (eof-object) (eof-object))"), "#t");
+        assert_eq!(
+            t.eval("(write 1 q)"),
+            "write: contract violation\nexpected: output-port?\ngiven: #<input-port>\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(close-port out) (display 1 out)"),
+            "display: port is closed\nport: #<output-port>"
+        );
+    }
+
+    #[test]
+    fn bignums() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(t.eval("(+ 9223372036854775807 1)"), "9223372036854775808");
+        assert_eq!(t.eval("(- -9223372036854775808 1)"), "-9223372036854775809");
+        assert_eq!(
+            t.eval("(* 4294967296 4294967296 4294967296)"),
+            "79228162514264337593543950336"
+        );
+        assert_eq!(
+            t.eval("(- 79228162514264337593543950336 79228162514264337593543950335)"),
+            "1"
+        );
+        assert_eq!(t.eval("(integer? 100000000000000000000)"), "#t");
+        assert_eq!(
+            t.eval("(list (< 100000000000000000000 100000000000000000001) (> 1.5 100000000000000000000))"),
+            "(#t #f)"
+        );
+        assert_eq!(
+            t.eval("(= 100000000000000000000 (* 10000000000 10000000000))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(/ 100000000000000000000 300000000000000000000000)"),
+            "1/3000"
+        );
+        assert_eq!(
+            t.eval("(/ 100000000000000000000 50000000000000000000)"),
+            "2"
+        );
+        assert_eq!(t.eval("(* 1/3 3)"), "1");
+        assert_eq!(
+            t.eval("(number->string 340282366920938463463374607431768211455 16)"),
+            "\"ffffffffffffffffffffffffffffffff\""
+        );
+        assert_eq!(t.eval("(+ 0.5 100000000000000000000)"), "1e20");
+        assert_eq!(t.eval("(modulo 100000000000000000007 10)"), "7");
+    }
+
+    #[test]
+    fn exactness() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (exact? 1/2) (exact? 0.5) (inexact? 0.5) (exact-integer? 5) (exact-integer? 5.0))"),
+            "(#t #f #t #t #f)"
+        );
+        assert_eq!(
+            t.eval("(list (integer? 2.0) (integer? 1e300) (integer? 2.5) (integer? +inf.0) (integer? +nan.0) (integer? 4/2) (integer? 1/2))"),
+            "(#t #t #f #f #f #t #f)"
+        );
+        assert_eq!(
+            t.eval("(list (rational? 1.5) (rational? 1e300) (rational? -inf.0) (rational? +nan.0) (rational? 1/3) (real? +inf.0))"),
+            "(#t #t #f #f #t #t)"
+        );
+        assert_eq!(t.eval("(exact 0.25)"), "1/4");
+        assert_eq!(
+            t.eval("(inexact->exact 0.1)"),
+            "3602879701896397/36028797018963968"
+        );
+        assert_eq!(t.eval("(exact->inexact 1/4)"), "0.25");
+        assert_eq!(t.eval("(inexact? (inexact 1))"), "#t");
+        assert_eq!(
+            t.eval("(list #e1.2 #e#x10 #x#e10 #i1/2 (exact? #e1.5))"),
+            "(6/5 16 16 0.5 #t)"
+        );
+        assert_eq!(t.eval("(* 1.0 0)"), "0");
+        assert_eq!(t.eval("(exact? (* 1.5 0))"), "#t");
+        assert_eq!(t.eval("(exact? (+ 1/2 0.5))"), "#f");
+        assert_eq!(t.eval("(/ 0 2.5)"), "0");
+        assert_eq!(t.eval("(list (- 5) (/ 4))"), "(-5 1/4)");
+        assert_eq!(
+            t.eval("(define inf (/ 1.0 0.0)) (define nan (- inf inf)) (list (nan? nan) (nan? 1) (infinite? inf) (infinite? 1.5) (finite? 1.5) (finite? inf) (finite? 1/3))"),
+            "(#t #f #t #f #t #f #t)"
+        );
+        assert_eq!(t.eval("(/ 1.0 0)"), "Divide by zero");
+        assert_eq!(
+            t.eval("(exact inf)"),
+            "exact: no exact representation\nnumber: +inf.0"
+        );
+        assert_eq!(
+            t.eval("(exact? 'a)"),
+            "exact?: contract violation\nexpected: number?\ngiven: a\nargument position: 1st"
+        );
+    }
+
+    #[test]
+    fn integer_division() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (quotient -7 2) (remainder -7 2) (modulo -7 2) (modulo 7 -2))"),
+            "(-3 -1 1 -1)"
+        );
+        assert_eq!(t.eval("(floor/ -7 2)"), "(-4 1)");
+        assert_eq!(t.eval("(truncate/ -7 2)"), "(-3 -1)");
+        assert_eq!(
+            t.eval("(list (floor-quotient 7 -2) (floor-remainder 7 -2) (truncate-quotient 7 -2) (truncate-remainder 7 -2))"),
+            "(-4 -1 -3 1)"
+        );
+        assert_eq!(t.eval("(quotient 7.0 2)"), "3.0");
+        assert_eq!(t.eval("(inexact? (quotient 7.0 2))"), "#t");
+        assert_eq!(
+            t.eval("(quotient -9223372036854775808 -1)"),
+            "9223372036854775808"
+        );
+        assert_eq!(t.eval("(modulo 100000000000000000000 7)"), "2");
+        assert_eq!(t.eval("(modulo 5 0)"), "modulo: undefined for 0");
+        assert_eq!(
+            t.eval("(quotient 1.5 1)"),
+            "quotient: contract violation\nexpected: integer?\ngiven: 1.5\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(list (gcd 32 -36) (gcd) (lcm 32 -36) (lcm))"),
+            "(4 0 288 1)"
+        );
+        assert_eq!(
+            t.eval("(list (abs -7) (abs -1/2) (abs -9223372036854775808))"),
+            "(7 1/2 9223372036854775808)"
+        );
+        assert_eq!(t.eval("(list (min 1 2 -3) (max 1/2 1/3))"), "(-3 1/2)");
+        assert_eq!(t.eval("(inexact? (max 3 2.5))"), "#t");
+        assert_eq!(
+            t.eval("(list (floor -7/2) (ceiling -7/2) (round -7/2) (truncate -7/2) (round 5/2) (round 7/2))"),
+            "(-4 -3 -4 -3 2 4)"
+        );
+        assert_eq!(
+            t.eval("(list (round 2.5) (round -3.5) (floor -4.3))"),
+            "(2.0 -4.0 -5.0)"
+        );
+        assert_eq!(
+            t.eval("(list (numerator 6/4) (denominator 6/4) (denominator 5) (denominator 0.5))"),
+            "(3 2 1 2.0)"
+        );
+        assert_eq!(t.eval("(rationalize 3/10 1/10)"), "1/3");
+        assert_eq!(t.eval("(rationalize -3/10 1/10)"), "-1/3");
+        assert_eq!(t.eval("(rationalize 1/4 1/4)"), "0");
+        assert_eq!(t.eval("(= (rationalize 0.3 1/10) (/ 1.0 3))"), "#t");
+        assert_eq!(t.eval("(inexact? (rationalize 0.3 1/10))"), "#t");
+    }
+
+    #[test]
+    fn transcendental_functions() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (sqrt 16) (sqrt 9/4) (exact? (sqrt 16)) (exact? (sqrt 2)))"),
+            "(4 3/2 #t #f)"
+        );
+        assert_eq!(t.eval("(sqrt 2)"), "1.4142135623730951");
+        assert_eq!(t.eval("(sqrt -4)"), "+2i");
+        assert_eq!(t.eval("(exact-integer-sqrt 17)"), "(4 1)");
+        assert_eq!(
+            t.eval("(exact-integer-sqrt -1)"),
+            "exact-integer-sqrt: contract violation\nexpected: exact-nonnegative-integer?\ngiven: -1\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(list (square 5) (square 1/2) (square 1.5))"),
+            "(25 1/4 2.25)"
+        );
+        assert_eq!(
+            t.eval("(list (expt 2 10) (expt 2 -2) (expt 2/3 3) (expt 0 0) (expt 2.5 0))"),
+            "(1024 1/4 8/27 1 1)"
+        );
+        assert_eq!(t.eval("(expt 2 100)"), "1267650600228229401496703205376");
+        assert_eq!(t.eval("(expt -1 100000000000000000001)"), "-1");
+        assert_eq!(t.eval("(inexact? (expt 4 0.5))"), "#t");
+        assert_eq!(t.eval("(complex? (expt -8 1/3))"), "#t");
+        assert_eq!(t.eval("(expt 0 -1)"), "Divide by zero");
+        assert_eq!(
+            t.eval("(expt 10 1000000000)"),
+            "expt: result is too large\nbits: 3000000000"
+        );
+        assert_eq!(
+            t.eval("(expt 1/2 -100000000000)"),
+            "expt: result is too large\nbits: 100000000000"
+        );
+        assert_eq!(t.eval("(exp 1)"), "2.718281828459045");
+        assert_eq!(t.eval("(log -1)"), "+3.141592653589793i");
+        assert_eq!(
+            t.eval("(list (sqrt (+ (expt 10 400) 1)) (sqrt (/ 1 (expt 10 401))) (sqrt 1/3) (sqrt (- 2 (expt 10 400))))"),
+            "(1e200 3.1622776601683792e-201 0.5773502691896257 +1e200i)"
+        );
+        assert_eq!(
+            t.eval("(list (log (expt 10 400)) (log (/ 1 (expt 10 400))) (log 1/2) (log (- (expt 10 400))))"),
+            "(921.0340371976182 -921.0340371976182 -0.6931471805599453 921.0340371976182+3.141592653589793i)"
+        );
+        assert_eq!(t.eval("(list (log 100 10) (log 8 2))"), "(2.0 3.0)");
+        assert_eq!(
+            t.eval("(list (sin 0) (cos 0) (tan 0) (asin 1) (acos 1) (atan 1) (atan -1 0))"),
+            "(0.0 1.0 0.0 1.5707963267948966 0.0 0.7853981633974483 -1.5707963267948966)"
+        );
+        assert_eq!(
+            t.eval("(sin 'a)"),
+            "sin: contract violation\nexpected: number?\ngiven: a\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(define-library (roots) (export root) (import (only (scheme inexact) sqrt nan?)) (begin (define (root n) (if (nan? n) 0 (sqrt n))))) (import (roots)) (root 16)"),
+            "4"
+        );
+        assert_eq!(
+            t.eval("(import (prefix (scheme inexact) i:)) (list (i:exp 0) (i:finite? +inf.0) (i:atan 0))"),
+            "(1.0 #f 0.0)"
+        );
+        assert_eq!(
+            t.eval("(import (only (scheme inexact) expt))"),
+            "import: identifier expt is not exported: (only (scheme inexact) expt)"
+        );
+    }
+
+    #[test]
+    fn complex_numbers() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(t.eval("(* 1/2+1/3i 2)"), "1+2/3i");
+        assert_eq!(t.eval("(exact? (* 1/2+1/3i 2))"), "#t");
+        assert_eq!(t.eval("(+ 1+1i 1-1i)"), "2");
+        assert_eq!(
+            t.eval("(list (zero? (- 1.0+2.0i 1.0+2.0i)) (zero? (* 0.0 +i)) (zero? 1.0+0.0i) (zero? 0.0+1.0i) (zero? 1+2i))"),
+            "(#t #t #f #f #f)"
+        );
+        assert_eq!(
+            t.eval("(define i (make-rectangular 0 1)) (list i (- i) (* i i))"),
+            "(+i -i -1)"
+        );
+        assert_eq!(t.eval("(make-rectangular 1.5 -2.5)"), "1.5-2.5i");
+        assert_eq!(t.eval("(make-rectangular 0.0 -2.5)"), "-2.5i");
+        assert_eq!(t.eval("(imag-part -2.5i)"), "-2.5");
+        assert_eq!(t.eval("(make-polar 2 0)"), "2");
+        assert_eq!(t.eval("(= (make-polar 2 0.5) 2@0.5)"), "#t");
+        assert_eq!(
+            t.eval(
+                "(list (real-part 1/2+3i) (imag-part 1/2+3i) (imag-part 5) (real-part 1.5+2.5i))"
+            ),
+            "(1/2 3 0 1.5)"
+        );
+        assert_eq!(
+            t.eval("(list (magnitude 3+4i) (magnitude -5) (angle 1))"),
+            "(5 5 0)"
+        );
+        assert_eq!(t.eval("(angle -1)"), "3.141592653589793");
+        assert_eq!(t.eval("(angle 0)"), "angle: undefined for 0");
+        assert_eq!(t.eval("(exact (make-rectangular 1.5 2.5))"), "3/2+5/2i");
+        assert_eq!(t.eval("(inexact? (inexact 1/2+1i))"), "#t");
+        assert_eq!(t.eval("(list (expt 1+1i 2) (/ 1+1i 1-1i))"), "(+2i +i)");
+        assert_eq!(t.eval("(number->string 1/2-1i 2)"), "\"1/10-i\"");
+        assert_eq!(t.eval("(< 1+2i 2)"), "< not defined for complex numbers");
+        assert_eq!(
+            t.eval("(make-rectangular 1+2i 1)"),
+            "make-rectangular: contract violation\nexpected: real?\ngiven: 1+2i\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(define-library (points) (export norm) (import (only (scheme complex) make-rectangular magnitude)) (begin (define (norm x y) (magnitude (make-rectangular x y))))) (import (points)) (norm 3 4)"),
+            "5"
+        );
+        assert_eq!(
+            t.eval("(import (prefix (scheme complex) c:)) (list (c:real-part 1+2i) (c:imag-part 1+2i) (c:angle 1) (c:make-polar 2 0))"),
+            "(1 2 0 2)"
+        );
+        assert_eq!(
+            t.eval("(import (only (scheme complex) sqrt))"),
+            "import: identifier sqrt is not exported: (only (scheme complex) sqrt)"
+        );
+    }
+
+    #[test]
+    fn number_syntax() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list #e1e3 #e1.5e-3 (* 2 +i) 1-i)"),
+            "(1000 3/2000 +2i 1-i)"
+        );
+        assert_eq!(
+            t.eval("(list (< 1e10 1e11) (> +inf.0 1e308) (nan? -nan.0))"),
+            "(#t #t #t)"
+        );
+        assert_eq!(
+            t.eval("(list (string->number \"100\") (string->number \"ff\" 16) (string->number \"#b101\") (string->number \"1/2\"))"),
+            "(100 255 5 1/2)"
+        );
+        assert_eq!(
+            t.eval(
+                "(list (string->number \"abc\") (string->number \"1+\") (string->number \"2\" 2))"
+            ),
+            "(#f #f #f)"
+        );
+        assert_eq!(
+            t.eval(
+                "(list (string->number \"#e1e1000000000\") (string->number \"#e1e-1000000000\"))"
+            ),
+            "(#f #f)"
+        );
+        assert_eq!(
+            t.eval("(define x 1) '(1 #e1e1000000000)"),
+            "Parse error at '(1 #e1e1000000000)"
+        );
+        // Nothing runs if any of the input can't be read
+        assert_eq!(t.eval("x"), "Getting an unbound variable: x");
+        assert_eq!(
+            t.eval("(read (open-input-string \"#e1e1000000000\"))"),
+            "read: bad syntax `#e1e1000000000`"
+        );
+        assert_eq!(
+            t.eval("(string->number \"1\" 3)"),
+            "string->number: contract violation\nexpected: (or/c 2 8 10 16)\ngiven: 3\nargument position: 2nd"
+        );
+        assert_eq!(t.eval("'|+i|"), "|+i|");
+    }
+
+    #[test]
+    fn numeric_equivalence() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (eqv? (expt 2 100) (expt 2 100)) (eqv? (expt 2 100) (expt 2 101)) (equal? (expt 2 100) (expt 2 100)))"),
+            "(#t #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (eqv? 1/2 1/2) (eqv? 1/2 1/3) (eqv? 1/2 0.5))"),
+            "(#t #f #f)"
+        );
+        assert_eq!(
+            t.eval("(list (eqv? 1.5 1.5) (eqv? 2 2.0) (eqv? 0.0 -0.0) (eqv? +nan.0 +nan.0))"),
+            "(#t #f #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (eqv? 1/2+2i 1/2+2i) (eqv? 1+2i 1+3i) (eqv? 1+2i 1.0+2.0i))"),
+            "(#t #f #f)"
+        );
+        assert_eq!(
+            t.eval("(list (equal? 1.5+2i 1.5+2i) (eqv? 1.5+2i 1.5-2i) (equal? '(1.5 1/2) (list 1.5 1/2)))"),
+            "(#t #f #t)"
+        );
+        assert_eq!(
+            t.eval("(member (expt 2 64) (list 1 (expt 2 64) 3))"),
+            "(18446744073709551616 3)"
+        );
+    }
+
+    #[test]
+    fn mixed_exactness_comparison() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (= 9007199254740993 9007199254740992.0) (= 9007199254740992 9007199254740992.0) (= 1/3 (/ 1.0 3)) (= 1/2 0.5))"),
+            "(#f #t #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (< 9007199254740992.0 9007199254740993) (> 9007199254740993 9007199254740992.0) (<= 9007199254740993 9007199254740992.0) (>= 0.5 1/2))"),
+            "(#t #t #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (< (expt 10 400) +inf.0) (> (- (expt 10 400)) -inf.0) (< 1 +nan.0) (> 1 +nan.0) (= 1 +nan.0) (= 1/2+1i 0.5+1.0i))"),
+            "(#t #t #f #f #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (max 9007199254740993 9007199254740992.0) (min 1/3 0.3))"),
+            "(9007199254740992.0 0.3)"
+        );
+    }
+
+    #[test]
+    fn number_printing() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list 2.0 (/ 6 3) (/ 1 3) 1e21 (/ 1.0 3))"),
+            "(2.0 2 1/3 1e21 0.3333333333333333)"
+        );
+        assert_eq!(
+            t.eval("(list (/ 1.0 0.0) (- +inf.0) (- +inf.0 +inf.0))"),
+            "(+inf.0 -inf.0 +nan.0)"
+        );
+        assert_eq!(
+            t.eval("(list 1-2i 1.5-2.5i (make-rectangular 1.0 0))"),
+            "(1-2i 1.5-2.5i 1.0+0.0i)"
+        );
+        assert_eq!(
+            t.eval("(list (number->string 2.0) (number->string 255.5 16) (number->string -0.5 2) (number->string 1/2+1i 2))"),
+            "(\"2.0\" \"ff.8\" \"-0.1\" \"1/10+i\")"
+        );
+        assert_eq!(t.eval("(string->number (number->string 0.1))"), "0.1");
+        assert_eq!(
+            t.eval("(list (string->number (number->string 0.1 2) 2) (string->number (number->string -1e-300 2) 2))"),
+            "(0.1 -1e-300)"
+        );
+        assert_eq!(
+            t.eval("(number->string 'a)"),
+            "number->string: contract violation\nexpected: number?\ngiven: a\nargument position: 1st"
+        );
+    }
+
+    #[test]
+    fn bitwise_operations() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (bitwise-and 12 10) (bitwise-ior 12 10) (bitwise-xor 12 10) (bitwise-not 12))"),
+            "(8 14 6 -13)"
+        );
+        assert_eq!(
+            t.eval("(list (bitwise-and) (bitwise-ior) (bitwise-xor) (bitwise-and -1 -8 255))"),
+            "(-1 0 0 248)"
+        );
+        assert_eq!(
+            t.eval("(bitwise-and (- (expt 2 100) 1) -4294967296)"),
+            "1267650600228229401492408238080"
+        );
+        assert_eq!(t.eval("(bitwise-xor (expt 2 64) (expt 2 64) 5)"), "5");
+        assert_eq!(
+            t.eval("(list (arithmetic-shift 1 70) (arithmetic-shift -5 -1) (arithmetic-shift (expt 2 70) -68) (arithmetic-shift -1 (- (expt 2 70))))"),
+            "(1180591620717411303424 -3 4 -1)"
+        );
+        assert_eq!(
+            t.eval("(list (bit-count 13) (bit-count -13) (integer-length 255) (integer-length -256) (integer-length 0))"),
+            "(3 2 8 8 0)"
+        );
+        assert_eq!(
+            t.eval("(list (bit-set? 2 13) (bit-set? 1 13) (bit-set? 200 -1))"),
+            "(#t #f #t)"
+        );
+        assert_eq!(
+            t.eval("(list (copy-bit 1 13 #t) (copy-bit 0 13 #f) (copy-bit 64 0 #t))"),
+            "(15 12 18446744073709551616)"
+        );
+        assert_eq!(
+            t.eval("(list (bit-field 180 2 6) (bit-field -1 0 4) (bit-field 5 3 3))"),
+            "(13 15 0)"
+        );
+        assert_eq!(
+            t.eval("(list (bit-field 5 0 1000000000000) (bit-field -1 1000000000000 1000000000003) (bit-field 5 1000000000000 1000000000003))"),
+            "(5 7 0)"
+        );
+        assert_eq!(
+            t.eval("(list (copy-bit 1000000000000 5 #f) (copy-bit (expt 10 30) -5 #t) (bit-set? (expt 10 30) -5) (bit-set? (expt 10 30) 5) (bit-set? 5 (expt 2 70)))"),
+            "(5 -5 #t #f #f)"
+        );
+        assert_eq!(
+            t.eval("(list (arithmetic-shift 5 (- (expt 10 30))) (arithmetic-shift -5 -1000000000000) (arithmetic-shift 0 (expt 10 30)))"),
+            "(0 -1 0)"
+        );
+        assert_eq!(
+            t.eval("(arithmetic-shift 1 100000000000000)"),
+            "arithmetic-shift: result is too large\nbits: 100000000000001"
+        );
+        assert_eq!(
+            t.eval("(copy-bit 1000000000000 5 #t)"),
+            "copy-bit: result is too large\nbits: 1000000000000"
+        );
+        assert_eq!(
+            t.eval("(bit-field -1 0 1000000000000)"),
+            "bit-field: result is too large\nbits: 1000000000000"
+        );
+        assert_eq!(
+            t.eval("(bitwise-and 1 1.0)"),
+            "bitwise-and: contract violation\nexpected: exact-integer?\ngiven: 1.0\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(bit-set? -1 5)"),
+            "bit-set?: contract violation\nexpected: exact-nonnegative-integer?\ngiven: -1\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(import (only (srfi 151) bit-count)) (bit-count 255)"),
+            "8"
+        );
+    }
+
+    #[test]
+    fn fixnum_arithmetic() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (fx+ 3 4) (fx- 3 4) (fx- 5) (fx* -3 4) (fxquotient -7 2) (fxremainder -7 2) (fxabs -5))"),
+            "(7 -1 -5 -12 -3 -1 5)"
+        );
+        assert_eq!(
+            t.eval("(list (fx<? 1 2 3) (fx<? 1 3 2) (fx=? 2 2) (fx>=? 3 3 1) (fxzero? 0) (fxodd? -3) (fxmax 1 5 3) (fxmin 4 -2))"),
+            "(#t #f #t #t #t #t 5 -2)"
+        );
+        assert_eq!(
+            t.eval("(list (fxand 12 10) (fxior 12 10) (fxxor 12 10) (fxnot 0) (fxarithmetic-shift 3 4) (fxarithmetic-shift -17 -2))"),
+            "(8 14 6 -1 48 -5)"
+        );
+        assert_eq!(
+            t.eval("(list (fixnum? (greatest-fixnum)) (fixnum? (+ (greatest-fixnum) 1)) (fixnum-width))"),
+            "(#t #f 64)"
+        );
+        assert_eq!(
+            t.eval("(fx+ (greatest-fixnum) 1)"),
+            "fx+: result is not a fixnum\narguments: 9223372036854775807 1"
+        );
+        assert_eq!(
+            t.eval("(fx- (least-fixnum))"),
+            "fx-: result is not a fixnum\narguments: -9223372036854775808"
+        );
+        assert_eq!(
+            t.eval("(fxarithmetic-shift 1 63)"),
+            "fxarithmetic-shift: result is not a fixnum\narguments: 1 63"
+        );
+        assert_eq!(t.eval("(fxquotient 1 0)"), "fxquotient: undefined for 0");
+        assert_eq!(
+            t.eval("(list (fxdiv 7 2) (fxmod 7 2) (fxdiv -7 2) (fxmod -7 2) (fxdiv 7 -2) (fxmod 7 -2) (fxdiv -7 -2) (fxmod -7 -2))"),
+            "(3 1 -4 1 -3 1 4 1)"
+        );
+        assert_eq!(
+            t.eval("(list (fxdiv0 7 2) (fxmod0 7 2) (fxdiv0 -7 2) (fxmod0 -7 2) (fxdiv0 5 3) (fxmod0 5 3) (fxdiv0 -5 -3) (fxmod0 -5 -3))"),
+            "(4 -1 -3 -1 2 -1 2 1)"
+        );
+        assert_eq!(
+            t.eval("(list (fxdiv-and-mod -7 2) (fxdiv0-and-mod0 7 2) (fxmod (least-fixnum) -1))"),
+            "((-4 1) (4 -1) 0)"
+        );
+        assert_eq!(
+            t.eval("(fxdiv (least-fixnum) -1)"),
+            "fxdiv: result is not a fixnum\narguments: -9223372036854775808 -1"
+        );
+        assert_eq!(t.eval("(fxmod0 1 0)"), "fxmod0: undefined for 0");
+        assert_eq!(
+            t.eval("(fx+ 1 1.0)"),
+            "fx+: contract violation\nexpected: fixnum?\ngiven: 1.0\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(fx<? 2 1 'a)"),
+            "fx<?: contract violation\nexpected: fixnum?\ngiven: a\nargument position: 3rd"
+        );
+        assert_eq!(
+            t.eval("(import (only (rnrs arithmetic fixnums) fx*)) (fx* 6 7)"),
+            "42"
+        );
+    }
+
+    #[test]
+    fn flonum_arithmetic() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(list (fl+ 1.5 2.0 0.5) (fl+) (fl- 1.0) (fl- 5.0 1.0 1.5) (fl* 2.0 3.0) (fl/ 2.0) (fl/ 1.0 0.0))"),
+            "(4.0 0.0 -1.0 2.5 6.0 0.5 +inf.0)"
+        );
+        assert_eq!(
+            t.eval("(list (flfloor -1.5) (flceiling 1.2) (flround 2.5) (flround 3.5) (fltruncate -1.7) (flabs -2.0))"),
+            "(-2.0 2.0 2.0 4.0 -1.0 2.0)"
+        );
+        assert_eq!(
+            t.eval("(list (flsqrt 16.0) (flexpt 2.0 10.0) (fllog 8.0 2.0) (flexp 0.0) (flatan 1.0 0.0) (flmax 1.0 3.0) (flmin 1.0 3.0))"),
+            "(4.0 1024.0 3.0 1.0 1.5707963267948966 3.0 1.0)"
+        );
+        assert_eq!(
+            t.eval("(list (fl<? 1.0 2.0) (fl=? 1.0 1.0 2.0) (flzero? -0.0) (flinteger? 2.0) (flinteger? +inf.0) (flnan? +nan.0) (flonum? 1))"),
+            "(#t #f #t #t #f #t #f)"
+        );
+        assert_eq!(t.eval("(fixnum->flonum 3)"), "3.0");
+        assert_eq!(
+            t.eval("(fl+ 1.0 1)"),
+            "fl+: contract violation\nexpected: flonum?\ngiven: 1\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(import (only (rnrs arithmetic flonums) flsqrt)) (flsqrt 2.25)"),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn random_numbers() {
+        let t = Thingus::new(Box::new(noop));
+        let u = Thingus::new(Box::new(noop));
+        let draws =
+            "(list (random-integer 1000) (random-integer (expt 10 30)) (random-real) (random 6))";
+        assert_eq!(t.eval(draws), u.eval(draws));
+        let zeros = format!("'({})", "0 ".repeat(100));
+        assert_eq!(
+            t.eval(&format!("(define draws (map (lambda (_) (random-integer 6)) {})) (list (apply min draws) (apply max draws))", zeros)),
+            "(0 5)"
+        );
+        assert_eq!(
+            t.eval("(define x (random-real)) (and (< 0 x) (< x 1) (inexact? x))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(define y (random 2.5)) (and (< 0 y) (< y 2.5))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(define state (random-source-state-ref default-random-source)) (define a (random-integer (expt 10 30))) (random-source-pseudo-randomize! default-random-source 1 2) (random-source-state-set! default-random-source state) (= a (random-integer (expt 10 30)))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(define (draw) (random-source-pseudo-randomize! default-random-source 7 0) (list (random-integer 100) (random-integer 100))) (equal? (draw) (draw))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(list (random-source? default-random-source) (random-source? 1) default-random-source)"),
+            "(#t #f #<random-source>)"
+        );
+        assert_eq!(
+            t.eval("(random-integer 0)"),
+            "random-integer: contract violation\nexpected: exact-positive-integer?\ngiven: 0\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(random-source-state-set! (make-random-source) '(0 0 0 0))"),
+            "random-source-state-set!: invalid state\nstate: (0 0 0 0)"
+        );
+        assert_eq!(
+            t.eval("(random-source-pseudo-randomize! (make-random-source) -1 0)"),
+            "random-source-pseudo-randomize!: contract violation\nexpected: exact-nonnegative-integer?\ngiven: -1\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(import (only (srfi 27) random-integer)) (random-integer 1)"),
+            "0"
+        );
+
+        // Sources other than the default one
+        let input = concat!(
+            "(define s (make-random-source))",
+            "(define next (random-source-make-integers s))",
+            "(define (draw) (random-source-pseudo-randomize! s 3 4) (map next '(10 1000 100000)))",
+            "(define first (draw))",
+            "(define other (random-integer 1000))",
+            "(equal? first (draw))",
+        );
+        assert_eq!(t.eval(input), "#t");
+        assert_eq!(
+            t.eval("(define real (random-source-make-reals s 0.001)) (define r (real)) (and (< 0 r) (< r 1))"),
+            "#t"
+        );
+        assert_eq!(
+            t.eval("(list (procedure? next) next)"),
+            "(#t #<procedure:random-integer>)"
+        );
+        assert_eq!(
+            t.eval("(next 0)"),
+            "random-integer: contract violation\nexpected: exact-positive-integer?\ngiven: 0\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(random-source-make-reals s 2)"),
+            "random-source-make-reals: contract violation\nexpected: (and/c real? (>/c 0) (</c 1))\ngiven: 2\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(define before (random-source-state-ref s)) (random-source-randomize! s) (equal? before (random-source-state-ref s))"),
+            "#f"
+        );
+    }
+
+    #[test]
+    fn write_and_display() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(define out (open-output-string)) (write '(\"a\\\"b\" #\\space |x y|) out) (get-output-string out)"),
+            "\"(\\\"a\\\\\\\"b\\\" #\\\\space |x y|)\""
+        );
+        assert_eq!(
+            t.eval("(define out2 (open-output-string)) (display '(\"a\\\"b\" #\\space |x y|) out2) (get-output-string out2)"),
+            "\"(a\\\"b   x y)\""
+        );
+        assert_eq!(
+            t.eval("(define v (vector 1 2)) (vector-set! v 1 v) v"),
+            "#0=#(1 #0#)"
+        );
+        assert_eq!(
+            t.eval("(define out3 (open-output-string)) (define xs (list 1)) (write-shared (list xs xs) out3) (write-simple (list xs xs) out3) (get-output-string out3)"),
+            "\"(#0=(1) #0#)((1) (1))\""
+        );
+        assert_eq!(
+            t.eval("(define out5 (open-output-string)) (write v out5) (define w (read (open-input-string (get-output-string out5)))) (list w (equal? v w) (eq? w (vector-ref w 1)))"),
+            "(#0=#(1 #0#) #t #t)"
+        );
+        assert_eq!(
+            t.eval("(define u (vector 0)) (define ys (list 1 u)) (vector-set! u 0 ys) (define out6 (open-output-string)) (write ys out6) (define zs (read (open-input-string (get-output-string out6)))) (list (get-output-string out6) (eq? zs (vector-ref (cadr zs) 0)))"),
+            "(\"#0=(1 #(#0#))\" #t)"
+        );
+        assert_eq!(
+            t.eval("(read (open-input-string \"#0=(1 #0#)\"))"),
+            "read: bad syntax `#0=(1 #0#)`"
+        );
+        assert_eq!(
+            t.eval("(define out4 (open-output-string)) (write (newline (open-output-string)) out4) (get-output-string out4)"),
+            "\"#<void>\""
+        );
+    }
+
+    #[test]
+    fn pretty_printing() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(define out (open-output-string)) (pretty-print '(a b) out) (get-output-string out)"),
+            "\"(a b)\\n\""
+        );
+        let nested = "'(define (f x) (let loop ((i 0) (acc '())) (if (= i x) (reverse acc) (loop (+ i 1) (cons i acc)))))";
+        assert_eq!(
+            t.eval(nested),
+            "(define (f x) (let loop ((i 0) (acc '())) (if (= i x) (reverse acc) (loop (+ i 1) (cons i acc)))))"
+        );
+        t.set_pretty_width(Some(40));
+        assert_eq!(
+            t.eval(nested),
+            "(define (f x)\n  (let loop ((i 0) (acc '()))\n    (if (= i x)\n        (reverse acc)\n        (loop (+ i 1) (cons i acc)))))"
+        );
+    }
+
+    #[test]
+    fn binary_ports() {
+        let t = Thingus::new(Box::new(noop));
+        assert_eq!(
+            t.eval("(define bv (bytevector 1 2 255)) bv (bytevector-u8-ref bv 2) (equal? bv #u8(1 2 255))"),
+            "#u8(1 2 255)\n255\n#t"
+        );
+        assert_eq!(
+            t.eval("(define in (open-input-bytevector #u8(7 8))) (peek-u8 in) (read-u8 in) (read-bytevector 5 in) (eof-object? (read-u8 in))"),
+            "7\n7\n#u8(8)\n#t"
+        );
+        assert_eq!(
+            t.eval("(define out (open-output-bytevector)) (write-u8 1 out) (write-bytevector #u8(2 3 4) out 1) (get-output-bytevector out)"),
+            "#u8(1 3 4)"
+        );
+        assert_eq!(
+            t.eval("(list (binary-port? out) (textual-port? out) (output-port? out) (binary-port? (open-input-string \"\")))"),
+            "(#t #f #t #f)"
+        );
+        assert_eq!(
+            t.eval("(write-char #\\a out)"),
+            "write-char: contract violation\nexpected: textual-port?\ngiven: #<binary-output-port>\nargument position: 2nd"
+        );
+        assert_eq!(
+            t.eval("(read-u8 (open-input-string \"a\"))"),
+            "read-u8: contract violation\nexpected: (and/c input-port? binary-port?)\ngiven: #<input-port>\nargument position: 1st"
+        );
+        assert_eq!(
+            t.eval("(bytevector 256)"),
+            "bytevector: contract violation\nexpected: byte?\ngiven: 256"
+        );
+    }
+
+    #[test]
+    fn waiting_for_input() {
+        let s = Rc::new(RefCell::new(String::new()));
+        let cs = s.clone();
+        let f = move |output: &mut String| {
+            cs.as_ref().borrow_mut().push_str(&std::mem::take(output));
+        };
+        let t = Thingus::new(Box::new(f));
+        let input = port::Port::interactive();
+        t.ports.set_input(input.clone());
+
+        let program = concat!(
+            "(define (greet) (display \"name? \") (let ((name (read-line))) (display (string-append \"hi \" name)) (newline) name))",
+            "(greet) (read) (+ 1 2)",
+        );
+        assert_eq!(t.eval(program), "");
+        assert!(t.is_waiting());
+        assert_eq!(*s.borrow(), "name? ");
+
+        input.push_input("bob\n");
+        assert_eq!(t.resume(), "\"bob\"");
+        assert_eq!(*s.borrow(), "name? hi bob\n");
+
+        // `12` might be the start of a longer number
+        input.push_input("12");
+        assert_eq!(t.resume(), "");
+        assert!(t.is_waiting());
+        input.push_input(" ");
+        assert_eq!(t.resume(), "12\n3");
+        assert!(!t.is_waiting());
+
+        assert_eq!(t.eval("(read-char) (char-ready?)"), "#\\space\n#f");
+        assert_eq!(eval("(read-char)", Ports::new(Box::new(noop))), "#<eof>");
+    }
+
+    #[test]
+    fn waiting_after_side_effects() {
+        let t = Thingus::new(Box::new(noop));
+        let input = port::Port::interactive();
+        t.ports.set_input(input.clone());
+
+        // Re-running these forms would repeat their effects, so they can't pause
+        let refused =
+            "read-line: can't wait for input after side effects in the same top-level form";
+        assert_eq!(
+            t.eval("(define v (vector 0)) ((lambda () (vector-set! v 0 (+ (vector-ref v 0) 1)) (read-line)))"),
+            refused
+        );
+        assert!(!t.is_waiting());
+        assert_eq!(t.eval("v"), "#(1)");
+        assert_eq!(
+            t.eval("((lambda () (eval '(define x 1) (interaction-environment)) (read-line)))"),
+            refused
+        );
+        assert_eq!(
+            t.eval("((lambda () (random-integer 10) (read-line)))"),
+            refused
+        );
+        assert_eq!(
+            t.eval("(define out (open-output-string)) ((lambda () (write 1 out) (read-line)))"),
+            refused
+        );
+
+        // Writing to the current output port and defining locally are fine
+        assert_eq!(
+            t.eval("(define (ask) (define prompt \"? \") (display prompt) (read-line)) (ask)"),
+            ""
+        );
+        assert!(t.is_waiting());
+        input.push_input("yes\n");
+        assert_eq!(t.resume(), "\"yes\"");
+    }
+
+    #[test]
+    fn files() {
+        let t = Thingus::new(Box::new(noop));
+        let files = Rc::new(file_system::MemoryFileSystem::new());
+        files.insert("data/in.txt", "(1 2)");
+        t.set_file_system(files.clone());
+        assert_eq!(
+            t.eval("(call-with-input-file \"data/in.txt\" read) (create-directory \"out\") (file-exists? \"out\")"),
+            "(1 2)\n#t"
+        );
+        assert_eq!(
+            t.eval(concat!(
+                "(define path \"out/a.scm\")",
+                "(with-output-to-file path (lambda () (display \"(define x 5)\") (newline) (write '(+ x 1))))",
+                "(call-with-input-file path read-line)",
+                "(with-input-from-file path (lambda () (read-line) (read)))",
+                "(file-size path)",
+                "(load path)",
+                "x",
+            )),
+            "\"(define x 5)\"\n(+ x 1)\n20\n5"
+        );
+        // Ports are closed even when the procedure raises an error
+        assert_eq!(
+            t.eval(concat!(
+                "(define ports (make-vector 2 #f))",
+                "(call-with-input-file path (lambda (p) (vector-set! ports 0 p) (car '())))",
+            )),
+            "car: contract violation\nexpected: pair?\ngiven: ()"
+        );
+        assert_eq!(
+            t.eval("(call-with-output-file \"err.txt\" (lambda (p) (vector-set! ports 1 p) (car '())))"),
+            "car: contract violation\nexpected: pair?\ngiven: ()"
+        );
+        assert_eq!(
+            t.eval("(list (input-port-open? (vector-ref ports 0)) (output-port-open? (vector-ref ports 1)))"),
+            "(#f #f)"
+        );
+        assert_eq!(
+            files.get("out/a.scm"),
+            Some("(define x 5)\n(+ x 1)".to_string())
+        );
+        assert_eq!(
+            t.eval(concat!(
+                "(define port (open-binary-output-file \"out/b.bin\"))",
+                "(write-bytevector #u8(0 1 2) port) (close-port port)",
+                "(define in (open-binary-input-file \"out/b.bin\"))",
+                "(read-u8 in) (read-bytevector 10 in)",
+                "(directory-files \"out\")",
+            )),
+            "0\n#u8(1 2)\n(\"a.scm\" \"b.bin\")"
+        );
+        assert_eq!(t.eval("(delete-file path) (file-exists? path)"), "#f");
+        assert_eq!(
+            t.eval("(open-input-file path)"),
+            "open-input-file: cannot open input file\npath: out/a.scm\nsystem error: no such file: out/a.scm"
+        );
     }
 }
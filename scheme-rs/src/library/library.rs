@@ -0,0 +1,364 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::{Bindings, Env};
+use crate::error::{LispError, LispResult};
+use crate::eval::eval;
+use crate::lisp_val::LispVal;
+use crate::parser;
+use crate::primitive_functions::standard_library;
+use crate::source::include_forms;
+
+/// Supplies the source of libraries that aren't already registered or in the
+/// interpreter's file system, e.g. from a package index.
+pub trait LibraryLoader {
+    /// Returns the source text for the library with the given name, which
+    /// is expected to contain its `define-library` form.
+    fn load(&self, name: &[String]) -> Option<String>;
+}
+
+impl<L: LibraryLoader> LibraryLoader for RefCell<L> {
+    fn load(&self, name: &[String]) -> Option<String> {
+        self.borrow().load(name)
+    }
+}
+
+/// The conventional relative path for a library, e.g. `(foo bar)` -> `foo/bar.sld`
+pub fn library_path(name: &[String]) -> String {
+    format!("{}.sld", name.join("/"))
+}
+
+#[derive(Default)]
+pub struct NoLoader;
+
+impl LibraryLoader for NoLoader {
+    fn load(&self, _name: &[String]) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct Library {
+    pub name: Vec<String>,
+    pub exports: Bindings,
+}
+
+struct Registry {
+    libraries: RefCell<HashMap<Vec<String>, Rc<Library>>>,
+    loader: RefCell<Rc<dyn LibraryLoader>>,
+    // The libraries whose source is being evaluated, outermost first
+    loading: RefCell<Vec<Vec<String>>>,
+}
+
+/// All the libraries known to an interpreter, shared by every frame of its environment.
+#[derive(Clone)]
+pub struct Libraries(Rc<Registry>);
+
+impl Default for Libraries {
+    fn default() -> Self {
+        Libraries::new(Rc::new(NoLoader))
+    }
+}
+
+impl Libraries {
+    pub fn new(loader: Rc<dyn LibraryLoader>) -> Self {
+        Libraries(Rc::new(Registry {
+            libraries: RefCell::new(HashMap::new()),
+            loader: RefCell::new(loader),
+            loading: RefCell::new(vec![]),
+        }))
+    }
+
+    pub fn set_loader(&self, loader: Rc<dyn LibraryLoader>) {
+        *self.0.loader.borrow_mut() = loader;
+    }
+
+    pub fn register(&self, library: Library) {
+        self.0
+            .libraries
+            .borrow_mut()
+            .insert(library.name.clone(), Rc::new(library));
+    }
+
+    /// Finds a library by name, falling back to the built-in standard
+    /// libraries, then to the loader and finally to the file at its
+    /// `library_path` in the interpreter's file system.
+    pub fn find(&self, env: &Env, name: &[String]) -> LispResult<Rc<Library>> {
+        if let Some(library) = self.0.libraries.borrow().get(name) {
+            return Ok(library.clone());
+        }
+        if let Some(exports) = standard_library(name) {
+            self.register(Library {
+                name: name.to_vec(),
+                exports,
+            });
+            return self.find(env, name);
+        }
+        if let Some(start) = self.0.loading.borrow().iter().position(|n| n == name) {
+            let cycle = self.0.loading.borrow()[start..]
+                .iter()
+                .map(|n| format_name(n))
+                .chain(std::iter::once(format_name(name)))
+                .collect::<Vec<_>>();
+            return Err(LispError::GenericError(format!(
+                "import: cycle in library imports: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let loader = self.0.loader.borrow().clone();
+        let (path, source) = match loader.load(name) {
+            Some(source) => (None, source),
+            None => {
+                let file_system = env.sources.file_system();
+                let path = file_system.resolve(&library_path(name), None);
+                let source = file_system
+                    .read(&path)
+                    .ok()
+                    .and_then(|contents| String::from_utf8(contents).ok())
+                    .ok_or_else(|| {
+                        LispError::GenericError(format!(
+                            "import: unknown library {}",
+                            format_name(name)
+                        ))
+                    })?;
+                (Some(path), source)
+            }
+        };
+        let (_, exprs) =
+            parser::datum_list(&source).map_err(|err| LispError::Parser(format!("{}", err)))?;
+        // The library source is evaluated for its `define-library` forms only,
+        // in a scratch environment that shares this registry. A library read
+        // from a file is the current file while it's evaluated, so that its
+        // `include`s resolve against it.
+        let scratch = Env::new_sharing(env, Bindings::new());
+        let define = || {
+            exprs
+                .iter()
+                .try_for_each(|expr| eval(&scratch, expr).map(|_| ()))
+        };
+        self.0.loading.borrow_mut().push(name.to_vec());
+        let defined = match path {
+            Some(path) => env.sources.with_current(path, define),
+            None => define(),
+        };
+        self.0.loading.borrow_mut().pop();
+        defined?;
+
+        match self.0.libraries.borrow().get(name) {
+            Some(library) => Ok(library.clone()),
+            None => Err(LispError::GenericError(format!(
+                "import: source for {} does not define it",
+                format_name(name)
+            ))),
+        }
+    }
+}
+
+fn format_name(name: &[String]) -> String {
+    format!("({})", name.join(" "))
+}
+
+fn library_name(val: &LispVal) -> LispResult<Vec<String>> {
+    match val {
+        LispVal::List(parts) if !parts.is_empty() => parts
+            .iter()
+            .map(|part| match part {
+                LispVal::Atom(s) => Ok(s.to_string()),
+                LispVal::Integer(n) if *n >= 0 => Ok(n.to_string()),
+                _ => Err(LispError::BadSpecialForm(
+                    "Invalid library name".to_string(),
+                    val.clone(),
+                )),
+            })
+            .collect(),
+        _ => Err(LispError::BadSpecialForm(
+            "Invalid library name".to_string(),
+            val.clone(),
+        )),
+    }
+}
+
+fn identifiers(form: &LispVal, ids: &[LispVal]) -> LispResult<Vec<String>> {
+    ids.iter()
+        .map(|id| match id {
+            LispVal::Atom(s) => Ok(s.to_string()),
+            _ => Err(LispError::BadSpecialForm(
+                "Invalid import set".to_string(),
+                form.clone(),
+            )),
+        })
+        .collect()
+}
+
+fn missing_identifier(form: &LispVal, id: &str) -> LispError {
+    LispError::BadSpecialForm(
+        format!("import: identifier {} is not exported", id),
+        form.clone(),
+    )
+}
+
+/// Resolves an import set such as `(prefix (only (scheme base) car cdr) base:)`
+/// to the bindings it makes visible.
+fn resolve_import_set(env: &Env, set: &LispVal) -> LispResult<Bindings> {
+    let xs = match set {
+        LispVal::List(xs) => xs,
+        _ => {
+            return Err(LispError::BadSpecialForm(
+                "Invalid import set".to_string(),
+                set.clone(),
+            ))
+        }
+    };
+    match &xs[..] {
+        [LispVal::Atom(s), inner, ids @ ..] if s == "only" => {
+            let mut bindings = resolve_import_set(env, inner)?;
+            let mut result = Bindings::new();
+            for id in identifiers(set, ids)? {
+                let val = bindings
+                    .remove(&id)
+                    .ok_or_else(|| missing_identifier(set, &id))?;
+                result.insert(id, val);
+            }
+            Ok(result)
+        }
+        [LispVal::Atom(s), inner, ids @ ..] if s == "except" => {
+            let mut bindings = resolve_import_set(env, inner)?;
+            for id in identifiers(set, ids)? {
+                bindings
+                    .remove(&id)
+                    .ok_or_else(|| missing_identifier(set, &id))?;
+            }
+            Ok(bindings)
+        }
+        [LispVal::Atom(s), inner, LispVal::Atom(prefix)] if s == "prefix" => {
+            let bindings = resolve_import_set(env, inner)?;
+            Ok(bindings
+                .into_iter()
+                .map(|(id, val)| (format!("{}{}", prefix, id), val))
+                .collect())
+        }
+        [LispVal::Atom(s), inner, renames @ ..] if s == "rename" => {
+            let mut bindings = resolve_import_set(env, inner)?;
+            let mut renamed = Bindings::new();
+            for rename in renames {
+                match rename {
+                    LispVal::List(pair) => match &pair[..] {
+                        [LispVal::Atom(from), LispVal::Atom(to)] => {
+                            let val = bindings
+                                .remove(from)
+                                .ok_or_else(|| missing_identifier(set, from))?;
+                            renamed.insert(to.to_string(), val);
+                        }
+                        _ => {
+                            return Err(LispError::BadSpecialForm(
+                                "Invalid import set".to_string(),
+                                set.clone(),
+                            ))
+                        }
+                    },
+                    _ => {
+                        return Err(LispError::BadSpecialForm(
+                            "Invalid import set".to_string(),
+                            set.clone(),
+                        ))
+                    }
+                }
+            }
+            bindings.extend(renamed);
+            Ok(bindings)
+        }
+        _ => {
+            let name = library_name(set)?;
+            let library = env.libraries.find(env, &name)?;
+            Ok(library.exports.clone())
+        }
+    }
+}
+
+/// `(import <import set> ...)`: binds everything named by the import sets in `env`.
+pub fn import(env: &Env, sets: &[LispVal]) -> LispResult<LispVal> {
+    for set in sets {
+        for (id, val) in resolve_import_set(env, set)? {
+            env.bind(&id, val);
+        }
+    }
+    Ok(LispVal::Void)
+}
+
+/// `(define-library <name> <declaration> ...)`: evaluates the library body in
+/// its own environment and registers the exported bindings.
+pub fn define_library(env: &Env, name: &LispVal, declarations: &[LispVal]) -> LispResult<LispVal> {
+    let name = library_name(name)?;
+    let library_env = Env::new_sharing(env, Bindings::new());
+    let mut exports = vec![];
+
+    for declaration in declarations {
+        let bad_declaration = || {
+            LispError::BadSpecialForm(
+                "define-library: invalid library declaration".to_string(),
+                declaration.clone(),
+            )
+        };
+        let xs = match declaration {
+            LispVal::List(xs) => xs,
+            _ => return Err(bad_declaration()),
+        };
+        match &xs[..] {
+            [LispVal::Atom(s), specs @ ..] if s == "export" => {
+                for spec in specs {
+                    match spec {
+                        LispVal::Atom(id) => exports.push((id.to_string(), id.to_string())),
+                        LispVal::List(rename) => match &rename[..] {
+                            [LispVal::Atom(r), LispVal::Atom(from), LispVal::Atom(to)]
+                                if r == "rename" =>
+                            {
+                                exports.push((from.to_string(), to.to_string()))
+                            }
+                            _ => return Err(bad_declaration()),
+                        },
+                        _ => return Err(bad_declaration()),
+                    }
+                }
+            }
+            [LispVal::Atom(s), sets @ ..] if s == "import" => {
+                import(&library_env, sets)?;
+            }
+            [LispVal::Atom(s), body @ ..] if s == "begin" => {
+                for expr in body {
+                    eval(&library_env, expr)?;
+                }
+            }
+            [LispVal::Atom(s), paths @ ..] if s == "include" || s == "include-ci" => {
+                for (path, body) in include_forms(&library_env, paths, s == "include-ci")? {
+                    library_env.sources.with_current(path, || {
+                        body.iter()
+                            .try_for_each(|expr| eval(&library_env, expr).map(|_| ()))
+                    })?;
+                }
+            }
+            _ => return Err(bad_declaration()),
+        }
+    }
+
+    let mut bindings = Bindings::new();
+    for (internal, external) in exports {
+        let val = library_env.lookup_local(&internal).ok_or_else(|| {
+            LispError::UnboundVar(
+                format!(
+                    "define-library: exported identifier is not defined in {}",
+                    format_name(&name)
+                ),
+                internal.to_string(),
+            )
+        })?;
+        bindings.insert(external, val);
+    }
+
+    env.libraries.register(Library {
+        name,
+        exports: bindings,
+    });
+    Ok(LispVal::Void)
+}
@@ -0,0 +1,5 @@
+mod library;
+
+pub use library::{
+    define_library, import, library_path, Libraries, Library, LibraryLoader, NoLoader,
+};
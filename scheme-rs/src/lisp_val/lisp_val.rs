@@ -1,11 +1,18 @@
-use num::complex::Complex64;
-use num::rational::Rational64;
+use num::complex::{Complex, Complex64};
+use num::{BigInt, BigRational};
+use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 use uuid::Uuid;
 
 use crate::environment::Env;
 use crate::error::LispResult;
+use crate::port::Port;
+use crate::printer::{print, Mode};
+use crate::random::RandomSource;
+
+use super::random_proc::RandomProc;
+use super::record::{Record, RecordProc, RecordType};
 
 // TODO: Constructor funcs for IFunc & EnvCtx?
 
@@ -28,6 +35,33 @@ pub fn prim_func(name: String, func: fn(Vec<LispVal>) -> LispResult<LispVal>) ->
     LispVal::PrimitiveFunc(PrimitiveFunc { name, func })
 }
 
+/// A primitive which needs the environment it's called from, e.g. for `eval`,
+/// or to call back into Scheme procedures with `eval::apply`
+#[derive(Debug, Clone)]
+pub struct IOFunc {
+    pub name: String,
+    pub func: fn(&Env, Vec<LispVal>) -> LispResult<LispVal>,
+}
+
+impl IOFunc {
+    pub fn new(func: fn(&Env, Vec<LispVal>) -> LispResult<LispVal>, name: String) -> Self {
+        Self { func, name }
+    }
+    pub fn apply(&self, env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+        (self.func)(env, args)
+    }
+}
+
+impl PartialEq for IOFunc {
+    fn eq(&self, other: &IOFunc) -> bool {
+        self.name == other.name && std::ptr::fn_addr_eq(self.func, other.func)
+    }
+}
+
+pub fn io_func(name: String, func: fn(&Env, Vec<LispVal>) -> LispResult<LispVal>) -> LispVal {
+    LispVal::IOFunc(IOFunc { name, func })
+}
+
 #[derive(Clone)]
 pub struct Func {
     pub name: String,
@@ -84,106 +118,47 @@ pub enum LispVal {
     Atom(String),
     List(Rc<Vec<LispVal>>),
     DottedList(Rc<Vec<LispVal>>, Rc<LispVal>),
-    Vector(Rc<Vec<LispVal>>),
+    Vector(Rc<RefCell<Vec<LispVal>>>),
+    Bytevector(Rc<RefCell<Vec<u8>>>),
     Integer(i64),
+    /// An integer too big for an `i64` (see `numbers::from_bigint`)
+    BigInteger(Rc<BigInt>),
     Float(f64),
     Complex(Complex64),
-    Rational(Rational64),
+    /// Always has a denominator other than 1 (see `numbers::from_big_rational`)
+    Rational(Rc<BigRational>),
+    /// A complex number with rational parts, which always has an imaginary
+    /// part other than 0 (see `numbers::from_exact_complex`)
+    ExactComplex(Rc<Complex<BigRational>>),
     String(String),
     Char(char), // TODO: Need this?
     PrimitiveFunc(PrimitiveFunc),
+    IOFunc(IOFunc),
     Func(Func),
+    Record(Record),
+    RecordType(Rc<RecordType>),
+    RecordProc(RecordProc),
+    RandomProc(RandomProc),
+    Environment(Env),
+    Port(Port),
+    RandomSource(RandomSource),
     Bool(bool),
     Quote(Rc<LispVal>),
     QuasiQuote(Rc<LispVal>),
     Unquote(Rc<LispVal>),
     UnquoteSplicing(Rc<LispVal>),
+    /// `#n=` followed by the datum it labels, which only the reader produces
+    /// (see `parser::canonicalize`)
+    DatumLabel(u64, Rc<LispVal>),
+    /// `#n#`, a reference to the datum labelled `n`
+    DatumRef(u64),
     Nil,
+    Eof,
     Void,
 }
 
 impl fmt::Display for LispVal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fn format_list(xs: &[LispVal]) -> String {
-            let result = xs
-                .iter()
-                .map(format_helper)
-                .collect::<Vec<String>>()
-                .join(" ");
-            result
-        }
-
-        fn format_number(n: &LispVal) -> String {
-            match n {
-                LispVal::Integer(n) => format!("{}", n),
-                LispVal::Float(n) => format!("{}", n),
-                LispVal::Rational(r) => {
-                    if *r.denom() == 0 {
-                        format!("{}", r.numer())
-                    } else {
-                        format!("{}/{}", r.numer(), r.denom())
-                    }
-                }
-                LispVal::Complex(c) => {
-                    if c.im == 0.0 {
-                        format!("{}", c.re)
-                    } else {
-                        format!("{}+{}i", c.re, c.im)
-                    }
-                }
-                _ => unreachable!(),
-            }
-        }
-
-        fn format_helper(val: &LispVal) -> String {
-            // TODO: Better number formatting
-            match val {
-                LispVal::Atom(s) => s.clone(),
-                LispVal::List(xs) => format!("({})", format_list(xs)),
-                LispVal::DottedList(h, t) => format!("({} . {})", format_list(h), t),
-                LispVal::Vector(xs) => format!("#({})", format_list(xs)),
-                n @ LispVal::Integer(_) => format_number(n),
-                n @ LispVal::Float(_) => format_number(n),
-                n @ LispVal::Complex(_) => format_number(n),
-                n @ LispVal::Rational(_) => format_number(n),
-                LispVal::String(s) => format!("\"{}\"", s),
-                LispVal::Char(c) => format_char(c),
-                LispVal::PrimitiveFunc(f) => format!("#<procedure:{}>", f.name),
-                LispVal::Func(f) => format!("#<procedure:{}>", f.name),
-                LispVal::Nil => "Nil".to_owned(),
-                LispVal::Bool(true) => "#t".to_owned(),
-                LispVal::Bool(false) => "#f".to_owned(),
-                LispVal::Quote(v) => format!("'{}", v),
-                LispVal::QuasiQuote(v) => format!("`{}", v),
-                LispVal::UnquoteSplicing(v) => format!(",@{}", v),
-                LispVal::Unquote(v) => format!(",{}", v),
-                LispVal::Void => "".to_owned(),
-            }
-        }
-        fn format_char(c: &char) -> String {
-            let cs = format!("{}", c);
-            let s = match c.to_digit(10) {
-                Some(27) => "altmode",
-                Some(31) => "backnext",
-                Some(8) => "backspace",
-                Some(26) => "call",
-                Some(10) => "linefeed",
-                Some(12) => "page",
-                Some(13) => "return",
-                Some(127) => "rubout",
-                Some(9) => "tab",
-                _ => {
-                    if *c == '\n' {
-                        "newline"
-                    } else if *c == ' ' {
-                        "space"
-                    } else {
-                        cs.as_str()
-                    }
-                }
-            };
-            format!("#\\{}", s)
-        }
-        write!(f, "{}", format_helper(self))
+        write!(f, "{}", print(self, Mode::Write))
     }
 }
@@ -1,4 +1,8 @@
 mod lisp_val;
+mod random_proc;
+mod record;
 #[cfg(test)]
 mod tests;
-pub use lisp_val::{prim_func, Func, LispVal, PrimitiveFunc};
+pub use lisp_val::{io_func, prim_func, Func, IOFunc, LispVal, PrimitiveFunc};
+pub use random_proc::{RandomProc, RandomProcKind};
+pub use record::{Record, RecordProc, RecordProcKind, RecordType};
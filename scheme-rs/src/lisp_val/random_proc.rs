@@ -0,0 +1,58 @@
+use num::Signed;
+
+use crate::error::{Arity, LispError, LispResult};
+use crate::numbers::{from_bigint, to_bigint};
+use crate::random::RandomSource;
+
+use super::lisp_val::LispVal;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RandomProcKind {
+    Integers,
+    Reals,
+}
+
+/// A procedure returned by `random-source-make-integers` or
+/// `random-source-make-reals`, which draws from its own source rather than
+/// `default-random-source`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RandomProc {
+    pub name: String,
+    pub source: RandomSource,
+    pub kind: RandomProcKind,
+}
+
+impl RandomProc {
+    pub fn new(name: String, source: RandomSource, kind: RandomProcKind) -> Self {
+        Self { name, source, kind }
+    }
+
+    fn check_arity(&self, args: &[LispVal], n: i8) -> LispResult<()> {
+        if args.len() == n as usize {
+            Ok(())
+        } else {
+            let len = i8::try_from(args.len())
+                .map_err(|_| LispError::GenericError("weird argument length".to_string()))?;
+            Err(LispError::NumArgs(Arity::MinMax(n, n), len, args.to_vec()))
+        }
+    }
+
+    pub fn apply(&self, args: Vec<LispVal>) -> LispResult<LispVal> {
+        match self.kind {
+            RandomProcKind::Integers => {
+                self.check_arity(&args, 1)?;
+                match to_bigint(&args[0]) {
+                    Some(n) if n.is_positive() => Ok(from_bigint(self.source.integer(&n))),
+                    _ => Err(LispError::GenericError(format!(
+                        "{}: contract violation\nexpected: exact-positive-integer?\ngiven: {}\nargument position: 1st",
+                        self.name, args[0]
+                    ))),
+                }
+            }
+            RandomProcKind::Reals => {
+                self.check_arity(&args, 0)?;
+                Ok(LispVal::Float(self.source.real()))
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use uuid::Uuid;
+
+use crate::error::{Arity, LispError, LispResult};
+use crate::lisp_val::LispVal;
+
+/// The descriptor shared by every instance of a type created with
+/// `define-record-type`.
+#[derive(Debug)]
+pub struct RecordType {
+    pub name: String,
+    pub id: u128,
+    pub fields: Vec<String>,
+}
+
+impl RecordType {
+    pub fn new(name: String, fields: Vec<String>) -> Self {
+        let id = Uuid::new_v4().as_u128();
+        Self { name, id, fields }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn field_names(&self) -> &[String] {
+        &self.fields
+    }
+
+    pub fn field_index(&self, field: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f == field)
+    }
+
+    /// The type name without the conventional `<...>` brackets, used when printing.
+    pub fn display_name(&self) -> &str {
+        self.name
+            .strip_prefix('<')
+            .and_then(|name| name.strip_suffix('>'))
+            .unwrap_or(&self.name)
+    }
+}
+
+impl PartialEq for RecordType {
+    fn eq(&self, other: &RecordType) -> bool {
+        self.id == other.id
+    }
+}
+
+#[derive(Clone)]
+pub struct Record {
+    pub record_type: Rc<RecordType>,
+    pub fields: Rc<RefCell<Vec<LispVal>>>,
+}
+
+impl Record {
+    pub fn new(record_type: Rc<RecordType>, fields: Vec<LispVal>) -> Self {
+        Self {
+            record_type,
+            fields: Rc::new(RefCell::new(fields)),
+        }
+    }
+
+    pub fn record_type_descriptor(&self) -> &Rc<RecordType> {
+        &self.record_type
+    }
+
+    pub fn is_a(&self, record_type: &Rc<RecordType>) -> bool {
+        Rc::ptr_eq(&self.record_type, record_type)
+    }
+
+    pub fn field(&self, field: &str) -> Option<LispVal> {
+        self.record_type
+            .field_index(field)
+            .map(|index| self.fields.borrow()[index].clone())
+    }
+
+    pub fn values(&self) -> Vec<LispVal> {
+        self.fields.borrow().clone()
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("record_type", &self.record_type.name)
+            .finish()
+    }
+}
+
+// Records are compared by identity, so `eq?` only holds for the very same instance
+impl PartialEq for Record {
+    fn eq(&self, other: &Record) -> bool {
+        Rc::ptr_eq(&self.fields, &other.fields)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RecordProcKind {
+    /// Indices of the fields initialised by each constructor argument, in order
+    Constructor(Vec<usize>),
+    Predicate,
+    Accessor(usize),
+    Modifier(usize),
+}
+
+/// One of the procedures generated by `define-record-type`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecordProc {
+    pub name: String,
+    pub record_type: Rc<RecordType>,
+    pub kind: RecordProcKind,
+}
+
+impl RecordProc {
+    pub fn new(name: String, record_type: Rc<RecordType>, kind: RecordProcKind) -> Self {
+        Self {
+            name,
+            record_type,
+            kind,
+        }
+    }
+
+    fn check_arity(&self, args: &[LispVal], n: usize) -> LispResult<()> {
+        if args.len() == n {
+            Ok(())
+        } else {
+            let n = i8::try_from(n)
+                .map_err(|_| LispError::GenericError("weird argument length".to_string()))?;
+            let len = i8::try_from(args.len())
+                .map_err(|_| LispError::GenericError("weird argument length".to_string()))?;
+            Err(LispError::NumArgs(Arity::MinMax(n, n), len, args.to_vec()))
+        }
+    }
+
+    fn instance<'a>(&self, val: &'a LispVal) -> LispResult<&'a Record> {
+        match val {
+            LispVal::Record(record) if record.is_a(&self.record_type) => Ok(record),
+            _ => Err(LispError::GenericError(format!(
+                "{}: contract violation\nexpected: {}?\ngiven: {}",
+                self.name,
+                self.record_type.display_name(),
+                val
+            ))),
+        }
+    }
+
+    pub fn apply(&self, args: Vec<LispVal>) -> LispResult<LispVal> {
+        match &self.kind {
+            RecordProcKind::Constructor(indices) => {
+                self.check_arity(&args, indices.len())?;
+                let mut fields = vec![LispVal::Bool(false); self.record_type.fields.len()];
+                for (index, arg) in indices.iter().zip(args) {
+                    fields[*index] = arg;
+                }
+                Ok(LispVal::Record(Record::new(
+                    self.record_type.clone(),
+                    fields,
+                )))
+            }
+            RecordProcKind::Predicate => {
+                self.check_arity(&args, 1)?;
+                match &args[0] {
+                    LispVal::Record(record) => Ok(LispVal::Bool(record.is_a(&self.record_type))),
+                    _ => Ok(LispVal::Bool(false)),
+                }
+            }
+            RecordProcKind::Accessor(index) => {
+                self.check_arity(&args, 1)?;
+                let record = self.instance(&args[0])?;
+                let val = record.fields.borrow()[*index].clone();
+                Ok(val)
+            }
+            RecordProcKind::Modifier(index) => {
+                self.check_arity(&args, 2)?;
+                let record = self.instance(&args[0])?;
+                record.fields.borrow_mut()[*index] = args[1].clone();
+                Ok(LispVal::Void)
+            }
+        }
+    }
+}
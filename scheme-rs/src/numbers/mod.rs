@@ -1,4 +1,7 @@
 mod numbers;
 
-pub use numbers::cast;
-pub use numbers::is_num;
+pub use numbers::{
+    cast, float_to_exact, from_big_rational, from_bigint, from_exact_complex, is_exact, is_num,
+    make_polar, make_rectangular, to_big_rational, to_bigint, to_complex64, to_exact_complex,
+    to_f64,
+};
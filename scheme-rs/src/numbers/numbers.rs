@@ -1,5 +1,7 @@
-use num::complex::Complex64;
-use num::rational::Rational64;
+use std::rc::Rc;
+
+use num::complex::{Complex, Complex64};
+use num::{BigInt, BigRational, ToPrimitive, Zero};
 
 use crate::{
     error::{LispError, LispResult},
@@ -9,80 +11,163 @@ use crate::{
 pub fn is_num(val: &LispVal) -> bool {
     matches!(
         val,
-        LispVal::Integer(_) | LispVal::Float(_) | LispVal::Rational(_) | LispVal::Complex(_)
+        LispVal::Integer(_)
+            | LispVal::BigInteger(_)
+            | LispVal::Float(_)
+            | LispVal::Rational(_)
+            | LispVal::ExactComplex(_)
+            | LispVal::Complex(_)
+    )
+}
+
+/// Whether a number is exact, i.e. an integer, a rational or a complex
+/// number with rational parts. Floats and other complex numbers are inexact.
+pub fn is_exact(val: &LispVal) -> bool {
+    matches!(
+        val,
+        LispVal::Integer(_)
+            | LispVal::BigInteger(_)
+            | LispVal::Rational(_)
+            | LispVal::ExactComplex(_)
     )
 }
 
-fn rational_cast(n: &Rational64) -> Option<f64> {
-    if *n.denom() == 0 {
-        None
+/// The exact value of a float, which has none if it's infinite or NaN
+pub fn float_to_exact(n: f64) -> Option<LispVal> {
+    BigRational::from_float(n).map(from_big_rational)
+}
+
+/// An exact integer, kept as a fixnum if it fits in an `i64`. Integers are
+/// only ever `BigInteger`s when they don't fit, so results should always be
+/// made with this.
+pub fn from_bigint(n: BigInt) -> LispVal {
+    match n.to_i64() {
+        Some(n) => LispVal::Integer(n),
+        None => LispVal::BigInteger(Rc::new(n)),
+    }
+}
+
+/// An exact rational, which is an integer if its denominator is 1
+pub fn from_big_rational(r: BigRational) -> LispVal {
+    if r.is_integer() {
+        from_bigint(r.to_integer())
     } else {
-        Some((*n.numer() as f64) / (*n.denom() as f64))
+        LispVal::Rational(Rc::new(r))
+    }
+}
+
+/// An exact complex number, which is rational if its imaginary part is 0
+pub fn from_exact_complex(c: Complex<BigRational>) -> LispVal {
+    if c.im.is_zero() {
+        from_big_rational(c.re)
+    } else {
+        LispVal::ExactComplex(Rc::new(c))
+    }
+}
+
+/// The complex number `re + im i`, which is exact if both parts are
+pub fn make_rectangular(re: &LispVal, im: &LispVal) -> Option<LispVal> {
+    match (to_big_rational(re), to_big_rational(im)) {
+        (Some(re), Some(im)) => Some(from_exact_complex(Complex::new(re, im))),
+        _ => Some(LispVal::Complex(Complex64::new(to_f64(re)?, to_f64(im)?))),
+    }
+}
+
+/// The complex number with the given magnitude and angle. Only an angle of
+/// exactly 0 gives an exact result.
+pub fn make_polar(magnitude: &LispVal, angle: &LispVal) -> Option<LispVal> {
+    if *angle == LispVal::Integer(0) && to_big_rational(magnitude).is_some() {
+        return Some(magnitude.clone());
+    }
+    Some(LispVal::Complex(Complex64::from_polar(
+        to_f64(magnitude)?,
+        to_f64(angle)?,
+    )))
+}
+
+/// The value of an exact integer
+pub fn to_bigint(val: &LispVal) -> Option<BigInt> {
+    match val {
+        LispVal::Integer(n) => Some(BigInt::from(*n)),
+        LispVal::BigInteger(n) => Some((**n).clone()),
+        _ => None,
+    }
+}
+
+/// The value of an exact number as a rational
+pub fn to_big_rational(val: &LispVal) -> Option<BigRational> {
+    match val {
+        LispVal::Rational(r) => Some((**r).clone()),
+        val => to_bigint(val).map(BigRational::from_integer),
+    }
+}
+
+/// The value of an exact number as a complex number with rational parts
+pub fn to_exact_complex(val: &LispVal) -> Option<Complex<BigRational>> {
+    match val {
+        LispVal::ExactComplex(c) => Some((**c).clone()),
+        val => to_big_rational(val).map(|r| Complex::new(r, BigRational::zero())),
+    }
+}
+
+/// The nearest float to a real number
+pub fn to_f64(val: &LispVal) -> Option<f64> {
+    match val {
+        LispVal::Integer(n) => Some(*n as f64),
+        LispVal::BigInteger(n) => n.to_f64(),
+        LispVal::Rational(r) => r.to_f64(),
+        LispVal::Float(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// The nearest inexact complex number to a number
+pub fn to_complex64(val: &LispVal) -> Option<Complex64> {
+    match val {
+        LispVal::Complex(c) => Some(*c),
+        LispVal::ExactComplex(c) => Some(Complex64::new(c.re.to_f64()?, c.im.to_f64()?)),
+        val => to_f64(val).map(|n| Complex64::new(n, 0.0)),
+    }
+}
+
+// How far up the numeric tower a number is. Of any two numbers, the one
+// lower down can be converted to the type of the other, except that an exact
+// complex number and a float both have to become an inexact complex number.
+fn rank(val: &LispVal) -> Option<u8> {
+    match val {
+        LispVal::Integer(_) => Some(0),
+        LispVal::BigInteger(_) => Some(1),
+        LispVal::Rational(_) => Some(2),
+        LispVal::ExactComplex(_) => Some(3),
+        LispVal::Float(_) => Some(4),
+        LispVal::Complex(_) => Some(5),
+        _ => None,
+    }
+}
+
+// Converts `val` to the type at `rank`, which is no lower than its own
+fn promote(val: &LispVal, rank: u8) -> Option<LispVal> {
+    match rank {
+        1 => to_bigint(val).map(|n| LispVal::BigInteger(Rc::new(n))),
+        2 => to_big_rational(val).map(|r| LispVal::Rational(Rc::new(r))),
+        3 => to_exact_complex(val).map(|c| LispVal::ExactComplex(Rc::new(c))),
+        4 => to_f64(val).map(LispVal::Float),
+        5 => to_complex64(val).map(LispVal::Complex),
+        _ => Some(val.clone()),
     }
 }
 
+/// Converts two numbers to the same type, e.g. an integer and a float to
+/// two floats, so they can be added, compared and so on
 pub fn cast(m: &LispVal, n: &LispVal) -> LispResult<(LispVal, LispVal)> {
-    match (m, n) {
-        (a @ LispVal::Integer(_), b @ LispVal::Integer(_)) => Ok((a.clone(), b.clone())),
-        (a @ LispVal::Rational(_), b @ LispVal::Rational(_)) => Ok((a.clone(), b.clone())),
-        (a @ LispVal::Float(_), b @ LispVal::Float(_)) => Ok((a.clone(), b.clone())),
-        (a @ LispVal::Complex(_), b @ LispVal::Complex(_)) => Ok((a.clone(), b.clone())),
-        // -- Integer
-        (LispVal::Integer(a), b @ LispVal::Rational(_)) => {
-            Ok((LispVal::Rational(Rational64::new(*a, 1)), b.clone()))
-        }
-        (LispVal::Integer(a), b @ LispVal::Float(_)) => {
-            // TODO: Conversion :S
-            Ok((LispVal::Float(*a as f64), b.clone()))
-        }
-        // TODO: Conversion :S
-        (LispVal::Integer(a), b @ LispVal::Complex(_)) => {
-            Ok((LispVal::Complex(Complex64::from(*a as f64)), b.clone()))
-        }
-        // -- Rational
-        (a @ LispVal::Rational(_), LispVal::Integer(b)) => {
-            Ok((a.clone(), LispVal::Rational(Rational64::new(*b, 1))))
-        }
-        (LispVal::Rational(a), b @ LispVal::Float(_)) => match rational_cast(a) {
-            None => Err(LispError::GenericError(
-                "Unexpected error converting rational".to_string(),
-            )),
-            Some(n) => Ok((LispVal::Float(n), b.clone())),
-        },
-        (LispVal::Rational(a), b @ LispVal::Complex(_)) => match rational_cast(a) {
-            None => Err(LispError::GenericError(
-                "Unexpected error converting rational".to_string(),
-            )),
-            Some(n) => Ok((LispVal::Complex(Complex64::new(n, 0.0)), b.clone())),
-        },
-        // -- Float
-        (a @ LispVal::Float(_), LispVal::Rational(b)) => match rational_cast(b) {
-            None => Err(LispError::GenericError(
-                "Unexpected error converting rational".to_string(),
-            )),
-            Some(n) => Ok((a.clone(), LispVal::Float(n))),
-        },
-        // TODO: Cast
-        (a @ LispVal::Float(_), LispVal::Integer(b)) => Ok((a.clone(), LispVal::Float(*b as f64))),
-        (LispVal::Float(a), b @ LispVal::Complex(_)) => {
-            Ok((LispVal::Complex(Complex64::new(*a, 0.0)), b.clone()))
-        }
-        // -- Complex
-        (a @ LispVal::Complex(_), LispVal::Rational(b)) => match rational_cast(b) {
-            None => Err(LispError::GenericError(
-                "Unexpected error converting rational".to_string(),
-            )),
-            Some(n) => Ok((a.clone(), LispVal::Complex(Complex64::new(n, 0.0)))),
-        },
-        (a @ LispVal::Complex(_), LispVal::Float(b)) => {
-            Ok((a.clone(), LispVal::Complex(Complex64::new(*b, 0.0))))
-        }
-        // TODO: Cast
-        (a @ LispVal::Complex(_), LispVal::Integer(b)) => {
-            Ok((a.clone(), LispVal::Complex(Complex64::new(*b as f64, 0.0))))
-        }
-        _ => Err(LispError::GenericError(
-            "Unexpected error in cast".to_string(),
-        )),
+    let cast_error = || LispError::GenericError("Unexpected error in cast".to_string());
+    let rank = match (rank(m), rank(n)) {
+        (Some(3), Some(4)) | (Some(4), Some(3)) => 5,
+        (Some(a), Some(b)) => a.max(b),
+        _ => return Err(cast_error()),
+    };
+    match (promote(m, rank), promote(n, rank)) {
+        (Some(m), Some(n)) => Ok((m, n)),
+        _ => Err(cast_error()),
     }
 }
@@ -3,4 +3,6 @@ mod parser;
 #[cfg(test)]
 mod tests;
 
-pub use parser::{expression, expression_list};
+pub use parser::{
+    canonicalize, datum, datum_list, expression, expression_list, read_datum, string_to_number,
+};
@@ -1,19 +1,25 @@
 extern crate nom;
 
 use crate::lisp_val::LispVal;
+use crate::numbers::{
+    float_to_exact, from_big_rational, from_bigint, make_polar, make_rectangular, to_bigint,
+    to_complex64, to_f64,
+};
 
 use nom::{
     branch::alt,
+    bytes::complete::tag,
     character::complete::{
         char, digit0, digit1, hex_digit0, hex_digit1, none_of, oct_digit0, oct_digit1, one_of,
     },
-    combinator::{opt, peek},
+    combinator::{fail, opt, peek, recognize, success},
     multi::{many0, many1},
-    sequence::{preceded, separated_pair, tuple},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
     IResult, Parser,
 };
-use num::{complex::Complex64, Rational64};
+use num::{BigInt, BigRational, Zero};
 
+#[derive(Clone, Copy)]
 enum Base {
     Binary,
     Decimal,
@@ -39,74 +45,61 @@ fn parse_base(input: &str) -> IResult<&str, Base> {
 -- Integer
 --------------*/
 
+// Integers of any length are read exactly, so they never overflow
+fn big_digits(digits: &str, radix: u32) -> BigInt {
+    BigInt::parse_bytes(digits.as_bytes(), radix).unwrap()
+}
+
 fn integer_decimal(input: &str) -> IResult<&str, LispVal> {
     integer_helper(input, |input| {
-        digit1.map(|n: &str| n.parse::<i64>().unwrap()).parse(input)
+        digit1.map(|n: &str| big_digits(n, 10)).parse(input)
     })
 }
 fn integer_octal(input: &str) -> IResult<&str, LispVal> {
     integer_helper(input, |input| {
-        oct_digit1
-            .map(|n: &str| i64::from_str_radix(n, 8).unwrap())
-            .parse(input)
+        oct_digit1.map(|n: &str| big_digits(n, 8)).parse(input)
     })
 }
 fn integer_hex(input: &str) -> IResult<&str, LispVal> {
     integer_helper(input, |input| {
-        hex_digit1
-            .map(|n: &str| i64::from_str_radix(n, 16).unwrap())
-            .parse(input)
+        hex_digit1.map(|n: &str| big_digits(n, 16)).parse(input)
     })
 }
 fn integer_binary(input: &str) -> IResult<&str, LispVal> {
     integer_helper(input, |input| {
         many1(one_of("01"))
-            .map(|n| i64::from_str_radix(&n.iter().collect::<String>(), 2).unwrap())
+            .map(|n| big_digits(&n.iter().collect::<String>(), 2))
             .parse(input)
     })
 }
 
-fn integer_base(input: &str) -> IResult<&str, LispVal> {
-    let (input, (_, base)) = tuple((char('#'), parse_base)).parse(input)?;
-    match base {
-        Base::Binary => integer_binary(input),
-        Base::Decimal => integer_decimal(input),
-        Base::Octal => integer_octal(input),
-        Base::Hex => integer_hex(input),
-    }
-}
-
 fn positive_integer<F>(f: F) -> impl FnMut(&str) -> IResult<&str, LispVal>
 where
-    F: Fn(&str) -> IResult<&str, i64> + Clone,
+    F: Fn(&str) -> IResult<&str, BigInt> + Clone,
 {
     move |input| {
         let (input, n) = preceded(opt(char('+')), f.clone()).parse(input)?;
-        Ok((input, LispVal::Integer(n)))
+        Ok((input, from_bigint(n)))
     }
 }
 
 fn negative_integer<F>(f: F) -> impl FnMut(&str) -> IResult<&str, LispVal>
 where
-    F: Fn(&str) -> IResult<&str, i64> + Clone,
+    F: Fn(&str) -> IResult<&str, BigInt> + Clone,
 {
     move |input| {
         let (input, n) = preceded(char('-'), f.clone()).parse(input)?;
-        Ok((input, LispVal::Integer(-n)))
+        Ok((input, from_bigint(-n)))
     }
 }
 
 fn integer_helper<F>(input: &str, f: F) -> IResult<&str, LispVal>
 where
-    F: Fn(&str) -> IResult<&str, i64> + Clone,
+    F: Fn(&str) -> IResult<&str, BigInt> + Clone,
 {
     alt((negative_integer(f.clone()), positive_integer(f))).parse(input)
 }
 
-fn integer(input: &str) -> IResult<&str, LispVal> {
-    alt((integer_decimal, integer_base)).parse(input)
-}
-
 /*--------------
 -- Float
 --------------*/
@@ -118,6 +111,14 @@ where
     alt((negative_float(f.clone()), positive_float(f))).parse(input)
 }
 
+// The value of a run of digits, which may be too long for an integer type
+fn float_digits(digits: &str, radix: u32) -> f64 {
+    digits
+        .chars()
+        .filter_map(|d| d.to_digit(radix))
+        .fold(0.0, |n, d| n * radix as f64 + d as f64)
+}
+
 fn float_converter(m: f64, base: f64, size: i32) -> f64 {
     let mut size = size;
     let mut m = m;
@@ -129,37 +130,19 @@ fn float_converter(m: f64, base: f64, size: i32) -> f64 {
 }
 
 // TODO: DRY these... function or macro if absolutely necessary
-fn float_decimal(input: &str) -> IResult<&str, LispVal> {
-    float_helper(input, |input| {
-        separated_pair(digit1, char('.'), digit0)
-            .map(|(n, m): (&str, &str)| {
-                let n = n.parse::<i64>().unwrap();
-                let (size, m) = if m.is_empty() {
-                    (1, 0)
-                } else {
-                    let size = m.chars().count();
-                    (size, m.parse::<i64>().unwrap())
-                };
-                let base: f64 = 10.0;
-                n as f64 + float_converter(m as f64, base, size as i32)
-            })
-            .parse(input)
-    })
-}
-
 fn float_octal(input: &str) -> IResult<&str, LispVal> {
     float_helper(input, |input| {
         separated_pair(oct_digit1, char('.'), oct_digit0)
             .map(|(n, m)| {
-                let n = i64::from_str_radix(n, 8).unwrap();
+                let n = float_digits(n, 8);
                 let (size, m) = if m.is_empty() {
-                    (1, 0)
+                    (1, 0.0)
                 } else {
                     let size = m.chars().count();
-                    (size, i64::from_str_radix(m, 8).unwrap())
+                    (size, float_digits(m, 8))
                 };
                 let base: f64 = 8.0;
-                n as f64 + float_converter(m as f64, base, size as i32)
+                n + float_converter(m, base, size as i32)
             })
             .parse(input)
     })
@@ -169,15 +152,15 @@ fn float_hex(input: &str) -> IResult<&str, LispVal> {
     float_helper(input, |input| {
         separated_pair(hex_digit1, char('.'), hex_digit0)
             .map(|(n, m)| {
-                let n = i64::from_str_radix(n, 16).unwrap();
+                let n = float_digits(n, 16);
                 let (size, m) = if m.is_empty() {
-                    (1, 0)
+                    (1, 0.0)
                 } else {
                     let size = m.chars().count();
-                    (size, i64::from_str_radix(m, 16).unwrap())
+                    (size, float_digits(m, 16))
                 };
                 let base: f64 = 16.0;
-                n as f64 + float_converter(m as f64, base, size as i32)
+                n + float_converter(m, base, size as i32)
             })
             .parse(input)
     })
@@ -188,31 +171,21 @@ fn float_binary(input: &str) -> IResult<&str, LispVal> {
         separated_pair(many1(one_of("01")), char('.'), many0(one_of("01")))
             .map(|(n, m)| {
                 let n = n.iter().collect::<String>();
-                let n = i64::from_str_radix(&n, 2).unwrap();
+                let n = float_digits(&n, 2);
                 let m = m.iter().collect::<String>();
                 let (size, m) = if m.is_empty() {
-                    (1, 0)
+                    (1, 0.0)
                 } else {
                     let size = m.chars().count();
-                    (size, i64::from_str_radix(&m, 2).unwrap())
+                    (size, float_digits(&m, 2))
                 };
-                let base: i32 = 2;
-                n as f64 + ((m as f64) / base.pow(size as u32) as f64)
+                let base: f64 = 2.0;
+                n + float_converter(m, base, size as i32)
             })
             .parse(input)
     })
 }
 
-fn float_base(input: &str) -> IResult<&str, LispVal> {
-    let (input, base) = preceded(char('#'), parse_base).parse(input)?;
-    match base {
-        Base::Binary => float_binary(input),
-        Base::Decimal => float_decimal(input),
-        Base::Octal => float_octal(input),
-        Base::Hex => float_hex(input),
-    }
-}
-
 fn positive_float<F>(f: F) -> impl FnMut(&str) -> IResult<&str, LispVal>
 where
     F: Fn(&str) -> IResult<&str, f64> + Clone,
@@ -233,78 +206,250 @@ where
     }
 }
 
-fn float(input: &str) -> IResult<&str, LispVal> {
-    alt((float_decimal, float_base)).parse(input)
-}
-
 /*--------------
--- Complex
+-- Decimal
 --------------*/
 
-fn complex(input: &str) -> IResult<&str, LispVal> {
-    // Store the base for later if it's there, but don't consume it
-    // b/c float/integer will need it
-    let (input, base) = peek(opt(preceded(char('#'), parse_base))).parse(input)?;
-    let (input, m) = alt((float, integer)).parse(input)?;
-
-    // Just enforce that there is a +/-, because they're optional for
-    // the various float/integer parsers
-    let (input, _) = peek(alt((char('-'), char('+')))).parse(input)?;
-    let (input, n) = match base {
-        Some(Base::Binary) => alt((float_binary, integer_binary)).parse(input),
-        Some(Base::Decimal) => alt((float_decimal, integer_decimal)).parse(input),
-        Some(Base::Octal) => alt((float_octal, integer_octal)).parse(input),
-        Some(Base::Hex) => alt((float_hex, integer_hex)).parse(input),
-        None => alt((float_decimal, integer_decimal)).parse(input),
-    }?;
-    let (input, _) = char('i').parse(input)?;
-
-    match (m, n) {
-        (LispVal::Float(m), LispVal::Float(n)) => {
-            Ok((input, LispVal::Complex(Complex64::new(m, n))))
-        }
-        (LispVal::Float(m), LispVal::Integer(n)) => {
-            Ok((input, LispVal::Complex(Complex64::new(m, n as f64))))
-        }
-        (LispVal::Integer(m), LispVal::Float(n)) => {
-            Ok((input, LispVal::Complex(Complex64::new(m as f64, n))))
-        }
-        (LispVal::Integer(m), LispVal::Integer(n)) => {
-            Ok((input, LispVal::Complex(Complex64::new(m as f64, n as f64))))
-        }
-        _ => unreachable!(),
+// A decimal with its sign, e.g. `1.5`, `-.5`, `1.` or `6.02e23`
+fn decimal_text(input: &str) -> IResult<&str, &str> {
+    let exponent = || tuple((one_of("eE"), opt(one_of("+-")), digit1));
+    recognize(tuple((
+        opt(one_of("+-")),
+        alt((
+            recognize(tuple((digit1, char('.'), digit0, opt(exponent())))),
+            recognize(tuple((char('.'), digit1, opt(exponent())))),
+            recognize(tuple((digit1, exponent()))),
+        )),
+    )))
+    .parse(input)
+}
+
+// The largest power of ten an exact decimal may be scaled by, so that e.g.
+// `#e1e1000000000` is rejected rather than exhausting memory
+const MAX_EXACT_EXPONENT: u32 = 1_000_000;
+
+// The exact value of a decimal like `-1.25` or `1e-3`, rather than of the
+// float nearest to it
+fn exact_decimal(text: &str) -> Option<BigRational> {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (negative, digits) = match mantissa.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let numer = BigInt::parse_bytes(format!("{}{}", whole, fraction).as_bytes(), 10)?;
+    let scale = exponent.checked_sub(i32::try_from(fraction.len()).ok()?)?;
+    if scale.unsigned_abs() > MAX_EXACT_EXPONENT {
+        return None;
+    }
+    let power =
+        BigRational::from_integer(num::pow(BigInt::from(10), scale.unsigned_abs() as usize));
+    let r = BigRational::from_integer(numer);
+    let r = if scale < 0 { r / power } else { r * power };
+    Some(if negative { -r } else { r })
+}
+
+// A decimal, which is read exactly for `#e` and otherwise as the nearest float
+fn decimal(exact: bool, input: &str) -> IResult<&str, LispVal> {
+    let (rest, text) = decimal_text(input)?;
+    let n = if exact {
+        exact_decimal(text).map(from_big_rational)
+    } else {
+        text.parse().ok().map(LispVal::Float)
+    };
+    match n {
+        Some(n) => Ok((rest, n)),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Float,
+        ))),
     }
 }
 
+// Infinities and NaN, which always have a sign
+fn infnan(input: &str) -> IResult<&str, LispVal> {
+    alt((
+        tag("+inf.0").map(|_| LispVal::Float(f64::INFINITY)),
+        tag("-inf.0").map(|_| LispVal::Float(f64::NEG_INFINITY)),
+        alt((tag("+nan.0"), tag("-nan.0"))).map(|_| LispVal::Float(f64::NAN)),
+    ))
+    .parse(input)
+}
+
 /*--------------
 -- Rational
 --------------*/
 
-fn rational(input: &str) -> IResult<&str, LispVal> {
-    // Store the base for later if it's there, but don't consume it
-    // b/c float/integer will need it
-    let (input, base) = peek(opt(preceded(char('#'), parse_base))).parse(input)?;
-    let (input, m) = integer.parse(input)?;
+// A numerator and denominator, each read by `integer`
+fn ratio(integer: RealParser, input: &str) -> IResult<&str, LispVal> {
+    let (input, m) = integer(input)?;
 
     let (input, _) = char('/').parse(input)?;
     let (input, _) = peek(none_of("+-")).parse(input)?;
 
-    let (input, n) = match base {
-        Some(Base::Binary) => integer_binary.parse(input),
-        Some(Base::Decimal) => integer_decimal.parse(input),
-        Some(Base::Octal) => integer_octal.parse(input),
-        Some(Base::Hex) => integer_hex.parse(input),
-        None => integer_decimal.parse(input),
-    }?;
-
-    match (m, n) {
-        (LispVal::Integer(m), LispVal::Integer(n)) => {
-            Ok((input, LispVal::Rational(Rational64::new(m, n))))
-        }
+    let (input, n) = integer(input)?;
+
+    // Both parts are exact integers
+    let (m, n) = (to_bigint(&m).unwrap(), to_bigint(&n).unwrap());
+    if n.is_zero() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    Ok((input, from_big_rational(BigRational::new(m, n))))
+}
+
+/*--------------
+-- Complex
+--------------*/
+
+type RealParser = fn(&str) -> IResult<&str, LispVal>;
+
+// A real number in `base`, e.g. `-1/2`, `1.5e3` or `+inf.0`. Decimals are read
+// exactly if `exact` is set.
+fn real(base: Base, exact: bool, input: &str) -> IResult<&str, LispVal> {
+    let integer: RealParser = match base {
+        Base::Binary => integer_binary,
+        Base::Octal => integer_octal,
+        Base::Decimal => integer_decimal,
+        Base::Hex => integer_hex,
+    };
+    let float = move |input| match base {
+        Base::Binary => float_binary(input),
+        Base::Octal => float_octal(input),
+        Base::Decimal => decimal(exact, input),
+        Base::Hex => float_hex(input),
+    };
+    alt((infnan, move |input| ratio(integer, input), float, integer)).parse(input)
+}
+
+// The imaginary part of a complex number, which always has a sign and can
+// leave out a 1, e.g. `+2i`, `-1/2i` or `+i`
+fn imaginary(base: Base, exact: bool, input: &str) -> IResult<&str, LispVal> {
+    let (input, _) = peek(one_of("+-")).parse(input)?;
+    terminated(
+        alt((
+            move |input| real(base, exact, input),
+            one_of("+-").map(|sign| LispVal::Integer(if sign == '-' { -1 } else { 1 })),
+        )),
+        char('i'),
+    )
+    .parse(input)
+}
+
+// A real or complex number, e.g. `1.5`, `1/2+3i`, `-i` or `1@1.57`
+fn complex(base: Base, exact: bool, input: &str) -> IResult<&str, LispVal> {
+    let real = move |input| real(base, exact, input);
+    let imaginary = move |input| imaginary(base, exact, input);
+    alt((
+        imaginary.map(|im| make_rectangular(&LispVal::Integer(0), &im).unwrap()),
+        separated_pair(real, char('@'), real).map(|(m, a)| make_polar(&m, &a).unwrap()),
+        // Exact if both parts are, e.g. `1/2+1/3i`
+        pair(real, imaginary).map(|(re, im)| make_rectangular(&re, &im).unwrap()),
+        real,
+    ))
+    .parse(input)
+}
+
+/*--------------
+-- Exactness
+--------------*/
+
+#[derive(Clone, Copy)]
+enum Exactness {
+    Exact,
+    Inexact,
+}
+
+fn parse_exactness(input: &str) -> IResult<&str, Exactness> {
+    let (input, exactness) = preceded(char('#'), one_of("ei")).parse(input)?;
+    let exactness = match exactness {
+        'e' => Exactness::Exact,
+        'i' => Exactness::Inexact,
         _ => unreachable!(),
+    };
+    Ok((input, exactness))
+}
+
+// Converts `n` to the given exactness. Decimals are already read exactly for
+// `#e`, so only other floats, e.g. `#e#b1.1`, are converted here.
+fn with_exactness(n: LispVal, exactness: Exactness) -> Option<LispVal> {
+    match (exactness, n) {
+        (Exactness::Exact, LispVal::Float(f)) => float_to_exact(f),
+        (Exactness::Exact, LispVal::Complex(c)) => {
+            let (re, im) = (float_to_exact(c.re)?, float_to_exact(c.im)?);
+            make_rectangular(&re, &im)
+        }
+        (Exactness::Inexact, n @ (LispVal::Complex(_) | LispVal::ExactComplex(_))) => {
+            to_complex64(&n).map(LispVal::Complex)
+        }
+        (Exactness::Inexact, n) => to_f64(&n).map(LispVal::Float),
+        (Exactness::Exact, n) => Some(n),
+    }
+}
+
+// The radix and exactness prefixes, which can come in either order, e.g.
+// `#x#e` or `#e#x`
+fn prefix(input: &str) -> IResult<&str, (Option<Base>, Option<Exactness>)> {
+    let radix = || preceded(char('#'), parse_base);
+    alt((
+        pair(radix().map(Some), opt(parse_exactness)),
+        pair(parse_exactness, opt(radix())).map(|(exactness, base)| (base, Some(exactness))),
+        success((None, None)),
+    ))
+    .parse(input)
+}
+
+// Numbers end where a symbol would, so that e.g. `1+` isn't read as 1 and `+`
+pub(super) fn delimiter(input: &str) -> IResult<&str, ()> {
+    match input.chars().next() {
+        None => Ok((input, ())),
+        Some(c) if c.is_whitespace() || "()[]{}\";|".contains(c) => Ok((input, ())),
+        Some(_) => fail(input),
+    }
+}
+
+// A number in `base` unless it has a radix prefix
+fn number_in(base: Base, input: &str) -> IResult<&str, LispVal> {
+    let (rest, (radix, exactness)) = prefix(input)?;
+    let exact = matches!(exactness, Some(Exactness::Exact));
+    let (rest, n) = terminated(
+        move |input| complex(radix.unwrap_or(base), exact, input),
+        peek(delimiter),
+    )
+    .parse(rest)?;
+    match exactness {
+        None => Ok((rest, n)),
+        Some(exactness) => match with_exactness(n, exactness) {
+            Some(n) => Ok((rest, n)),
+            None => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            ))),
+        },
     }
 }
 
 pub fn number(input: &str) -> IResult<&str, LispVal> {
-    alt((complex, rational, float, integer)).parse(input)
+    number_in(Base::Decimal, input)
+}
+
+/// Reads all of `text` as a number, in the given radix unless it has a radix
+/// prefix, as `string->number` does
+pub fn string_to_number(text: &str, radix: u32) -> Option<LispVal> {
+    let base = match radix {
+        2 => Base::Binary,
+        8 => Base::Octal,
+        10 => Base::Decimal,
+        16 => Base::Hex,
+        _ => return None,
+    };
+    match number_in(base, text) {
+        Ok(("", n)) => Some(n),
+        _ => None,
+    }
 }
@@ -1,21 +1,24 @@
 extern crate nom;
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::lisp_val::LispVal;
 
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag},
-    character::complete::{char, multispace0, multispace1, newline, none_of, one_of},
-    combinator::fail,
+    bytes::complete::{tag, take_till},
+    character::complete::{char, digit1, hex_digit1, multispace1, newline, none_of, one_of},
+    combinator::{fail, map_opt, map_res, opt, verify},
     error::ParseError,
     multi::{many0, many1, separated_list0},
     sequence::{delimited, terminated, tuple},
     Err, IResult, Parser,
 };
 
-pub use super::parse_number::number;
+use super::parse_number::delimiter;
+pub use super::parse_number::{number, string_to_number};
 
 pub fn letter(input: &str) -> IResult<&str, char> {
     one_of("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ")(input)
@@ -64,19 +67,39 @@ where
 {
     move |input| {
         let (input, open) = alt((char('('), char('['), char('{')))(input)?;
-        let (input, _) = multispace0.parse(input)?;
+        let (input, _) = intertoken_space0.parse(input)?;
         let (input, val) = p.parse(input)?;
-        let (input, _) = multispace0.parse(input)?;
+        let (input, _) = intertoken_space0.parse(input)?;
         let (input, _) = match_bracket(open).parse(input)?;
         Ok((input, val))
     }
 }
 
+// `x41;`, the hex code of a character after a backslash
+fn hex_scalar(input: &str) -> IResult<&str, char> {
+    let (rest, (_, hex, _)) = tuple((char('x'), hex_digit1, char(';'))).parse(input)?;
+    let c = u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| {
+            Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::HexDigit,
+            ))
+        })?;
+    Ok((rest, c))
+}
+
 pub fn escaped_char(input: &str) -> IResult<&str, char> {
-    let (input, (_, c)) = tuple((char('\\'), one_of("\\\"nrt"))).parse(input)?;
+    let (input, _) = char('\\').parse(input)?;
+    if let Ok(escaped) = hex_scalar(input) {
+        return Ok(escaped);
+    }
+    let (input, c) = one_of("\\\"|abnrt").parse(input)?;
     let val = match c {
-        '\\' => c,
-        '"' => c,
+        '\\' | '"' | '|' => c,
+        'a' => '\u{7}',
+        'b' => '\u{8}',
         'n' => '\n',
         'r' => '\r',
         't' => '\t',
@@ -95,56 +118,99 @@ pub fn string(input: &str) -> IResult<&str, LispVal> {
     Ok((input, LispVal::String(val.iter().collect::<String>())))
 }
 
+/// A symbol written between bars, e.g. `|hello world|`, which can contain any character
+pub fn bar_symbol(input: &str) -> IResult<&str, LispVal> {
+    let (input, name) = delimited(
+        char('|'),
+        many0(alt((escaped_char, none_of("|\\")))),
+        char('|'),
+    )
+    .parse(input)?;
+    Ok((input, LispVal::Atom(name.iter().collect())))
+}
+
+pub fn boolean(input: &str) -> IResult<&str, LispVal> {
+    let (input, b) = terminated(
+        alt((tag("#true"), tag("#false"), tag("#t"), tag("#f"))),
+        delimiter,
+    )
+    .parse(input)?;
+    Ok((input, LispVal::Bool(b.starts_with("#t"))))
+}
+
 pub fn atom(input: &str) -> IResult<&str, LispVal> {
-    let (input, (first, rest)) =
-        tuple((alt((letter, symbol)), many0(alt((letter, digit, symbol))))).parse(input)?;
-    let atom = format!("{}{}", first, rest.iter().collect::<String>());
-    Ok((
-        input,
-        match atom.as_str() {
-            "#t" => LispVal::Bool(true),
-            "#f" => LispVal::Bool(false),
-            _ => LispVal::Atom(atom),
-        },
+    if input.starts_with('|') {
+        return bar_symbol(input);
+    }
+    if input.starts_with('#') {
+        return boolean(input);
+    }
+    // `#` can only appear after the first character, since it starts other syntax
+    let (input, (first, rest)) = tuple((
+        alt((letter, verify(symbol, |c| *c != '#'))),
+        many0(alt((letter, digit, symbol))),
     ))
+    .parse(input)?;
+    let atom = format!("{}{}", first, rest.iter().collect::<String>());
+    Ok((input, LispVal::Atom(atom)))
+}
+
+/// The character with the given name, e.g. `space`, as written after `#\\`
+pub fn named_char(name: &str) -> Option<char> {
+    let c = match name {
+        "alarm" => '\u{7}',
+        "backspace" => '\u{8}',
+        "delete" => '\u{7f}',
+        "escape" => '\u{1b}',
+        "newline" => '\n',
+        "null" => '\0',
+        "return" => '\r',
+        "space" => ' ',
+        "tab" => '\t',
+        // Older names
+        "altmode" => '\u{1b}',
+        "backnext" => '\u{1f}',
+        "call" => '\u{1a}',
+        "linefeed" => '\n',
+        "page" => '\u{c}',
+        "rubout" => '\u{7f}',
+        _ => {
+            let hex = name.strip_prefix('x')?;
+            return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+        }
+    };
+    Some(c)
 }
 
 pub fn character(input: &str) -> IResult<&str, LispVal> {
     // -- TODO: Meta-, bucky-bit stuff
-    let (input, (_, c)) = tuple((tag("#\\"), many1(letter))).parse(input)?;
-    let s = c.iter().collect::<String>();
-    if s.len() == 1 {
-        let val = s
-            .chars()
-            .next()
-            .ok_or_else(|| Err::Error(nom::error::Error::new("", nom::error::ErrorKind::Char)))?;
-        Ok((input, LispVal::Char(val)))
+    let (rest, _) = tag("#\\").parse(input)?;
+    let first = rest
+        .chars()
+        .next()
+        .ok_or_else(|| Err::Error(nom::error::Error::new(rest, nom::error::ErrorKind::Char)))?;
+    // A letter may start a name or hex code, which runs to the next delimiter
+    let len = if first.is_alphabetic() {
+        rest.find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len())
     } else {
-        let val = match s.as_str() {
-            "newline" => LispVal::Char('\n'),
-            "space" => LispVal::Char(' '),
-            "altmode" => LispVal::Char(27u8 as char),
-            "backnext" => LispVal::Char(31u8 as char),
-            "backspace" => LispVal::Char(8u8 as char),
-            "call" => LispVal::Char(26u8 as char),
-            "linefeed" => LispVal::Char(10u8 as char),
-            "page" => LispVal::Char(12u8 as char),
-            "return" => LispVal::Char(13u8 as char),
-            "rubout" => LispVal::Char(127u8 as char),
-            "tab" => LispVal::Char(9u8 as char),
-            _ => {
-                return Err(Err::Error(nom::error::Error::new(
-                    input,
-                    nom::error::ErrorKind::Char,
-                )))
-            }
-        };
-        Ok((input, val))
+        first.len_utf8()
+    };
+    let (name, input) = rest.split_at(len);
+    if name.len() == first.len_utf8() {
+        return Ok((input, LispVal::Char(first)));
+    }
+    match named_char(name) {
+        Some(c) => Ok((input, LispVal::Char(c))),
+        None => Err(Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Char,
+        ))),
     }
 }
 
 pub fn line_comment(input: &str) -> IResult<&str, ()> {
-    let (input, _) = tuple((char(';'), terminated(is_not("\n"), newline))).parse(input)?;
+    let (input, _) = tuple((char(';'), take_till(|c| c == '\n'), opt(newline))).parse(input)?;
     Ok((input, ()))
 }
 
@@ -152,71 +218,34 @@ pub fn take_until_unmatched(
     opening_bracket: &str,
     closing_bracket: &str,
 ) -> impl Fn(&str) -> IResult<&str, &str> {
-    #[derive(Clone)]
-    enum Match {
-        OpeningBracket(usize),
-        ClosingBracket(usize),
-        Escape(usize),
-    }
-    impl Match {
-        fn index(&self) -> usize {
-            match self {
-                Match::OpeningBracket(s) => *s,
-                Match::ClosingBracket(s) => *s,
-                Match::Escape(s) => *s,
-            }
-        }
-    }
-    fn find_next(s: &str, opening_bracket: &str, closing_bracket: &str) -> Option<Match> {
-        let mut possible_matches = vec![];
-        if let Some(s) = s.find(opening_bracket) {
-            possible_matches.push(Match::OpeningBracket(s));
-        };
-        if let Some(s) = s.find(closing_bracket) {
-            possible_matches.push(Match::ClosingBracket(s));
-        };
-        if let Some(s) = s.find('\\') {
-            possible_matches.push(Match::Escape(s));
-        }
-        let least = possible_matches.iter().min_by(|x, y| {
-            let x_index = x.index();
-            let y_index = y.index();
-            x_index.cmp(&y_index)
-        });
-        least.cloned()
-    }
-
     let opening_bracket = opening_bracket.to_string();
     let closing_bracket = closing_bracket.to_string();
 
     move |i: &str| {
-        let mut index = 0;
         let mut bracket_counter = 0;
-        while let Some(m) = find_next(&i[index..], &opening_bracket, &closing_bracket) {
-            match m {
-                Match::OpeningBracket(n) => {
-                    bracket_counter += 1;
-                    index += n;
-                    index += opening_bracket.len();
-                }
-                Match::ClosingBracket(n) => {
-                    bracket_counter -= 1;
-                    index += n;
-                    index += closing_bracket.len();
-                }
-                Match::Escape(n) => {
-                    index += n;
-                    index += '\\'.len_utf8();
-                    let mut c = i.chars();
-                    c.next();
-                    let c = c.next().unwrap_or_default();
-                    index += c.len_utf8();
+        // Walks the input a character at a time, so that an escape is
+        // always followed by a whole character, however many bytes it takes
+        let mut chars = i.char_indices();
+        while let Some((index, c)) = chars.next() {
+            let rest = &i[index..];
+            if rest.starts_with(opening_bracket.as_str()) {
+                bracket_counter += 1;
+                chars
+                    .by_ref()
+                    .take(opening_bracket.chars().count() - 1)
+                    .for_each(drop);
+            } else if rest.starts_with(closing_bracket.as_str()) {
+                if bracket_counter == 0 {
+                    return Ok((rest, &i[0..index]));
                 }
+                bracket_counter -= 1;
+                chars
+                    .by_ref()
+                    .take(closing_bracket.chars().count() - 1)
+                    .for_each(drop);
+            } else if c == '\\' {
+                chars.next();
             }
-            if bracket_counter == -1 {
-                index -= closing_bracket.len();
-                return Ok((&i[index..], &i[0..index]));
-            };
         }
 
         if bracket_counter == 0 {
@@ -237,14 +266,46 @@ pub fn block_comment(input: &str) -> IResult<&str, ()> {
     Ok((input, ()))
 }
 
-pub fn comment(input: &str) -> IResult<&str, LispVal> {
-    let (input, _) = alt((line_comment, block_comment)).parse(input)?;
-    Ok((input, LispVal::Void))
+/// `#;` followed by a datum, which is skipped
+pub fn datum_comment(input: &str) -> IResult<&str, ()> {
+    let (input, _) = tuple((tag("#;"), intertoken_space0, expression)).parse(input)?;
+    Ok((input, ()))
+}
+
+pub fn comment(input: &str) -> IResult<&str, ()> {
+    alt((line_comment, block_comment, datum_comment)).parse(input)
+}
+
+/// Whitespace and comments, any amount of which can appear between tokens
+pub fn intertoken_space0(input: &str) -> IResult<&str, ()> {
+    many0(alt((multispace1.map(|_| ()), comment)))
+        .map(|_| ())
+        .parse(input)
+}
+
+/// Like `intertoken_space0`, but there must be at least some space
+pub fn intertoken_space1(input: &str) -> IResult<&str, ()> {
+    many1(alt((multispace1.map(|_| ()), comment)))
+        .map(|_| ())
+        .parse(input)
 }
 
 pub fn vector(input: &str) -> IResult<&str, LispVal> {
     let (input, (_, list)) = tuple((char('#'), bracketed(raw_list))).parse(input)?;
-    Ok((input, LispVal::Vector(Rc::new(list))))
+    Ok((input, LispVal::Vector(Rc::new(RefCell::new(list)))))
+}
+
+pub fn bytevector(input: &str) -> IResult<&str, LispVal> {
+    let (rest, (_, list)) = tuple((tag("#u8"), bracketed(raw_list))).parse(input)?;
+    let bytes = list
+        .iter()
+        .map(|val| match val {
+            LispVal::Integer(n) => u8::try_from(*n).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))?;
+    Ok((rest, LispVal::Bytevector(Rc::new(RefCell::new(bytes)))))
 }
 
 pub fn quoted(input: &str) -> IResult<&str, LispVal> {
@@ -269,11 +330,30 @@ pub fn unquote_splicing(input: &str) -> IResult<&str, LispVal> {
     Ok((input, LispVal::UnquoteSplicing(Rc::new(q))))
 }
 
+/// `#n=` followed by the datum it labels
+pub fn datum_label(input: &str) -> IResult<&str, LispVal> {
+    let (input, (_, n, _, val)) = tuple((
+        char('#'),
+        map_res(digit1, str::parse),
+        char('='),
+        expression,
+    ))
+    .parse(input)?;
+    Ok((input, LispVal::DatumLabel(n, Rc::new(val))))
+}
+
+/// `#n#`, which stands for the datum labelled `#n=` earlier on
+pub fn datum_ref(input: &str) -> IResult<&str, LispVal> {
+    let (input, (_, n, _)) =
+        tuple((char('#'), map_res(digit1, str::parse), char('#'))).parse(input)?;
+    Ok((input, LispVal::DatumRef(n)))
+}
+
 pub fn raw_list(input: &str) -> IResult<&str, Vec<LispVal>> {
     let (input, (_, list, _)) = tuple((
-        multispace0,
-        separated_list0(multispace1, expression),
-        multispace0,
+        intertoken_space0,
+        separated_list0(intertoken_space1, expression),
+        intertoken_space0,
     ))
     .parse(input)?;
     Ok((input, list))
@@ -285,12 +365,12 @@ pub fn list(input: &str) -> IResult<&str, LispVal> {
 
 pub fn dotted_list(input: &str) -> IResult<&str, LispVal> {
     let (input, (_, head, _, _, tail, _)) = tuple((
-        multispace0,
-        end_by(expression, multispace1),
+        intertoken_space0,
+        end_by(expression, intertoken_space1),
         char('.'),
-        multispace1,
+        intertoken_space1,
         expression,
-        multispace0,
+        intertoken_space0,
     ))
     .parse(input)?;
     let result = match tail {
@@ -322,16 +402,16 @@ pub fn dotted_list(input: &str) -> IResult<&str, LispVal> {
 pub fn two_dotted_list(input: &str) -> IResult<&str, LispVal> {
     // TODO: Is this really needed?
     let (input, (_, mut head, _, _, mid, _, _, _, mut tail, _)) = tuple((
-        multispace0,
-        end_by(expression, multispace1),
+        intertoken_space0,
+        end_by(expression, intertoken_space1),
         char('.'),
-        multispace1,
+        intertoken_space1,
         expression,
-        multispace1,
+        intertoken_space1,
         char('.'),
-        multispace1,
+        intertoken_space1,
         separated_list0(tag(" "), expression),
-        multispace0,
+        intertoken_space0,
     ))
     .parse(input)?;
     if head.is_empty() || tail.is_empty() {
@@ -351,7 +431,7 @@ pub fn lists(input: &str) -> IResult<&str, LispVal> {
     // N.B. We're kind of semi-re-implementing part of `bracketed` here
     // but this allows us to significantly optimize list parsing
 
-    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = intertoken_space0.parse(input)?;
     // Get the opening bracket
     let (temp_input, open) = alt((char('('), char('['), char('{')))(input)?;
     // Use that to get the matching closing bracket
@@ -378,21 +458,172 @@ pub fn lists(input: &str) -> IResult<&str, LispVal> {
 pub fn expression(input: &str) -> IResult<&str, LispVal> {
     alt((
         lists,
+        datum_label,
+        datum_ref,
         vector,
-        comment,
+        bytevector,
         number,
         character,
         atom,
         string,
         quoted,
         quasi_quote,
-        unquoted,
         unquote_splicing,
+        unquoted,
     ))
     .parse(input)
 }
 
 pub fn expression_list(input: &str) -> IResult<&str, Vec<LispVal>> {
-    let (input, _) = multispace0.parse(input)?;
-    end_by(expression, multispace0).parse(input)
+    let (input, _) = intertoken_space0.parse(input)?;
+    end_by(expression, intertoken_space0).parse(input)
+}
+
+/// Rewrites the reader's abbreviated quote forms into the standard lists they
+/// stand for, e.g. `'x` becomes `(quote x)`, so quoted code is ordinary data.
+/// Datum labels are replaced by the data they label, which may make vectors
+/// cyclic. Returns `None` if a `#n#` has no datum to refer to, either because
+/// there's no `#n=` before it or because it's part of a list labelled `#n=`,
+/// which can't contain itself.
+pub fn canonicalize(val: LispVal) -> Option<LispVal> {
+    Labels::default().resolve(val)
+}
+
+type Slot = (Rc<RefCell<Vec<LispVal>>>, usize);
+
+/// The datum labels read so far in a single datum (see `canonicalize`)
+#[derive(Default)]
+struct Labels {
+    done: HashMap<u64, LispVal>,
+    /// Labels whose data is still being read
+    open: HashSet<u64>,
+    /// Vector slots holding a `#n#` for an open label, which are patched
+    /// once its datum has been read
+    pending: Vec<(Slot, u64)>,
+}
+
+impl Labels {
+    fn resolve(&mut self, val: LispVal) -> Option<LispVal> {
+        Some(match val {
+            LispVal::Quote(x) => self.wrap("quote", &x)?,
+            LispVal::QuasiQuote(x) => self.wrap("quasiquote", &x)?,
+            LispVal::Unquote(x) => self.wrap("unquote", &x)?,
+            LispVal::UnquoteSplicing(x) => self.wrap("unquote-splicing", &x)?,
+            LispVal::List(xs) => LispVal::List(Rc::new(self.resolve_all(&xs)?)),
+            LispVal::DottedList(xs, x) => LispVal::DottedList(
+                Rc::new(self.resolve_all(&xs)?),
+                Rc::new(self.resolve((*x).clone())?),
+            ),
+            LispVal::Vector(xs) => {
+                let vector = Rc::new(RefCell::new(Vec::new()));
+                self.fill(&vector, &xs.borrow())?;
+                LispVal::Vector(vector)
+            }
+            // A vector is made before its elements are read, so they can refer to it
+            LispVal::DatumLabel(n, x) => match &*x {
+                LispVal::Vector(xs) => {
+                    let vector = Rc::new(RefCell::new(Vec::new()));
+                    self.done.insert(n, LispVal::Vector(vector.clone()));
+                    self.fill(&vector, &xs.borrow())?;
+                    LispVal::Vector(vector)
+                }
+                x => {
+                    self.open.insert(n);
+                    let val = self.resolve(x.clone())?;
+                    self.open.remove(&n);
+                    self.done.insert(n, val.clone());
+                    self.pending.retain(|((vector, i), m)| {
+                        if *m == n {
+                            vector.borrow_mut()[*i] = val.clone();
+                        }
+                        *m != n
+                    });
+                    val
+                }
+            },
+            LispVal::DatumRef(n) => self.done.get(&n)?.clone(),
+            val => val,
+        })
+    }
+
+    fn resolve_all(&mut self, xs: &[LispVal]) -> Option<Vec<LispVal>> {
+        xs.iter().map(|x| self.resolve(x.clone())).collect()
+    }
+
+    fn wrap(&mut self, keyword: &str, val: &LispVal) -> Option<LispVal> {
+        Some(LispVal::List(Rc::new(vec![
+            LispVal::Atom(keyword.to_string()),
+            self.resolve(val.clone())?,
+        ])))
+    }
+
+    fn fill(&mut self, vector: &Rc<RefCell<Vec<LispVal>>>, xs: &[LispVal]) -> Option<()> {
+        for (i, x) in xs.iter().enumerate() {
+            let x = match x {
+                LispVal::DatumRef(n) if self.open.contains(n) => {
+                    self.pending.push(((vector.clone(), i), *n));
+                    LispVal::Void
+                }
+                x => self.resolve(x.clone())?,
+            };
+            vector.borrow_mut().push(x);
+        }
+        Some(())
+    }
+}
+
+/// Like `expression`, but reads quote forms as standard lists and resolves
+/// datum labels (see `canonicalize`).
+pub fn datum(input: &str) -> IResult<&str, LispVal> {
+    map_opt(expression, canonicalize).parse(input)
+}
+
+/// Like `expression_list`, but reads quote forms as standard lists and
+/// resolves datum labels (see `canonicalize`).
+pub fn datum_list(input: &str) -> IResult<&str, Vec<LispVal>> {
+    map_opt(expression_list, |exprs| {
+        exprs.into_iter().map(canonicalize).collect()
+    })
+    .parse(input)
+}
+
+/// Explains why `input`, which doesn't start with a valid datum, can't be read
+fn read_error(input: &str) -> String {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some(open @ ('(' | '[' | '{')) => {
+            let close = match open {
+                '(' => ")",
+                '[' => "]",
+                _ => "}",
+            };
+            match take_until_unmatched(&open.to_string(), close)(chars.as_str()) {
+                Ok((rest, _)) if rest.starts_with(close) => format!(
+                    "read: bad syntax in `{}`",
+                    input.lines().next().unwrap_or_default()
+                ),
+                _ => format!("read: expected a `{}` to close `{}`", close, open),
+            }
+        }
+        Some(close @ (')' | ']' | '}')) => format!("read: unexpected `{}`", close),
+        Some('"') => "read: expected a closing `\"`".to_string(),
+        _ => format!(
+            "read: bad syntax `{}`",
+            input.lines().next().unwrap_or_default()
+        ),
+    }
+}
+
+/// Reads a single datum for the `read` procedure, skipping any whitespace and
+/// comments before it. Returns the rest of the input along with the datum,
+/// which is `None` if the input runs out first.
+pub fn read_datum(input: &str) -> Result<(&str, Option<LispVal>), String> {
+    let (input, _) = intertoken_space0(input).map_err(|_| read_error(input))?;
+    if input.is_empty() {
+        return Ok((input, None));
+    }
+    match datum(input) {
+        Ok((rest, val)) => Ok((rest, Some(val))),
+        Err(_) => Err(read_error(input)),
+    }
 }
@@ -1,18 +1,32 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::parser::*;
 use crate::lisp_val::*;
 use nom::{Err, Parser};
-use num::{complex::Complex64, Rational64};
+use num::{complex::Complex64, BigRational};
 
 #[test]
 fn parse_atom() {
     assert_eq!(atom.parse("#t"), Ok(("", LispVal::Bool(true))));
     assert_eq!(atom.parse("#f"), Ok(("", LispVal::Bool(false))));
+    assert_eq!(atom.parse("#true"), Ok(("", LispVal::Bool(true))));
+    assert_eq!(atom.parse("#false)"), Ok((")", LispVal::Bool(false))));
+    assert!(atom.parse("#tru").is_err());
+    assert!(atom.parse("#").is_err());
+    assert_eq!(
+        atom.parse("a#b"),
+        Ok(("", LispVal::Atom("a#b".to_string())))
+    );
     assert_eq!(
         atom.parse("foobar"),
         Ok(("", LispVal::Atom("foobar".to_string())))
     );
+    assert_eq!(
+        atom.parse("|foo bar\\|baz|"),
+        Ok(("", LispVal::Atom("foo bar|baz".to_string())))
+    );
+    assert_eq!(atom.parse("||"), Ok(("", LispVal::Atom("".to_string()))));
 }
 
 #[test]
@@ -41,6 +55,10 @@ fn parse_string() {
         string.parse("\"foo\\nbar\""),
         Ok(("", LispVal::String("foo\nbar".to_string())))
     );
+    assert_eq!(
+        string.parse("\"\\a\\x3bb;\\x41;\""),
+        Ok(("", LispVal::String("\u{7}λA".to_string())))
+    );
     assert_eq!(
         string.parse("\"foo"),
         Err(Err::Error(nom::error::Error::new(
@@ -82,11 +100,40 @@ fn parse_character() {
         Ok(("", LispVal::Char('\u{7f}')))
     );
     assert_eq!(character.parse("#\\tab"), Ok(("", LispVal::Char('\t'))));
+    assert_eq!(
+        character.parse("#\\alarm"),
+        Ok(("", LispVal::Char('\u{7}')))
+    );
+    assert_eq!(character.parse("#\\null"), Ok(("", LispVal::Char('\0'))));
+    assert_eq!(character.parse("#\\x41"), Ok(("", LispVal::Char('A'))));
+    assert_eq!(character.parse("#\\x"), Ok(("", LispVal::Char('x'))));
+    assert_eq!(character.parse("#\\()"), Ok((")", LispVal::Char('('))));
+    assert_eq!(character.parse("#\\λ"), Ok(("", LispVal::Char('λ'))));
+    assert!(character.parse("#\\nonsense").is_err());
 }
 
 #[test]
 fn parse_line_comment() {
     assert_eq!(line_comment.parse("; foo bar baz qux\n"), Ok(("", ())));
+    assert_eq!(line_comment.parse("; at the end"), Ok(("", ())));
+}
+
+#[test]
+fn parse_datum_comment() {
+    assert_eq!(datum_comment.parse("#;(1 2) 3"), Ok((" 3", ())));
+    assert_eq!(datum_comment.parse("#; #;1 2 3"), Ok((" 3", ())));
+}
+
+#[test]
+fn comments_in_lists() {
+    let one_two = LispVal::List(Rc::new(vec![LispVal::Integer(1), LispVal::Integer(2)]));
+    assert_eq!(lists.parse("(1 #| x |# 2)"), Ok(("", one_two.clone())));
+    assert_eq!(lists.parse("(1 ; x\n 2)"), Ok(("", one_two.clone())));
+    assert_eq!(lists.parse("(#;0 1 #;(x y) 2 #;3)"), Ok(("", one_two)));
+    assert_eq!(
+        expression_list.parse("#;1 2 ; three"),
+        Ok(("", vec![LispVal::Integer(2)]))
+    );
 }
 
 #[test]
@@ -140,6 +187,29 @@ fn parse_integer_number() {
     );
 }
 
+#[test]
+fn parse_big_integer_number() {
+    let big = |digits: &str| {
+        LispVal::BigInteger(std::rc::Rc::new(digits.parse::<num::BigInt>().unwrap()))
+    };
+    assert_eq!(
+        number.parse("12345678901234567890"),
+        Ok(("", big("12345678901234567890")))
+    );
+    assert_eq!(
+        number.parse("-9223372036854775808"),
+        Ok(("", LispVal::Integer(i64::MIN)))
+    );
+    assert_eq!(
+        number.parse("#xFFFFFFFFFFFFFFFFFF"),
+        Ok(("", big("4722366482869645213695")))
+    );
+    assert_eq!(
+        number.parse("1.00000000000000000000001"),
+        Ok(("", LispVal::Float(1.0)))
+    );
+}
+
 #[test]
 fn parse_float_number() {
     // Decimal
@@ -213,6 +283,12 @@ fn parse_float_number() {
         number.parse("#b-11011000001.001"),
         Ok(("", LispVal::Float(-1729.125)))
     );
+    // Long fractions are scaled as floats, rather than by an integer power
+    // of two that would overflow
+    assert_eq!(
+        number.parse(&format!("#b0.{}", "01".repeat(32))),
+        Ok(("", LispVal::Float(1.0 / 3.0)))
+    );
     // Octal
     assert_eq!(number.parse("#o3301."), Ok(("", LispVal::Float(1729.0))));
     assert_eq!(number.parse("#o3301.0"), Ok(("", LispVal::Float(1729.0))));
@@ -271,17 +347,16 @@ fn parse_float_number() {
     );
 }
 
+// A complex number with exact parts, which is an integer if `im` is 0
+fn exact_complex(re: i64, im: i64) -> LispVal {
+    crate::numbers::make_rectangular(&LispVal::Integer(re), &LispVal::Integer(im)).unwrap()
+}
+
 #[test]
 fn parse_complex_number() {
     // Decimal
-    assert_eq!(
-        number.parse("1+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(1.0, 0.0))))
-    );
-    assert_eq!(
-        number.parse("+1+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(1.0, 0.0))))
-    );
+    assert_eq!(number.parse("1+0i"), Ok(("", exact_complex(1, 0))));
+    assert_eq!(number.parse("+1+0i"), Ok(("", exact_complex(1, 0))));
     assert_eq!(
         number.parse("1.+0i"),
         Ok(("", LispVal::Complex(Complex64::new(1.0, 0.0))))
@@ -322,10 +397,7 @@ fn parse_complex_number() {
         number.parse("+1.+0.i"),
         Ok(("", LispVal::Complex(Complex64::new(1.0, 0.0))))
     );
-    assert_eq!(
-        number.parse("-1+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(-1.0, 0.0))))
-    );
+    assert_eq!(number.parse("-1+0i"), Ok(("", exact_complex(-1, 0))));
     assert_eq!(
         number.parse("-1.0+0i"),
         Ok(("", LispVal::Complex(Complex64::new(-1.0, 0.0))))
@@ -342,10 +414,7 @@ fn parse_complex_number() {
         number.parse("-1+0.i"),
         Ok(("", LispVal::Complex(Complex64::new(-1.0, 0.0))))
     );
-    assert_eq!(
-        number.parse("1-0i"),
-        Ok(("", LispVal::Complex(Complex64::new(1.0, 0.0))))
-    );
+    assert_eq!(number.parse("1-0i"), Ok(("", exact_complex(1, 0))));
     assert_eq!(
         number.parse("0-1.0i"),
         Ok(("", LispVal::Complex(Complex64::new(0.0, -1.0))))
@@ -354,14 +423,11 @@ fn parse_complex_number() {
         number.parse("0-1.i"),
         Ok(("", LispVal::Complex(Complex64::new(0.0, -1.0))))
     );
-    assert_eq!(
-        number.parse("-1-1i"),
-        Ok(("", LispVal::Complex(Complex64::new(-1.0, -1.0))))
-    );
+    assert_eq!(number.parse("-1-1i"), Ok(("", exact_complex(-1, -1))));
     // Binary
     assert_eq!(
         number.parse("#b11011000001+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(1729.0, 0.0))))
+        Ok(("", exact_complex(1729, 0)))
     );
     assert_eq!(
         number.parse("#b11011000001.+0i"),
@@ -373,7 +439,7 @@ fn parse_complex_number() {
     );
     assert_eq!(
         number.parse("#b11011000001+11011000001i"),
-        Ok(("", LispVal::Complex(Complex64::new(1729.0, 1729.0))))
+        Ok(("", exact_complex(1729, 1729)))
     );
     assert_eq!(
         number.parse("#b11011000001.+11011000001i"),
@@ -385,19 +451,19 @@ fn parse_complex_number() {
     );
     assert_eq!(
         number.parse("#b+11011000001+11011000001i"),
-        Ok(("", LispVal::Complex(Complex64::new(1729.0, 1729.0))))
+        Ok(("", exact_complex(1729, 1729)))
     );
     assert_eq!(
         number.parse("#b-11011000001+11011000001i"),
-        Ok(("", LispVal::Complex(Complex64::new(-1729.0, 1729.0))))
+        Ok(("", exact_complex(-1729, 1729)))
     );
     assert_eq!(
         number.parse("#b11011000001-11011000001i"),
-        Ok(("", LispVal::Complex(Complex64::new(1729.0, -1729.0))))
+        Ok(("", exact_complex(1729, -1729)))
     );
     assert_eq!(
         number.parse("#b-11011000001-11011000001i"),
-        Ok(("", LispVal::Complex(Complex64::new(-1729.0, -1729.0))))
+        Ok(("", exact_complex(-1729, -1729)))
     );
     assert_eq!(
         number.parse("#b-11011000001.10000011011-11011000001.10000011011i"),
@@ -421,10 +487,7 @@ fn parse_complex_number() {
         ))
     );
     // Octal
-    assert_eq!(
-        number.parse("#o3301+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(1729.0, 0.0))))
-    );
+    assert_eq!(number.parse("#o3301+0i"), Ok(("", exact_complex(1729, 0))));
     assert_eq!(
         number.parse("#o3301.0+0i"),
         Ok(("", LispVal::Complex(Complex64::new(1729.0, 0.0))))
@@ -464,7 +527,7 @@ fn parse_complex_number() {
     // Hex
     assert_eq!(
         number.parse("#xDEADBEEF+0i"),
-        Ok(("", LispVal::Complex(Complex64::new(3735928559.0, 0.0))))
+        Ok(("", exact_complex(3735928559, 0)))
     );
     assert_eq!(
         number.parse("#xDEADBEEF.0+0i"),
@@ -522,80 +585,168 @@ fn parse_complex_number() {
         number.parse("#x+DEADBEEF.0-0.i"),
         Ok(("", LispVal::Complex(Complex64::new(3735928559.0, 0.0))))
     );
-}
-
-#[test]
-fn parse_rational_number() {
-    // Decimal
-    assert_eq!(
-        number.parse("1/1"),
-        Ok(("", LispVal::Rational(Rational64::new(1, 1))))
-    );
+    // Rational parts
     assert_eq!(
-        number.parse("1729/3"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 3))))
-    );
-    assert_eq!(
-        number.parse("+1729/3"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 3))))
-    );
-    assert_eq!(
-        number.parse("-1729/3"),
-        Ok(("", LispVal::Rational(Rational64::new(-1729, 3))))
+        number.parse("1/2-1/3i"),
+        Ok((
+            "",
+            crate::numbers::make_rectangular(&ratio(1, 2), &ratio(-1, 3)).unwrap()
+        ))
     );
-    // Binary
     assert_eq!(
-        number.parse("#b11011000001/1"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 1))))
+        number.parse("#x1/2+Ai"),
+        Ok((
+            "",
+            crate::numbers::make_rectangular(&ratio(1, 2), &LispVal::Integer(10)).unwrap()
+        ))
     );
     assert_eq!(
-        number.parse("#b11011000001/11"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 3))))
+        number.parse("1/2+0.5i"),
+        Ok(("", LispVal::Complex(Complex64::new(0.5, 0.5))))
     );
+    // Polar
     assert_eq!(
-        number.parse("#b+11011000001/11"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 3))))
+        number.parse("2@0.5"),
+        Ok(("", LispVal::Complex(Complex64::from_polar(2.0, 0.5))))
     );
+    assert_eq!(number.parse("-2@0"), Ok(("", LispVal::Integer(-2))));
     assert_eq!(
-        number.parse("#b-11011000001/11"),
-        Ok(("", LispVal::Rational(Rational64::new(-1729, 3))))
+        number.parse("1@-1/2"),
+        Ok(("", LispVal::Complex(Complex64::from_polar(1.0, -0.5))))
     );
+}
+
+// The exact value `n/d`, which is an integer if `d` divides `n`
+fn ratio(n: i64, d: i64) -> LispVal {
+    crate::numbers::from_big_rational(BigRational::new(n.into(), d.into()))
+}
+
+#[test]
+fn parse_rational_number() {
+    // Decimal
+    assert_eq!(number.parse("1/1"), Ok(("", ratio(1, 1))));
+    assert_eq!(number.parse("1729/3"), Ok(("", ratio(1729, 3))));
+    assert_eq!(number.parse("+1729/3"), Ok(("", ratio(1729, 3))));
+    assert_eq!(number.parse("-1729/3"), Ok(("", ratio(-1729, 3))));
+    // Binary
+    assert_eq!(number.parse("#b11011000001/1"), Ok(("", ratio(1729, 1))));
+    assert_eq!(number.parse("#b11011000001/11"), Ok(("", ratio(1729, 3))));
+    assert_eq!(number.parse("#b+11011000001/11"), Ok(("", ratio(1729, 3))));
+    assert_eq!(number.parse("#b-11011000001/11"), Ok(("", ratio(-1729, 3))));
     // Octal
+    assert_eq!(number.parse("#o3301/1"), Ok(("", ratio(1729, 1))));
+    assert_eq!(number.parse("#o3301/3"), Ok(("", ratio(1729, 3))));
+    assert_eq!(number.parse("#o+3301/11"), Ok(("", ratio(1729, 9))));
+    assert_eq!(number.parse("#o-3301/11"), Ok(("", ratio(-1729, 9))));
+    // Hex
+    assert_eq!(number.parse("#xDEADBEEF/1"), Ok(("", ratio(3735928559, 1))));
     assert_eq!(
-        number.parse("#o3301/1"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 1))))
+        number.parse("#xDEADBEEF/CAFE"),
+        Ok(("", ratio(3735928559, 51966)))
     );
     assert_eq!(
-        number.parse("#o3301/3"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 3))))
+        number.parse("#x+DEADBEEF/CAFE"),
+        Ok(("", ratio(3735928559, 51966)))
     );
     assert_eq!(
-        number.parse("#o+3301/11"),
-        Ok(("", LispVal::Rational(Rational64::new(1729, 9))))
+        number.parse("#x-DEADBEEF/CAFE"),
+        Ok(("", ratio(-3735928559, 51966)))
+    );
+}
+
+#[test]
+fn parse_exactness_prefix() {
+    assert_eq!(number.parse("#e1.25"), Ok(("", ratio(5, 4))));
+    assert_eq!(number.parse("#e-0.5"), Ok(("", ratio(-1, 2))));
+    assert_eq!(number.parse("#e#b1.1"), Ok(("", ratio(3, 2))));
+    assert_eq!(number.parse("#x#eF"), Ok(("", LispVal::Integer(15))));
+    assert_eq!(number.parse("#i3/4"), Ok(("", LispVal::Float(0.75))));
+    assert_eq!(number.parse("#i#o10"), Ok(("", LispVal::Float(8.0))));
+    assert_eq!(number.parse("#o#i10 x"), Ok((" x", LispVal::Float(8.0))));
+    assert_eq!(number.parse("#e1.5+2i"), number.parse("3/2+2i"));
+    assert_eq!(
+        number.parse("#i1+2i"),
+        Ok(("", LispVal::Complex(Complex64::new(1.0, 2.0))))
     );
+}
+
+#[test]
+fn parse_decimal_syntax() {
+    assert_eq!(number.parse("1e10"), Ok(("", LispVal::Float(1e10))));
+    assert_eq!(number.parse("6.02e23"), Ok(("", LispVal::Float(6.02e23))));
+    assert_eq!(number.parse("-1.5E-3"), Ok(("", LispVal::Float(-1.5e-3))));
+    assert_eq!(number.parse(".5"), Ok(("", LispVal::Float(0.5))));
+    assert_eq!(number.parse("-.5"), Ok(("", LispVal::Float(-0.5))));
+    assert_eq!(number.parse("0.1"), Ok(("", LispVal::Float(0.1))));
+    assert_eq!(number.parse("#e1e3"), Ok(("", LispVal::Integer(1000))));
+    assert_eq!(number.parse("#e1.5e-3"), Ok(("", ratio(3, 2000))));
+    assert_eq!(number.parse("#e.1"), Ok(("", ratio(1, 10))));
+    assert_eq!(number.parse("#x1e2"), Ok(("", LispVal::Integer(0x1e2))));
+}
+
+#[test]
+fn parse_infinities_and_nan() {
     assert_eq!(
-        number.parse("#o-3301/11"),
-        Ok(("", LispVal::Rational(Rational64::new(-1729, 9))))
+        number.parse("+inf.0"),
+        Ok(("", LispVal::Float(f64::INFINITY)))
     );
-    // Hex
     assert_eq!(
-        number.parse("#xDEADBEEF/1"),
-        Ok(("", LispVal::Rational(Rational64::new(3735928559, 1))))
+        number.parse("-inf.0"),
+        Ok(("", LispVal::Float(f64::NEG_INFINITY)))
     );
+    assert!(matches!(number.parse("+nan.0"), Ok(("", LispVal::Float(n))) if n.is_nan()));
+    assert!(matches!(number.parse("-nan.0"), Ok(("", LispVal::Float(n))) if n.is_nan()));
     assert_eq!(
-        number.parse("#xDEADBEEF/CAFE"),
-        Ok(("", LispVal::Rational(Rational64::new(3735928559, 51966))))
+        number.parse("1-inf.0i"),
+        Ok(("", LispVal::Complex(Complex64::new(1.0, f64::NEG_INFINITY))))
     );
+    assert!(number.parse("inf.0").is_err());
+    assert!(number.parse("#e+inf.0").is_err());
+}
+
+#[test]
+fn parse_imaginary_number() {
+    assert_eq!(number.parse("+i"), Ok(("", exact_complex(0, 1))));
+    assert_eq!(number.parse("-i"), Ok(("", exact_complex(0, -1))));
+    assert_eq!(number.parse("-3i"), Ok(("", exact_complex(0, -3))));
+    assert_eq!(number.parse("1+i"), Ok(("", exact_complex(1, 1))));
+    assert_eq!(number.parse("#x-Ai"), Ok(("", exact_complex(0, -10))));
     assert_eq!(
-        number.parse("#x+DEADBEEF/CAFE"),
-        Ok(("", LispVal::Rational(Rational64::new(3735928559, 51966))))
+        number.parse("+2.5i"),
+        Ok(("", LispVal::Complex(Complex64::new(0.0, 2.5))))
     );
+}
+
+#[test]
+fn parse_number_delimiters() {
+    assert_eq!(number.parse("1)"), Ok((")", LispVal::Integer(1))));
+    assert_eq!(number.parse("1 2"), Ok((" 2", LispVal::Integer(1))));
+    assert!(number.parse("1+").is_err());
+    assert!(number.parse("+inf.0x").is_err());
+    assert!(number.parse("+id").is_err());
     assert_eq!(
-        number.parse("#x-DEADBEEF/CAFE"),
-        Ok(("", LispVal::Rational(Rational64::new(-3735928559, 51966))))
+        expression("+id"),
+        Ok(("", LispVal::Atom("+id".to_string())))
     );
 }
 
+#[test]
+fn read_string_to_number() {
+    assert_eq!(string_to_number("ff", 16), Some(LispVal::Integer(255)));
+    assert_eq!(string_to_number("#xff", 10), Some(LispVal::Integer(255)));
+    assert_eq!(string_to_number("#d10", 16), Some(LispVal::Integer(10)));
+    assert_eq!(string_to_number("1/2", 2), None);
+    assert_eq!(string_to_number("1 ", 10), None);
+    assert_eq!(string_to_number("", 10), None);
+    assert_eq!(string_to_number("abc", 10), None);
+    assert_eq!(string_to_number("#e1e1000000000", 10), None);
+    assert_eq!(string_to_number("#e1.5e-1000000000", 10), None);
+    assert!(matches!(
+        string_to_number("#e1e1000", 10),
+        Some(LispVal::BigInteger(_))
+    ));
+}
+
 #[test]
 fn parse_bracketed() {
     assert_eq!(
@@ -641,31 +792,48 @@ fn parse_vector() {
         vector("#(1 2 3)"),
         Ok((
             "",
-            LispVal::Vector(Rc::new(vec![
+            LispVal::Vector(Rc::new(RefCell::new(vec![
                 LispVal::Integer(1),
                 LispVal::Integer(2),
                 LispVal::Integer(3)
-            ]))
+            ])))
         ))
     );
     assert_eq!(
         vector("#(1 2 3 #[4 5 6])"),
         Ok((
             "",
-            LispVal::Vector(Rc::new(vec![
+            LispVal::Vector(Rc::new(RefCell::new(vec![
                 LispVal::Integer(1),
                 LispVal::Integer(2),
                 LispVal::Integer(3),
-                LispVal::Vector(Rc::new(vec![
+                LispVal::Vector(Rc::new(RefCell::new(vec![
                     LispVal::Integer(4),
                     LispVal::Integer(5),
                     LispVal::Integer(6)
-                ]))
-            ]))
+                ])))
+            ])))
         ))
     );
 }
 
+#[test]
+fn parse_bytevector() {
+    assert_eq!(
+        bytevector("#u8(1 2 255)"),
+        Ok((
+            "",
+            LispVal::Bytevector(Rc::new(RefCell::new(vec![1, 2, 255])))
+        ))
+    );
+    assert_eq!(
+        bytevector("#u8()"),
+        Ok(("", LispVal::Bytevector(Rc::new(RefCell::new(vec![])))))
+    );
+    assert!(bytevector("#u8(1 256)").is_err());
+    assert!(bytevector("#u8(a)").is_err());
+}
+
 #[test]
 fn parse_lists() {
     assert_eq!(
@@ -818,5 +986,90 @@ fn parse_unquote_splicing() {
                 LispVal::Integer(3),
             ]))))
         ))
+    );
+    assert_eq!(
+        expression(",@xs"),
+        Ok((
+            "",
+            LispVal::UnquoteSplicing(Rc::new(LispVal::Atom("xs".to_string())))
+        ))
     )
 }
+
+#[test]
+fn read_datums() {
+    assert_eq!(
+        read_datum("  ; comment\n #| block |# (1 2) rest"),
+        Ok((
+            " rest",
+            Some(LispVal::List(Rc::new(vec![
+                LispVal::Integer(1),
+                LispVal::Integer(2)
+            ])))
+        ))
+    );
+    assert_eq!(
+        read_datum("'x"),
+        Ok((
+            "",
+            Some(LispVal::List(Rc::new(vec![
+                LispVal::Atom("quote".to_string()),
+                LispVal::Atom("x".to_string())
+            ])))
+        ))
+    );
+    assert_eq!(read_datum("   "), Ok(("", None)));
+    assert_eq!(read_datum("; just a comment"), Ok(("", None)));
+    assert_eq!(
+        read_datum("(1 (2)"),
+        Err("read: expected a `)` to close `(`".to_string())
+    );
+    assert_eq!(
+        read_datum("(#\\é \"ü\\\"λ\") x"),
+        Ok((
+            " x",
+            Some(LispVal::List(Rc::new(vec![
+                LispVal::Char('é'),
+                LispVal::String("ü\"λ".to_string())
+            ])))
+        ))
+    );
+    assert_eq!(read_datum(") x"), Err("read: unexpected `)`".to_string()));
+    assert_eq!(
+        read_datum("\"abc"),
+        Err("read: expected a closing `\"`".to_string())
+    );
+}
+
+#[test]
+fn read_datum_labels() {
+    assert_eq!(
+        datum.parse("#0=(1 2)"),
+        Ok((
+            "",
+            LispVal::List(Rc::new(vec![LispVal::Integer(1), LispVal::Integer(2)]))
+        ))
+    );
+    match datum.parse("(#0=(1 2) #0#)") {
+        Ok(("", LispVal::List(xs))) => match (&xs[0], &xs[1]) {
+            (LispVal::List(a), LispVal::List(b)) => assert!(Rc::ptr_eq(a, b)),
+            _ => panic!("expected two lists: {:?}", xs),
+        },
+        result => panic!("expected a list: {:?}", result),
+    }
+    match datum.parse("#0=#(a #0#)") {
+        Ok(("", LispVal::Vector(xs))) => match &xs.borrow()[1] {
+            LispVal::Vector(ys) => assert!(Rc::ptr_eq(&xs, ys)),
+            x => panic!("expected a vector: {:?}", x),
+        },
+        _ => panic!("expected a vector"),
+    }
+    assert_eq!(
+        read_datum("(#1#)"),
+        Err("read: bad syntax in `(#1#)`".to_string())
+    );
+    assert_eq!(
+        read_datum("#0=(1 . #0#)"),
+        Err("read: bad syntax `#0=(1 . #0#)`".to_string())
+    );
+}
@@ -0,0 +1,5 @@
+mod port;
+#[cfg(test)]
+mod tests;
+
+pub use port::{Port, Signal, Sink};
@@ -0,0 +1,452 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::error::{LispError, LispResult};
+use crate::file_system::FileSystem;
+
+/// Called with an output port's buffered text whenever the port is written
+/// to. The callback is expected to drain whatever it consumes.
+pub type Signal = Box<dyn FnMut(&mut String)>;
+
+/// Where an output port's text or bytes go
+pub enum Sink {
+    /// Accumulates text for `get-output-string`
+    String(String),
+    /// Buffers text and signals the embedder after every write
+    Signal(String, Signal),
+    /// Accumulates bytes for `get-output-bytevector`
+    Bytes(Vec<u8>),
+    /// Appends to the file at a resolved path
+    File(Rc<dyn FileSystem>, String),
+}
+
+impl Sink {
+    fn write(&mut self, bytes: &[u8]) -> LispResult<()> {
+        match self {
+            Sink::String(buffer) => buffer.push_str(&String::from_utf8_lossy(bytes)),
+            Sink::Signal(buffer, signal) => {
+                if !bytes.is_empty() {
+                    buffer.push_str(&String::from_utf8_lossy(bytes));
+                    signal(buffer);
+                }
+            }
+            Sink::Bytes(buffer) => buffer.extend_from_slice(bytes),
+            Sink::File(file_system, path) => file_system.append(path, bytes).map_err(|err| {
+                LispError::GenericError(format!(
+                    "write: error writing to file\npath: {}\nsystem error: {}",
+                    path, err
+                ))
+            })?,
+        }
+        Ok(())
+    }
+}
+
+struct TextInput {
+    // Everything before `pos` has been read. The text is only ever trimmed
+    // when more is pushed, so reading doesn't have to copy what's left.
+    text: String,
+    pos: usize,
+    // Set for ports fed by the embedder, which may have more input to come.
+    // Holds everything read since the last checkpoint, so it can be rewound.
+    interactive: Option<String>,
+}
+
+impl TextInput {
+    fn new(text: String, interactive: Option<String>) -> Self {
+        TextInput {
+            text,
+            pos: 0,
+            interactive,
+        }
+    }
+
+    /// Consumes as many bytes of the unread text as `scan` says it used.
+    /// `scan` is told whether more input may come, and returns `None` if it
+    /// needs it.
+    fn take<T>(
+        &mut self,
+        scan: impl FnOnce(&str, bool) -> LispResult<Option<(usize, T)>>,
+    ) -> LispResult<Option<T>> {
+        let unread = &self.text[self.pos..];
+        let Some((used, result)) = scan(unread, self.interactive.is_some())? else {
+            return Ok(None);
+        };
+        if let Some(read) = &mut self.interactive {
+            read.push_str(&unread[..used]);
+        }
+        self.pos += used;
+        Ok(Some(result))
+    }
+}
+
+enum PortData {
+    Input(TextInput),
+    BinaryInput(VecDeque<u8>),
+    Output(Sink),
+    BinaryOutput(Sink),
+}
+
+struct PortState {
+    data: PortData,
+    open: bool,
+    // Characters written so far, and how many of the next ones to drop
+    written: usize,
+    skip: usize,
+}
+
+/// A textual or binary port. Ports are shared, so every copy of a port reads
+/// from or writes to the same place.
+#[derive(Clone)]
+pub struct Port(Rc<RefCell<PortState>>);
+
+impl Port {
+    fn new(data: PortData) -> Self {
+        Port(Rc::new(RefCell::new(PortState {
+            data,
+            open: true,
+            written: 0,
+            skip: 0,
+        })))
+    }
+
+    /// An input port that reads the characters of `s`
+    pub fn input_string(s: &str) -> Self {
+        Port::new(PortData::Input(TextInput::new(s.to_string(), None)))
+    }
+
+    /// An input port fed by the embedder with `push_input`. Reading past the
+    /// end of what's been pushed raises `LispError::InputPending` rather than
+    /// returning end of file.
+    pub fn interactive() -> Self {
+        Port::new(PortData::Input(TextInput::new(
+            String::new(),
+            Some(String::new()),
+        )))
+    }
+
+    /// An output port that accumulates everything written to it
+    pub fn output_string() -> Self {
+        Port::new(PortData::Output(Sink::String(String::new())))
+    }
+
+    /// An output port that calls `signal` after every write
+    pub fn signalling(signal: Signal) -> Self {
+        Port::new(PortData::Output(Sink::Signal(String::new(), signal)))
+    }
+
+    /// A binary input port that reads `bytes`
+    pub fn input_bytes(bytes: Vec<u8>) -> Self {
+        Port::new(PortData::BinaryInput(bytes.into()))
+    }
+
+    /// A binary output port that accumulates everything written to it
+    pub fn output_bytes() -> Self {
+        Port::new(PortData::BinaryOutput(Sink::Bytes(vec![])))
+    }
+
+    /// An output port that appends to the file at `path`, which should
+    /// already exist
+    pub fn output_file(file_system: Rc<dyn FileSystem>, path: String, binary: bool) -> Self {
+        let sink = Sink::File(file_system, path);
+        if binary {
+            Port::new(PortData::BinaryOutput(sink))
+        } else {
+            Port::new(PortData::Output(sink))
+        }
+    }
+
+    pub fn is_input(&self) -> bool {
+        matches!(
+            self.0.borrow().data,
+            PortData::Input(_) | PortData::BinaryInput(_)
+        )
+    }
+
+    pub fn is_output(&self) -> bool {
+        matches!(
+            self.0.borrow().data,
+            PortData::Output(_) | PortData::BinaryOutput(_)
+        )
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(
+            self.0.borrow().data,
+            PortData::BinaryInput(_) | PortData::BinaryOutput(_)
+        )
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.borrow().open
+    }
+
+    pub fn close(&self) {
+        self.0.borrow_mut().open = false;
+    }
+
+    fn closed_error(&self, name: &str) -> LispError {
+        LispError::GenericError(format!("{}: port is closed\nport: {}", name, self))
+    }
+
+    /// Runs `f` on an open port's data. `f` returns `None` if the port is the
+    /// wrong kind, which is reported as a contract violation against `expected`.
+    fn with_data<T>(
+        &self,
+        name: &str,
+        expected: &str,
+        f: impl FnOnce(&mut PortData) -> Option<T>,
+    ) -> LispResult<T> {
+        let mut state = self.0.borrow_mut();
+        if !state.open {
+            drop(state);
+            return Err(self.closed_error(name));
+        }
+        let result = f(&mut state.data);
+        drop(state);
+        result.ok_or_else(|| {
+            LispError::GenericError(format!(
+                "{}: contract violation\nexpected: {}\ngiven: {}",
+                name, expected, self
+            ))
+        })
+    }
+
+    /// Reads from a textual input port (see `TextInput::take`), raising
+    /// `LispError::InputPending` if an interactive port needs more input.
+    fn take<T>(
+        &self,
+        name: &str,
+        scan: impl FnOnce(&str, bool) -> LispResult<Option<(usize, T)>>,
+    ) -> LispResult<T> {
+        let taken = self.with_data(name, "textual-input-port?", |data| match data {
+            PortData::Input(input) => Some(input.take(scan)),
+            _ => None,
+        })??;
+        taken.ok_or_else(|| LispError::InputPending(name.to_string()))
+    }
+
+    fn with_interactive(&self, f: impl FnOnce(&mut TextInput, &mut String)) {
+        if let PortData::Input(input) = &mut self.0.borrow_mut().data {
+            if let Some(mut read) = input.interactive.take() {
+                f(input, &mut read);
+                input.interactive = Some(read);
+            }
+        }
+    }
+
+    /// Adds text for an interactive port to read, dropping what's been read
+    pub fn push_input(&self, s: &str) {
+        self.with_interactive(|input, _| {
+            input.text.drain(..input.pos);
+            input.pos = 0;
+            input.text.push_str(s);
+        })
+    }
+
+    /// Forgets what an interactive port has read, so that `rewind` only goes back to here
+    pub fn checkpoint(&self) {
+        self.with_interactive(|_, read| read.clear())
+    }
+
+    /// Puts back everything an interactive port has read since the last checkpoint
+    pub fn rewind(&self) {
+        self.with_interactive(|input, read| {
+            let unread = &input.text[input.pos..];
+            input.text = std::mem::take(read) + unread;
+            input.pos = 0;
+        })
+    }
+
+    fn with_binary_input<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut VecDeque<u8>) -> T,
+    ) -> LispResult<T> {
+        self.with_data(name, "binary-input-port?", |data| match data {
+            PortData::BinaryInput(bytes) => Some(f(bytes)),
+            _ => None,
+        })
+    }
+
+    /// Reads the next character, or `None` at end of file
+    pub fn read_char(&self) -> LispResult<Option<char>> {
+        self.take("read-char", |text, more| {
+            Ok(match text.chars().next() {
+                Some(c) => Some((c.len_utf8(), Some(c))),
+                None if more => None,
+                None => Some((0, None)),
+            })
+        })
+    }
+
+    pub fn peek_char(&self) -> LispResult<Option<char>> {
+        self.take("peek-char", |text, more| {
+            Ok(match text.chars().next() {
+                Some(c) => Some((0, Some(c))),
+                None if more => None,
+                None => Some((0, None)),
+            })
+        })
+    }
+
+    /// Reads up to the next newline, which is consumed but not returned
+    pub fn read_line(&self) -> LispResult<Option<String>> {
+        self.take("read-line", |text, more| {
+            Ok(match text.find('\n') {
+                Some(i) => Some((i + 1, Some(text[..i].to_string()))),
+                None if more => None,
+                None if text.is_empty() => Some((0, None)),
+                None => Some((text.len(), Some(text.to_string()))),
+            })
+        })
+    }
+
+    /// Reads at most `k` characters
+    pub fn read_string(&self, k: usize) -> LispResult<Option<String>> {
+        self.take("read-string", |text, more| {
+            // Where the first `k` characters end, if there are that many
+            let end = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(text.len()))
+                .nth(k);
+            Ok(match end {
+                Some(end) => Some((end, Some(text[..end].to_string()))),
+                None if more => None,
+                None if text.is_empty() => Some((0, None)),
+                None => Some((text.len(), Some(text.to_string()))),
+            })
+        })
+    }
+
+    /// Runs `f` on the port's remaining input, then consumes as many bytes
+    /// as it reports using. `f` is told whether more input may come, and
+    /// returns `None` if it has to wait for it.
+    pub fn read_with<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&str, bool) -> LispResult<Option<(usize, T)>>,
+    ) -> LispResult<T> {
+        self.take(name, f)
+    }
+
+    /// Whether a character can be read without waiting, which is only ever
+    /// false for an interactive port that's run out.
+    pub fn char_ready(&self) -> LispResult<bool> {
+        self.take("char-ready?", |text, more| {
+            Ok(Some((0, !text.is_empty() || !more)))
+        })
+    }
+
+    /// Reads the next byte, or `None` at end of file
+    pub fn read_u8(&self) -> LispResult<Option<u8>> {
+        self.with_binary_input("read-u8", |bytes| bytes.pop_front())
+    }
+
+    pub fn peek_u8(&self) -> LispResult<Option<u8>> {
+        self.with_binary_input("peek-u8", |bytes| bytes.front().copied())
+    }
+
+    /// Reads at most `k` bytes
+    pub fn read_bytes(&self, k: usize) -> LispResult<Option<Vec<u8>>> {
+        self.with_binary_input("read-bytevector", |bytes| {
+            if bytes.is_empty() && k > 0 {
+                return None;
+            }
+            let n = k.min(bytes.len());
+            Some(bytes.drain(..n).collect())
+        })
+    }
+
+    /// The number of characters written to the port so far
+    pub fn written(&self) -> usize {
+        self.0.borrow().written
+    }
+
+    /// Drops the next `n` characters written, e.g. output that's already
+    /// been shown and is being written again
+    pub fn skip_output(&self, n: usize) {
+        self.0.borrow_mut().skip = n;
+    }
+
+    pub fn write_str(&self, s: &str) -> LispResult<()> {
+        let s = {
+            let mut state = self.0.borrow_mut();
+            let count = s.chars().count();
+            let skipped = state.skip.min(count);
+            state.skip -= skipped;
+            state.written += count;
+            &s[s.char_indices().nth(skipped).map_or(s.len(), |(i, _)| i)..]
+        };
+        self.with_data("write", "textual-output-port?", |data| match data {
+            PortData::Output(sink) => Some(sink.write(s.as_bytes())),
+            _ => None,
+        })?
+    }
+
+    pub fn write_bytes(&self, bytes: &[u8]) -> LispResult<()> {
+        self.with_data(
+            "write-bytevector",
+            "binary-output-port?",
+            |data| match data {
+                PortData::BinaryOutput(sink) => Some(sink.write(bytes)),
+                _ => None,
+            },
+        )?
+    }
+
+    /// Everything written so far to a port made by `output_string`
+    pub fn output(&self) -> Option<String> {
+        match &self.0.borrow().data {
+            PortData::Output(Sink::String(buffer)) => Some(buffer.clone()),
+            _ => None,
+        }
+    }
+
+    /// Everything written so far to a port made by `output_bytes`
+    pub fn output_bytevector(&self) -> Option<Vec<u8>> {
+        match &self.0.borrow().data {
+            PortData::BinaryOutput(Sink::Bytes(buffer)) => Some(buffer.clone()),
+            _ => None,
+        }
+    }
+
+    /// Takes any text the signal callback has left in a signalling port's buffer
+    pub fn flush(&self) -> String {
+        match &mut self.0.borrow_mut().data {
+            PortData::Output(Sink::Signal(buffer, _)) => std::mem::take(buffer),
+            _ => String::new(),
+        }
+    }
+}
+
+impl fmt::Debug for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Port")
+            .field("input", &self.is_input())
+            .field("binary", &self.is_binary())
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_binary() { "binary-" } else { "" };
+        if self.is_input() {
+            write!(f, "#<{}input-port>", kind)
+        } else {
+            write!(f, "#<{}output-port>", kind)
+        }
+    }
+}
+
+// Ports are compared by identity
+impl PartialEq for Port {
+    fn eq(&self, other: &Port) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
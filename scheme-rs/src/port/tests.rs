@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::*;
+use crate::error::LispError;
+
+#[test]
+fn string_input_ports() {
+    let port = Port::input_string("ab\ncd");
+    assert!(port.is_input());
+    assert_eq!(port.peek_char(), Ok(Some('a')));
+    assert_eq!(port.read_char(), Ok(Some('a')));
+    assert_eq!(port.read_line(), Ok(Some("b".to_string())));
+    assert_eq!(port.read_string(10), Ok(Some("cd".to_string())));
+    assert_eq!(port.read_char(), Ok(None));
+    assert_eq!(port.read_line(), Ok(None));
+    assert_eq!(port.read_string(0), Ok(Some("".to_string())));
+}
+
+#[test]
+fn reading_with_a_parser() {
+    let port = Port::input_string("abc def");
+    let word = port.read_with("read", |text, more| {
+        assert!(!more);
+        let word = text.split(' ').next().unwrap_or_default().to_string();
+        Ok(Some((word.len(), word)))
+    });
+    assert_eq!(word, Ok("abc".to_string()));
+    assert_eq!(port.read_string(10), Ok(Some(" def".to_string())));
+}
+
+#[test]
+fn reading_non_ascii_text() {
+    let port = Port::input_string("λé\nüx");
+    assert_eq!(port.peek_char(), Ok(Some('λ')));
+    assert_eq!(port.read_string(2), Ok(Some("λé".to_string())));
+    assert_eq!(port.read_line(), Ok(Some("".to_string())));
+    let word = port.read_with("read", |text, _| Ok(Some(("ü".len(), text.to_string()))));
+    assert_eq!(word, Ok("üx".to_string()));
+    assert_eq!(port.read_char(), Ok(Some('x')));
+    assert_eq!(port.read_char(), Ok(None));
+}
+
+#[test]
+fn interactive_input_ports() {
+    let port = Port::interactive();
+    assert_eq!(
+        port.read_char(),
+        Err(LispError::InputPending("read-char".to_string()))
+    );
+    assert_eq!(port.char_ready(), Ok(false));
+    port.push_input("ab");
+    assert_eq!(port.char_ready(), Ok(true));
+    assert_eq!(port.read_char(), Ok(Some('a')));
+    // A line isn't finished until its newline arrives
+    assert_eq!(
+        port.read_line(),
+        Err(LispError::InputPending("read-line".to_string()))
+    );
+    port.push_input("c\nd");
+    assert_eq!(port.read_line(), Ok(Some("bc".to_string())));
+
+    port.checkpoint();
+    assert_eq!(port.read_char(), Ok(Some('d')));
+    port.push_input("é");
+    assert_eq!(port.read_char(), Ok(Some('é')));
+    port.rewind();
+    assert_eq!(port.read_string(2), Ok(Some("dé".to_string())));
+}
+
+#[test]
+fn skipping_output() {
+    let port = Port::output_string();
+    port.write_str("abc").unwrap();
+    port.skip_output(4);
+    port.write_str("abc").unwrap();
+    port.write_str("déf").unwrap();
+    assert_eq!(port.output(), Some("abcéf".to_string()));
+    assert_eq!(port.written(), 9);
+}
+
+#[test]
+fn string_output_ports() {
+    let port = Port::output_string();
+    assert!(port.is_output());
+    port.write_str("foo").unwrap();
+    port.write_str("bar").unwrap();
+    assert_eq!(port.output(), Some("foobar".to_string()));
+    port.close();
+    assert!(port.write_str("baz").is_err());
+    assert!(port.read_char().is_err());
+}
+
+#[test]
+fn signalling_ports() {
+    let signalled = Rc::new(RefCell::new(vec![]));
+    let cs = signalled.clone();
+    let port = Port::signalling(Box::new(move |output: &mut String| {
+        cs.borrow_mut().push(output.clone());
+    }));
+    port.write_str("a").unwrap();
+    port.write_str("b").unwrap();
+    assert_eq!(*signalled.borrow(), vec!["a".to_string(), "ab".to_string()]);
+    assert_eq!(port.flush(), "ab");
+    assert_eq!(port.flush(), "");
+    assert_eq!(port.output(), None);
+}
+
+#[test]
+fn ports_are_compared_by_identity() {
+    let port = Port::output_string();
+    assert_eq!(port, port.clone());
+    assert_ne!(port, Port::output_string());
+}
@@ -14,32 +14,6 @@ fn is_boolean(args: Vec<LispVal>) -> LispResult<LispVal> {
     }
 }
 
-pub fn and(args: Vec<LispVal>) -> LispResult<LispVal> {
-    check_arity(&args, Arity::Min(1))?;
-    for (i, val) in args.iter().enumerate() {
-        if i == args.len() - 1 {
-            return Ok(val.clone());
-        }
-        if let LispVal::Bool(false) = val {
-            return Ok(LispVal::Bool(false));
-        }
-    }
-    Ok(LispVal::Bool(true))
-}
-
-pub fn or(args: Vec<LispVal>) -> LispResult<LispVal> {
-    check_arity(&args, Arity::Min(1))?;
-    for (i, val) in args.iter().enumerate() {
-        if i == args.len() - 1 {
-            return Ok(val.clone());
-        }
-        if let LispVal::Bool(true) = val {
-            return Ok(LispVal::Bool(true));
-        }
-    }
-    Ok(LispVal::Bool(false))
-}
-
 pub fn not(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(1, 1))?;
     match args[..] {
@@ -51,8 +25,6 @@ pub fn not(args: Vec<LispVal>) -> LispResult<LispVal> {
 pub fn boolean_primitives() -> Bindings {
     HashMap::from([
         mk_prim_fn_binding("boolean?", is_boolean),
-        mk_prim_fn_binding("and", and),
-        mk_prim_fn_binding("or", or),
         mk_prim_fn_binding("not", not),
     ])
 }
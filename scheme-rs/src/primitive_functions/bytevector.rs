@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::Bindings;
+use crate::error::{Arity, LispError, LispResult};
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::mk_prim_fn_binding;
+
+fn byte_arg(name: &str, val: &LispVal) -> LispResult<u8> {
+    match val {
+        LispVal::Integer(n) => u8::try_from(*n).map_err(|_| byte_error(name, val)),
+        _ => Err(byte_error(name, val)),
+    }
+}
+
+fn byte_error(name: &str, val: &LispVal) -> LispError {
+    LispError::GenericError(format!(
+        "{}: contract violation\nexpected: byte?\ngiven: {}",
+        name, val
+    ))
+}
+
+fn bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    let bytes = args
+        .iter()
+        .map(|arg| byte_arg("bytevector", arg))
+        .collect::<LispResult<Vec<u8>>>()?;
+    Ok(LispVal::Bytevector(Rc::new(RefCell::new(bytes))))
+}
+
+fn is_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::Bytevector(_))))
+}
+
+fn make_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let fill = match args.get(1) {
+        Some(arg) => byte_arg("make-bytevector", arg)?,
+        None => 0,
+    };
+    match &args[0] {
+        LispVal::Integer(n) if *n >= 0 => Ok(LispVal::Bytevector(Rc::new(RefCell::new(vec![
+            fill;
+            *n as usize
+        ])))),
+        arg => Err(LispError::GenericError(format!(
+            "make-bytevector: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}",
+            arg
+        ))),
+    }
+}
+
+fn bytevector_length(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Bytevector(bytes) => Ok(LispVal::Integer(bytes.borrow().len() as i64)),
+        arg => Err(LispError::GenericError(format!(
+            "bytevector-length: contract violation\nexpected: bytevector?\ngiven: {}",
+            arg
+        ))),
+    }
+}
+
+fn bytevector_u8_ref(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    match &args[..] {
+        [v @ LispVal::Bytevector(bytes), LispVal::Integer(n)] => {
+            let bytes = bytes.borrow();
+            match usize::try_from(*n).ok().and_then(|index| bytes.get(index)) {
+                Some(byte) => Ok(LispVal::Integer(*byte as i64)),
+                None => Err(LispError::GenericError(format!("bytevector-u8-ref: index is out of range\nindex: {}\nvalid range: [0, {}]\nbytevector: {}", n, bytes.len(), v))),
+            }
+        }
+        [LispVal::Bytevector(_), arg] => Err(LispError::GenericError(format!(
+            "bytevector-u8-ref: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}\nargument position: 2nd",
+            arg
+        ))),
+        [arg, _] => Err(LispError::GenericError(format!(
+            "bytevector-u8-ref: contract violation\nexpected: bytevector?\ngiven: {}\nargument position: 1st",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+fn bytevector_u8_set(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(3, 3))?;
+    match &args[..] {
+        [v @ LispVal::Bytevector(bytes), LispVal::Integer(n), val] => {
+            let byte = byte_arg("bytevector-u8-set!", val)?;
+            let len = bytes.borrow().len();
+            match usize::try_from(*n) {
+                Ok(index) if index < len => {
+                    bytes.borrow_mut()[index] = byte;
+                    Ok(LispVal::Void)
+                }
+                _ => Err(LispError::GenericError(format!("bytevector-u8-set!: index is out of range\nindex: {}\nvalid range: [0, {}]\nbytevector: {}", n, len, v))),
+            }
+        }
+        [LispVal::Bytevector(_), arg, _] => Err(LispError::GenericError(format!(
+            "bytevector-u8-set!: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}\nargument position: 2nd",
+            arg
+        ))),
+        [arg, _, _] => Err(LispError::GenericError(format!(
+            "bytevector-u8-set!: contract violation\nexpected: bytevector?\ngiven: {}\nargument position: 1st",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+pub fn bytevector_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("bytevector", bytevector),
+        mk_prim_fn_binding("bytevector?", is_bytevector),
+        mk_prim_fn_binding("make-bytevector", make_bytevector),
+        mk_prim_fn_binding("bytevector-length", bytevector_length),
+        mk_prim_fn_binding("bytevector-u8-ref", bytevector_u8_ref),
+        mk_prim_fn_binding("bytevector-u8-set!", bytevector_u8_set),
+    ])
+}
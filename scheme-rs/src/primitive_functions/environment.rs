@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::environment::{Bindings, Env};
+use crate::error::{Arity, LispError, LispResult};
+use crate::eval::eval;
+use crate::library::import;
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::primitive_functions;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::{mk_io_fn_binding, mk_prim_fn_binding};
+use crate::source::load;
+
+fn eval_in(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    match &args[..] {
+        [expr] => eval(env, expr),
+        [expr, LispVal::Environment(env)] => eval(env, expr),
+        [_, arg] => Err(LispError::GenericError(format!(
+            "eval: contract violation\nexpected: environment?\ngiven: {}\nargument position: 2nd",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+fn is_environment(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match args[..] {
+        [LispVal::Environment(_)] => Ok(LispVal::Bool(true)),
+        _ => Ok(LispVal::Bool(false)),
+    }
+}
+
+fn interaction_environment(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Environment(env.global()))
+}
+
+fn check_version(name: &str, args: &[LispVal]) -> LispResult<()> {
+    check_arity(args, Arity::MinMax(1, 1))?;
+    match args {
+        [LispVal::Integer(5)] | [LispVal::Integer(7)] => Ok(()),
+        [arg] => Err(LispError::GenericError(format!(
+            "{}: unsupported version\nexpected: (or/c 5 7)\ngiven: {}",
+            name, arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+// Every primitive, in a fresh environment separate from the caller's definitions
+fn scheme_report_environment(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_version("scheme-report-environment", &args)?;
+    Ok(LispVal::Environment(Env::new_sharing(
+        env,
+        primitive_functions(),
+    )))
+}
+
+// Only syntactic keywords, which are always available
+fn null_environment(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_version("null-environment", &args)?;
+    Ok(LispVal::Environment(Env::new_sharing(env, Bindings::new())))
+}
+
+// `(environment '(scheme base) '(only (scheme char) char?))`
+fn environment(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    let new_env = Env::new_sharing(env, Bindings::new());
+    import(&new_env, &args)?;
+    Ok(LispVal::Environment(new_env))
+}
+
+// `(load "file")` or `(load "file" env)`
+fn load_in(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    match &args[..] {
+        [path] => load(env, path),
+        [path, LispVal::Environment(env)] => load(env, path),
+        [_, arg] => Err(LispError::GenericError(format!(
+            "load: contract violation\nexpected: environment?\ngiven: {}\nargument position: 2nd",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+pub fn eval_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("eval", eval_in),
+        mk_io_fn_binding("environment", environment),
+    ])
+}
+
+pub fn repl_primitives() -> Bindings {
+    HashMap::from([mk_io_fn_binding(
+        "interaction-environment",
+        interaction_environment,
+    )])
+}
+
+pub fn load_primitives() -> Bindings {
+    HashMap::from([mk_io_fn_binding("load", load_in)])
+}
+
+pub fn environment_primitives() -> Bindings {
+    let mut bindings = eval_primitives();
+    bindings.extend(repl_primitives());
+    bindings.extend([
+        mk_prim_fn_binding("environment?", is_environment),
+        mk_io_fn_binding("scheme-report-environment", scheme_report_environment),
+        mk_io_fn_binding("null-environment", null_environment),
+    ]);
+    bindings
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::io;
+use std::rc::Rc;
+
+use crate::environment::{Bindings, Env};
+use crate::error::{Arity, LispError, LispResult};
+use crate::eval::apply;
+use crate::lisp_val::LispVal;
+use crate::port::Port;
+use crate::primitive_functions::io::call_and_close;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::contract_violation;
+use crate::primitive_functions::util::mk_io_fn_binding;
+
+fn path_arg<'a>(name: &str, args: &'a [LispVal], index: usize) -> LispResult<&'a str> {
+    match &args[index] {
+        LispVal::String(path) => Ok(path),
+        arg => Err(contract_violation(name, "path-string?", arg, index)),
+    }
+}
+
+fn system_error(name: &str, message: &str, path: &str, err: io::Error) -> LispError {
+    LispError::GenericError(format!(
+        "{}: {}\npath: {}\nsystem error: {}",
+        name, message, path, err
+    ))
+}
+
+// Paths are resolved from the top of the file system, not the file being loaded
+fn resolve(env: &Env, path: &str) -> String {
+    env.sources.file_system().resolve(path, None)
+}
+
+// Input files are read in full up front, so they behave like string ports
+fn open_input(env: &Env, name: &str, path: &str, binary: bool) -> LispResult<Port> {
+    let path = resolve(env, path);
+    let contents = env
+        .sources
+        .file_system()
+        .read(&path)
+        .map_err(|err| system_error(name, "cannot open input file", &path, err))?;
+    if binary {
+        Ok(Port::input_bytes(contents))
+    } else {
+        Ok(Port::input_string(&String::from_utf8_lossy(&contents)))
+    }
+}
+
+fn open_output(env: &Env, name: &str, path: &str, binary: bool) -> LispResult<Port> {
+    let path = resolve(env, path);
+    let file_system = env.sources.file_system();
+    file_system
+        .write(&path, &[])
+        .map_err(|err| system_error(name, "cannot open output file", &path, err))?;
+    Ok(Port::output_file(file_system, path, binary))
+}
+
+fn open_input_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = path_arg("open-input-file", &args, 0)?;
+    Ok(LispVal::Port(open_input(
+        env,
+        "open-input-file",
+        path,
+        false,
+    )?))
+}
+
+fn open_binary_input_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = path_arg("open-binary-input-file", &args, 0)?;
+    Ok(LispVal::Port(open_input(
+        env,
+        "open-binary-input-file",
+        path,
+        true,
+    )?))
+}
+
+fn open_output_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = path_arg("open-output-file", &args, 0)?;
+    Ok(LispVal::Port(open_output(
+        env,
+        "open-output-file",
+        path,
+        false,
+    )?))
+}
+
+fn open_binary_output_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = path_arg("open-binary-output-file", &args, 0)?;
+    Ok(LispVal::Port(open_output(
+        env,
+        "open-binary-output-file",
+        path,
+        true,
+    )?))
+}
+
+// `(call-with-input-file path proc)`: calls `proc` with a port reading the
+// file, closing it afterwards
+fn call_with_input_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let path = path_arg("call-with-input-file", &args, 0)?;
+    let port = open_input(env, "call-with-input-file", path, false)?;
+    call_and_close(env, port, args[1].clone())
+}
+
+fn call_with_output_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let path = path_arg("call-with-output-file", &args, 0)?;
+    let port = open_output(env, "call-with-output-file", path, false)?;
+    call_and_close(env, port, args[1].clone())
+}
+
+// `(with-input-from-file path thunk)`: calls `thunk` with the file as the
+// current input port
+fn with_input_from_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let path = path_arg("with-input-from-file", &args, 0)?;
+    let port = open_input(env, "with-input-from-file", path, false)?;
+    let result = env
+        .ports
+        .with_input(port.clone(), || apply(env, args[1].clone(), vec![]));
+    port.close();
+    result
+}
+
+fn with_output_to_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let path = path_arg("with-output-to-file", &args, 0)?;
+    let port = open_output(env, "with-output-to-file", path, false)?;
+    let result = env
+        .ports
+        .with_output(port.clone(), || apply(env, args[1].clone(), vec![]));
+    port.close();
+    result
+}
+
+fn file_exists(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = resolve(env, path_arg("file-exists?", &args, 0)?);
+    Ok(LispVal::Bool(env.sources.file_system().exists(&path)))
+}
+
+fn delete_file(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = resolve(env, path_arg("delete-file", &args, 0)?);
+    env.sources
+        .file_system()
+        .delete(&path)
+        .map_err(|err| system_error("delete-file", "cannot delete file", &path, err))?;
+    Ok(LispVal::Void)
+}
+
+// `(directory-files path)`: the names of the entries in a directory, sorted
+fn directory_files(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = resolve(env, path_arg("directory-files", &args, 0)?);
+    let names = env
+        .sources
+        .file_system()
+        .read_dir(&path)
+        .map_err(|err| system_error("directory-files", "cannot read directory", &path, err))?;
+    Ok(LispVal::List(Rc::new(
+        names.into_iter().map(LispVal::String).collect(),
+    )))
+}
+
+fn create_directory(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = resolve(env, path_arg("create-directory", &args, 0)?);
+    env.sources
+        .file_system()
+        .create_dir(&path)
+        .map_err(|err| system_error("create-directory", "cannot make directory", &path, err))?;
+    Ok(LispVal::Void)
+}
+
+fn file_size(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let path = resolve(env, path_arg("file-size", &args, 0)?);
+    let size = env
+        .sources
+        .file_system()
+        .size(&path)
+        .map_err(|err| system_error("file-size", "cannot get size", &path, err))?;
+    let size =
+        i64::try_from(size).map_err(|_| LispError::GenericError("weird file size".to_string()))?;
+    Ok(LispVal::Integer(size))
+}
+
+/// The procedures of `(scheme file)`
+pub fn file_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("open-input-file", open_input_file),
+        mk_io_fn_binding("open-binary-input-file", open_binary_input_file),
+        mk_io_fn_binding("open-output-file", open_output_file),
+        mk_io_fn_binding("open-binary-output-file", open_binary_output_file),
+        mk_io_fn_binding("call-with-input-file", call_with_input_file),
+        mk_io_fn_binding("call-with-output-file", call_with_output_file),
+        mk_io_fn_binding("with-input-from-file", with_input_from_file),
+        mk_io_fn_binding("with-output-to-file", with_output_to_file),
+        mk_io_fn_binding("file-exists?", file_exists),
+        mk_io_fn_binding("delete-file", delete_file),
+    ])
+}
+
+/// Directory helpers, which aren't part of any R7RS library
+pub fn directory_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("directory-files", directory_files),
+        mk_io_fn_binding("create-directory", create_directory),
+        mk_io_fn_binding("file-size", file_size),
+    ])
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use crate::environment::Bindings;
+use crate::error::{Arity, LispError, LispResult};
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::contract_violation;
+use crate::primitive_functions::util::mk_prim_fn_binding;
+
+// Fixnums are the integers that fit in an i64, which are always represented
+// as `LispVal::Integer`. These procedures only accept fixnums, so unlike the
+// generic arithmetic they never promote their arguments, and an overflowing
+// result is an error rather than a bignum.
+
+fn fixnum_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<i64> {
+    match args[index] {
+        LispVal::Integer(n) => Ok(n),
+        ref n => Err(contract_violation(name, "fixnum?", n, index)),
+    }
+}
+
+fn overflow(name: &str, args: &[LispVal]) -> LispError {
+    LispError::GenericError(format!(
+        "{}: result is not a fixnum\narguments: {}",
+        name,
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    ))
+}
+
+fn is_fixnum(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::Integer(_))))
+}
+
+fn fixnum_constant(args: Vec<LispVal>, n: i64) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Integer(n))
+}
+
+fn fx_compare(name: &str, args: Vec<LispVal>, holds: fn(i64, i64) -> bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(2))?;
+    let mut previous = fixnum_arg(name, &args, 0)?;
+    let mut result = true;
+    // Every argument is checked, even once the result is known
+    for index in 1..args.len() {
+        let n = fixnum_arg(name, &args, index)?;
+        result &= holds(previous, n);
+        previous = n;
+    }
+    Ok(LispVal::Bool(result))
+}
+
+fn fx_predicate(name: &str, args: Vec<LispVal>, holds: fn(i64) -> bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(holds(fixnum_arg(name, &args, 0)?)))
+}
+
+// Applies an operation to two fixnums, which gives None if the result
+// overflows
+fn fx_binary(
+    name: &str,
+    args: Vec<LispVal>,
+    op: fn(i64, i64) -> Option<i64>,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let m = fixnum_arg(name, &args, 0)?;
+    let n = fixnum_arg(name, &args, 1)?;
+    op(m, n)
+        .map(LispVal::Integer)
+        .ok_or_else(|| overflow(name, &args))
+}
+
+fn fx_sub(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    if args.len() == 1 {
+        let n = fixnum_arg("fx-", &args, 0)?;
+        n.checked_neg()
+            .map(LispVal::Integer)
+            .ok_or_else(|| overflow("fx-", &args))
+    } else {
+        fx_binary("fx-", args, i64::checked_sub)
+    }
+}
+
+// `fxquotient` and `fxremainder` round towards zero, like `quotient` and
+// `remainder`
+fn fx_divide(name: &str, args: Vec<LispVal>, remainder: bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let m = fixnum_arg(name, &args, 0)?;
+    match fixnum_arg(name, &args, 1)? {
+        0 => Err(LispError::GenericError(format!(
+            "{}: undefined for 0",
+            name
+        ))),
+        n if remainder => Ok(LispVal::Integer(m.wrapping_rem(n))),
+        n => m
+            .checked_div(n)
+            .map(LispVal::Integer)
+            .ok_or_else(|| overflow(name, &args)),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DivMod {
+    Div,
+    Mod,
+    Both,
+}
+
+// `fxdiv` and `fxmod` give a modulus in [0, |n|), while `fxdiv0` and
+// `fxmod0` centre it on zero, in [-|n|/2, |n|/2). They're worked out in i128,
+// where neither can overflow, and only the quotient can be out of range
+// afterwards, e.g. `(fxdiv (least-fixnum) -1)`.
+fn fx_div_mod(
+    name: &str,
+    args: Vec<LispVal>,
+    centred: bool,
+    result: DivMod,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let m = i128::from(fixnum_arg(name, &args, 0)?);
+    let n = i128::from(fixnum_arg(name, &args, 1)?);
+    if n == 0 {
+        return Err(LispError::GenericError(format!(
+            "{}: undefined for 0",
+            name
+        )));
+    }
+    let (mut div, mut modulo) = (m.div_euclid(n), m.rem_euclid(n));
+    if centred && modulo * 2 >= n.abs() {
+        div += n.signum();
+        modulo -= n.abs();
+    }
+    let div = || {
+        i64::try_from(div)
+            .map(LispVal::Integer)
+            .map_err(|_| overflow(name, &args))
+    };
+    let modulo = LispVal::Integer(modulo as i64);
+    match result {
+        DivMod::Div => div(),
+        DivMod::Mod => Ok(modulo),
+        // There are no multiple values yet, so both are returned as a list,
+        // as `floor/` does
+        DivMod::Both => Ok(LispVal::List(vec![div()?, modulo].into())),
+    }
+}
+
+fn fx_abs(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    fixnum_arg("fxabs", &args, 0)?
+        .checked_abs()
+        .map(LispVal::Integer)
+        .ok_or_else(|| overflow("fxabs", &args))
+}
+
+fn fx_fold(
+    name: &str,
+    args: Vec<LispVal>,
+    min_args: i8,
+    identity: i64,
+    op: fn(i64, i64) -> i64,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(min_args))?;
+    (0..args.len())
+        .try_fold(identity, |m, index| {
+            Ok(op(m, fixnum_arg(name, &args, index)?))
+        })
+        .map(LispVal::Integer)
+}
+
+fn fx_not(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Integer(!fixnum_arg("fxnot", &args, 0)?))
+}
+
+// Shifts left for a positive count and right for a negative one. A left
+// shift overflows if it loses any bits that differ from the sign.
+fn fx_shift(m: i64, count: i64) -> Option<i64> {
+    if count >= 0 {
+        let shift = u32::try_from(count)
+            .ok()
+            .filter(|shift| *shift < i64::BITS)?;
+        Some(m << shift).filter(|n| n >> shift == m)
+    } else {
+        Some(m >> count.unsigned_abs().min(u64::from(i64::BITS - 1)))
+    }
+}
+
+pub fn fixnum_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("fixnum?", is_fixnum),
+        mk_prim_fn_binding("fixnum-width", |args| {
+            fixnum_constant(args, i64::from(i64::BITS))
+        }),
+        mk_prim_fn_binding("least-fixnum", |args| fixnum_constant(args, i64::MIN)),
+        mk_prim_fn_binding("greatest-fixnum", |args| fixnum_constant(args, i64::MAX)),
+        mk_prim_fn_binding("fx=?", |args| fx_compare("fx=?", args, |m, n| m == n)),
+        mk_prim_fn_binding("fx<?", |args| fx_compare("fx<?", args, |m, n| m < n)),
+        mk_prim_fn_binding("fx>?", |args| fx_compare("fx>?", args, |m, n| m > n)),
+        mk_prim_fn_binding("fx<=?", |args| fx_compare("fx<=?", args, |m, n| m <= n)),
+        mk_prim_fn_binding("fx>=?", |args| fx_compare("fx>=?", args, |m, n| m >= n)),
+        mk_prim_fn_binding("fxzero?", |args| fx_predicate("fxzero?", args, |n| n == 0)),
+        mk_prim_fn_binding("fxpositive?", |args| {
+            fx_predicate("fxpositive?", args, i64::is_positive)
+        }),
+        mk_prim_fn_binding("fxnegative?", |args| {
+            fx_predicate("fxnegative?", args, i64::is_negative)
+        }),
+        mk_prim_fn_binding("fxodd?", |args| {
+            fx_predicate("fxodd?", args, |n| n % 2 != 0)
+        }),
+        mk_prim_fn_binding("fxeven?", |args| {
+            fx_predicate("fxeven?", args, |n| n % 2 == 0)
+        }),
+        mk_prim_fn_binding("fxmax", |args| {
+            fx_fold("fxmax", args, 1, i64::MIN, i64::max)
+        }),
+        mk_prim_fn_binding("fxmin", |args| {
+            fx_fold("fxmin", args, 1, i64::MAX, i64::min)
+        }),
+        mk_prim_fn_binding("fx+", |args| fx_binary("fx+", args, i64::checked_add)),
+        mk_prim_fn_binding("fx-", fx_sub),
+        mk_prim_fn_binding("fx*", |args| fx_binary("fx*", args, i64::checked_mul)),
+        mk_prim_fn_binding("fxquotient", |args| fx_divide("fxquotient", args, false)),
+        mk_prim_fn_binding("fxremainder", |args| fx_divide("fxremainder", args, true)),
+        mk_prim_fn_binding("fxdiv", |args| {
+            fx_div_mod("fxdiv", args, false, DivMod::Div)
+        }),
+        mk_prim_fn_binding("fxmod", |args| {
+            fx_div_mod("fxmod", args, false, DivMod::Mod)
+        }),
+        mk_prim_fn_binding("fxdiv-and-mod", |args| {
+            fx_div_mod("fxdiv-and-mod", args, false, DivMod::Both)
+        }),
+        mk_prim_fn_binding("fxdiv0", |args| {
+            fx_div_mod("fxdiv0", args, true, DivMod::Div)
+        }),
+        mk_prim_fn_binding("fxmod0", |args| {
+            fx_div_mod("fxmod0", args, true, DivMod::Mod)
+        }),
+        mk_prim_fn_binding("fxdiv0-and-mod0", |args| {
+            fx_div_mod("fxdiv0-and-mod0", args, true, DivMod::Both)
+        }),
+        mk_prim_fn_binding("fxabs", fx_abs),
+        mk_prim_fn_binding("fxnot", fx_not),
+        mk_prim_fn_binding("fxand", |args| fx_fold("fxand", args, 0, -1, |m, n| m & n)),
+        mk_prim_fn_binding("fxior", |args| fx_fold("fxior", args, 0, 0, |m, n| m | n)),
+        mk_prim_fn_binding("fxxor", |args| fx_fold("fxxor", args, 0, 0, |m, n| m ^ n)),
+        mk_prim_fn_binding("fxarithmetic-shift", |args| {
+            fx_binary("fxarithmetic-shift", args, fx_shift)
+        }),
+    ])
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::environment::Bindings;
+use crate::error::{Arity, LispResult};
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::contract_violation;
+use crate::primitive_functions::util::mk_prim_fn_binding;
+
+// Flonums are the inexact reals, i.e. `LispVal::Float`. These procedures only
+// accept flonums, so they skip the numeric tower entirely.
+
+fn flonum_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<f64> {
+    match args[index] {
+        LispVal::Float(f) => Ok(f),
+        ref f => Err(contract_violation(name, "flonum?", f, index)),
+    }
+}
+
+fn is_flonum(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::Float(_))))
+}
+
+fn fl_compare(name: &str, args: Vec<LispVal>, holds: fn(f64, f64) -> bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(2))?;
+    let mut previous = flonum_arg(name, &args, 0)?;
+    let mut result = true;
+    // Every argument is checked, even once the result is known
+    for index in 1..args.len() {
+        let f = flonum_arg(name, &args, index)?;
+        result &= holds(previous, f);
+        previous = f;
+    }
+    Ok(LispVal::Bool(result))
+}
+
+fn fl_predicate(name: &str, args: Vec<LispVal>, holds: fn(f64) -> bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(holds(flonum_arg(name, &args, 0)?)))
+}
+
+fn fl_fold(
+    name: &str,
+    args: Vec<LispVal>,
+    min_args: i8,
+    identity: f64,
+    op: fn(f64, f64) -> f64,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(min_args))?;
+    (0..args.len())
+        .try_fold(identity, |m, index| {
+            Ok(op(m, flonum_arg(name, &args, index)?))
+        })
+        .map(LispVal::Float)
+}
+
+// `fl-` and `fl/`, which negate or take the reciprocal of a single argument
+fn fl_inverse(
+    name: &str,
+    args: Vec<LispVal>,
+    identity: f64,
+    op: fn(f64, f64) -> f64,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(1))?;
+    let first = flonum_arg(name, &args, 0)?;
+    if args.len() == 1 {
+        return Ok(LispVal::Float(op(identity, first)));
+    }
+    (1..args.len())
+        .try_fold(first, |m, index| Ok(op(m, flonum_arg(name, &args, index)?)))
+        .map(LispVal::Float)
+}
+
+fn fl_unary(name: &str, args: Vec<LispVal>, op: fn(f64) -> f64) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Float(op(flonum_arg(name, &args, 0)?)))
+}
+
+// `(fllog x [base])`
+fn fl_log(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let x = flonum_arg("fllog", &args, 0)?;
+    match args.len() {
+        1 => Ok(LispVal::Float(x.ln())),
+        _ => Ok(LispVal::Float(x.ln() / flonum_arg("fllog", &args, 1)?.ln())),
+    }
+}
+
+// `(flatan y [x])`
+fn fl_atan(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let y = flonum_arg("flatan", &args, 0)?;
+    match args.len() {
+        1 => Ok(LispVal::Float(y.atan())),
+        _ => Ok(LispVal::Float(y.atan2(flonum_arg("flatan", &args, 1)?))),
+    }
+}
+
+fn fl_expt(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let base = flonum_arg("flexpt", &args, 0)?;
+    Ok(LispVal::Float(base.powf(flonum_arg("flexpt", &args, 1)?)))
+}
+
+fn fixnum_to_flonum(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match args[0] {
+        LispVal::Integer(n) => Ok(LispVal::Float(n as f64)),
+        ref n => Err(contract_violation("fixnum->flonum", "fixnum?", n, 0)),
+    }
+}
+
+pub fn flonum_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("flonum?", is_flonum),
+        mk_prim_fn_binding("fl=?", |args| fl_compare("fl=?", args, |m, n| m == n)),
+        mk_prim_fn_binding("fl<?", |args| fl_compare("fl<?", args, |m, n| m < n)),
+        mk_prim_fn_binding("fl>?", |args| fl_compare("fl>?", args, |m, n| m > n)),
+        mk_prim_fn_binding("fl<=?", |args| fl_compare("fl<=?", args, |m, n| m <= n)),
+        mk_prim_fn_binding("fl>=?", |args| fl_compare("fl>=?", args, |m, n| m >= n)),
+        mk_prim_fn_binding("flzero?", |args| {
+            fl_predicate("flzero?", args, |f| f == 0.0)
+        }),
+        mk_prim_fn_binding("flpositive?", |args| {
+            fl_predicate("flpositive?", args, |f| f > 0.0)
+        }),
+        mk_prim_fn_binding("flnegative?", |args| {
+            fl_predicate("flnegative?", args, |f| f < 0.0)
+        }),
+        mk_prim_fn_binding("flinteger?", |args| {
+            fl_predicate("flinteger?", args, |f| f.is_finite() && f.fract() == 0.0)
+        }),
+        mk_prim_fn_binding("flfinite?", |args| {
+            fl_predicate("flfinite?", args, f64::is_finite)
+        }),
+        mk_prim_fn_binding("flinfinite?", |args| {
+            fl_predicate("flinfinite?", args, f64::is_infinite)
+        }),
+        mk_prim_fn_binding("flnan?", |args| fl_predicate("flnan?", args, f64::is_nan)),
+        mk_prim_fn_binding("flmax", |args| {
+            fl_fold("flmax", args, 1, f64::NEG_INFINITY, f64::max)
+        }),
+        mk_prim_fn_binding("flmin", |args| {
+            fl_fold("flmin", args, 1, f64::INFINITY, f64::min)
+        }),
+        mk_prim_fn_binding("fl+", |args| fl_fold("fl+", args, 0, 0.0, |m, n| m + n)),
+        mk_prim_fn_binding("fl*", |args| fl_fold("fl*", args, 0, 1.0, |m, n| m * n)),
+        mk_prim_fn_binding("fl-", |args| fl_inverse("fl-", args, 0.0, |m, n| m - n)),
+        mk_prim_fn_binding("fl/", |args| fl_inverse("fl/", args, 1.0, |m, n| m / n)),
+        mk_prim_fn_binding("flabs", |args| fl_unary("flabs", args, f64::abs)),
+        mk_prim_fn_binding("flfloor", |args| fl_unary("flfloor", args, f64::floor)),
+        mk_prim_fn_binding("flceiling", |args| fl_unary("flceiling", args, f64::ceil)),
+        mk_prim_fn_binding("flround", |args| {
+            fl_unary("flround", args, f64::round_ties_even)
+        }),
+        mk_prim_fn_binding("fltruncate", |args| {
+            fl_unary("fltruncate", args, f64::trunc)
+        }),
+        mk_prim_fn_binding("flsqrt", |args| fl_unary("flsqrt", args, f64::sqrt)),
+        mk_prim_fn_binding("flexp", |args| fl_unary("flexp", args, f64::exp)),
+        mk_prim_fn_binding("fllog", fl_log),
+        mk_prim_fn_binding("flsin", |args| fl_unary("flsin", args, f64::sin)),
+        mk_prim_fn_binding("flcos", |args| fl_unary("flcos", args, f64::cos)),
+        mk_prim_fn_binding("fltan", |args| fl_unary("fltan", args, f64::tan)),
+        mk_prim_fn_binding("flasin", |args| fl_unary("flasin", args, f64::asin)),
+        mk_prim_fn_binding("flacos", |args| fl_unary("flacos", args, f64::acos)),
+        mk_prim_fn_binding("flatan", fl_atan),
+        mk_prim_fn_binding("flexpt", fl_expt),
+        mk_prim_fn_binding("fixnum->flonum", fixnum_to_flonum),
+    ])
+}
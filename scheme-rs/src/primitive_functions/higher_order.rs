@@ -0,0 +1,227 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::{Bindings, Env};
+use crate::error::{Arity, LispError, LispResult};
+use crate::eval::apply;
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::primitive_functions::equal;
+use crate::primitive_functions::procedure::is_procedure_value;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::mk_io_fn_binding;
+
+fn procedure_arg(name: &str, val: &LispVal) -> LispResult<LispVal> {
+    if is_procedure_value(val) {
+        Ok(val.clone())
+    } else {
+        Err(LispError::GenericError(format!(
+            "{}: contract violation\nexpected: procedure?\ngiven: {}",
+            name, val
+        )))
+    }
+}
+
+fn list_arg(name: &str, val: &LispVal) -> LispResult<Vec<LispVal>> {
+    match val {
+        LispVal::List(xs) => Ok(xs.to_vec()),
+        _ => Err(LispError::GenericError(format!(
+            "{}: contract violation\nexpected: list?\ngiven: {}",
+            name, val
+        ))),
+    }
+}
+
+fn vector_arg(name: &str, val: &LispVal) -> LispResult<Vec<LispVal>> {
+    match val {
+        LispVal::Vector(xs) => Ok(xs.borrow().clone()),
+        _ => Err(LispError::GenericError(format!(
+            "{}: contract violation\nexpected: vector?\ngiven: {}",
+            name, val
+        ))),
+    }
+}
+
+fn string_arg(name: &str, val: &LispVal) -> LispResult<Vec<LispVal>> {
+    match val {
+        LispVal::String(s) => Ok(s.chars().map(LispVal::Char).collect()),
+        _ => Err(LispError::GenericError(format!(
+            "{}: contract violation\nexpected: string?\ngiven: {}",
+            name, val
+        ))),
+    }
+}
+
+fn is_true(val: &LispVal) -> bool {
+    !matches!(val, LispVal::Bool(false))
+}
+
+// `(apply + 1 2 '(3 4))`: the last argument holds the rest of the arguments
+fn apply_proc(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(2))?;
+    let function = procedure_arg("apply", &args[0])?;
+    let mut spread = args[1..args.len() - 1].to_vec();
+    spread.extend(list_arg("apply", &args[args.len() - 1])?);
+    apply(env, function, spread)
+}
+
+/// Calls `function` on the nth elements of each sequence in turn, stopping
+/// at the end of the shortest one.
+fn map_sequences(
+    env: &Env,
+    name: &str,
+    args: Vec<LispVal>,
+    elements: fn(&str, &LispVal) -> LispResult<Vec<LispVal>>,
+) -> LispResult<Vec<LispVal>> {
+    check_arity(&args, Arity::Min(2))?;
+    let function = procedure_arg(name, &args[0])?;
+    let sequences = args[1..]
+        .iter()
+        .map(|arg| elements(name, arg))
+        .collect::<LispResult<Vec<Vec<LispVal>>>>()?;
+    let len = sequences.iter().map(Vec::len).min().unwrap_or(0);
+    (0..len)
+        .map(|i| {
+            let args = sequences.iter().map(|xs| xs[i].clone()).collect();
+            apply(env, function.clone(), args)
+        })
+        .collect()
+}
+
+fn map(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    let results = map_sequences(env, "map", args, list_arg)?;
+    Ok(LispVal::List(Rc::new(results)))
+}
+
+fn for_each(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    map_sequences(env, "for-each", args, list_arg)?;
+    Ok(LispVal::Void)
+}
+
+fn vector_map(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    let results = map_sequences(env, "vector-map", args, vector_arg)?;
+    Ok(LispVal::Vector(Rc::new(RefCell::new(results))))
+}
+
+fn vector_for_each(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    map_sequences(env, "vector-for-each", args, vector_arg)?;
+    Ok(LispVal::Void)
+}
+
+fn string_map(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    let results = map_sequences(env, "string-map", args, string_arg)?;
+    results
+        .into_iter()
+        .map(|val| match val {
+            LispVal::Char(c) => Ok(c),
+            val => Err(LispError::GenericError(format!(
+                "string-map: contract violation\nexpected: char?\ngiven: {}",
+                val
+            ))),
+        })
+        .collect::<LispResult<String>>()
+        .map(LispVal::String)
+}
+
+fn string_for_each(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    map_sequences(env, "string-for-each", args, string_arg)?;
+    Ok(LispVal::Void)
+}
+
+// Compares with `equal?` unless a predicate is given
+fn matches(env: &Env, compare: &Option<LispVal>, x: &LispVal, y: &LispVal) -> LispResult<bool> {
+    match compare {
+        Some(compare) => Ok(is_true(&apply(
+            env,
+            compare.clone(),
+            vec![x.clone(), y.clone()],
+        )?)),
+        None => Ok(is_true(&equal(vec![x.clone(), y.clone()])?)),
+    }
+}
+
+fn compare_arg(name: &str, args: &[LispVal]) -> LispResult<Option<LispVal>> {
+    args.get(2).map(|arg| procedure_arg(name, arg)).transpose()
+}
+
+// `(member x list [compare])`: the first tail of `list` whose car matches `x`
+fn member(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 3))?;
+    let compare = compare_arg("member", &args)?;
+    let xs = list_arg("member", &args[1])?;
+    for (i, x) in xs.iter().enumerate() {
+        if matches(env, &compare, &args[0], x)? {
+            return Ok(LispVal::List(Rc::new(xs[i..].to_vec())));
+        }
+    }
+    Ok(LispVal::Bool(false))
+}
+
+// `(assoc key alist [compare])`: the first pair in `alist` whose car matches `key`
+fn assoc(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 3))?;
+    let compare = compare_arg("assoc", &args)?;
+    for pair in list_arg("assoc", &args[1])? {
+        let key = match &pair {
+            LispVal::List(xs) if !xs.is_empty() => &xs[0],
+            LispVal::DottedList(xs, _) => &xs[0],
+            _ => {
+                return Err(LispError::GenericError(format!(
+                    "assoc: contract violation\nexpected: pair?\ngiven: {}",
+                    pair
+                )))
+            }
+        };
+        if matches(env, &compare, &args[0], key)? {
+            return Ok(pair.clone());
+        }
+    }
+    Ok(LispVal::Bool(false))
+}
+
+// A stable merge sort. `slice::sort_by` can't be used, since the comparator
+// can fail and isn't guaranteed to be a total order.
+fn merge_sort(env: &Env, less_than: &LispVal, xs: Vec<LispVal>) -> LispResult<Vec<LispVal>> {
+    if xs.len() <= 1 {
+        return Ok(xs);
+    }
+    let mut left = xs;
+    let right = left.split_off(left.len() / 2);
+    let mut left = merge_sort(env, less_than, left)?.into_iter().peekable();
+    let mut right = merge_sort(env, less_than, right)?.into_iter().peekable();
+    let mut result = vec![];
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        // Only take from the right when it's strictly less, to keep the sort stable
+        if is_true(&apply(env, less_than.clone(), vec![y.clone(), x.clone()])?) {
+            result.extend(right.next());
+        } else {
+            result.extend(left.next());
+        }
+    }
+    result.extend(left);
+    result.extend(right);
+    Ok(result)
+}
+
+// `(sort list less-than?)`
+fn sort(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let xs = list_arg("sort", &args[0])?;
+    let less_than = procedure_arg("sort", &args[1])?;
+    Ok(LispVal::List(Rc::new(merge_sort(env, &less_than, xs)?)))
+}
+
+pub fn higher_order_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("apply", apply_proc),
+        mk_io_fn_binding("map", map),
+        mk_io_fn_binding("for-each", for_each),
+        mk_io_fn_binding("vector-map", vector_map),
+        mk_io_fn_binding("vector-for-each", vector_for_each),
+        mk_io_fn_binding("string-map", string_map),
+        mk_io_fn_binding("string-for-each", string_for_each),
+        mk_io_fn_binding("member", member),
+        mk_io_fn_binding("assoc", assoc),
+        mk_io_fn_binding("sort", sort),
+    ])
+}
@@ -0,0 +1,604 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::{Bindings, Env};
+use crate::error::{Arity, LispError, LispResult};
+use crate::eval::apply;
+use crate::lisp_val::LispVal;
+use crate::parser::read_datum;
+use crate::port::Port;
+use crate::primitive_functions::util::{check_arity, contract_violation};
+use crate::primitive_functions::util::{mk_io_fn_binding, mk_prim_fn_binding};
+use crate::printer::{self, print, Mode};
+
+fn open_port(name: &str, port: Port) -> LispResult<Port> {
+    if port.is_open() {
+        Ok(port)
+    } else {
+        Err(LispError::GenericError(format!(
+            "{}: port is closed\nport: {}",
+            name, port
+        )))
+    }
+}
+
+/// The open textual output port at `args[index]`, defaulting to the current output port
+fn output_port_arg(env: &Env, name: &str, args: &[LispVal], index: usize) -> LispResult<Port> {
+    match args.get(index) {
+        None => open_port(name, env.ports.current_output()),
+        Some(LispVal::Port(port)) if port.is_output() && !port.is_binary() => {
+            open_port(name, port.clone())
+        }
+        Some(arg @ LispVal::Port(port)) if port.is_output() => {
+            Err(contract_violation(name, "textual-port?", arg, index))
+        }
+        Some(arg) => Err(contract_violation(name, "output-port?", arg, index)),
+    }
+}
+
+/// The open textual input port at `args[index]`, defaulting to the current input port
+fn input_port_arg(env: &Env, name: &str, args: &[LispVal], index: usize) -> LispResult<Port> {
+    match args.get(index) {
+        None => open_port(name, env.ports.current_input()),
+        Some(LispVal::Port(port)) if port.is_input() && !port.is_binary() => {
+            open_port(name, port.clone())
+        }
+        Some(arg @ LispVal::Port(port)) if port.is_input() => {
+            Err(contract_violation(name, "textual-port?", arg, index))
+        }
+        Some(arg) => Err(contract_violation(name, "input-port?", arg, index)),
+    }
+}
+
+/// The open binary output port at `args[index]`. Unlike textual ports, there's
+/// no current binary port to default to.
+fn binary_output_port_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<Port> {
+    match &args[index] {
+        LispVal::Port(port) if port.is_output() && port.is_binary() => {
+            open_port(name, port.clone())
+        }
+        arg => Err(contract_violation(
+            name,
+            "(and/c output-port? binary-port?)",
+            arg,
+            index,
+        )),
+    }
+}
+
+fn binary_input_port_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<Port> {
+    match &args[index] {
+        LispVal::Port(port) if port.is_input() && port.is_binary() => open_port(name, port.clone()),
+        arg => Err(contract_violation(
+            name,
+            "(and/c input-port? binary-port?)",
+            arg,
+            index,
+        )),
+    }
+}
+
+fn bytevector_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<Vec<u8>> {
+    match &args[index] {
+        LispVal::Bytevector(bytes) => Ok(bytes.borrow().clone()),
+        arg => Err(contract_violation(name, "bytevector?", arg, index)),
+    }
+}
+
+fn bytevector_val(bytes: Vec<u8>) -> LispVal {
+    LispVal::Bytevector(Rc::new(RefCell::new(bytes)))
+}
+
+fn port_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<Port> {
+    match &args[index] {
+        LispVal::Port(port) => Ok(port.clone()),
+        arg => Err(contract_violation(name, "port?", arg, index)),
+    }
+}
+
+fn index_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<Option<usize>> {
+    match args.get(index) {
+        None => Ok(None),
+        Some(LispVal::Integer(n)) if *n >= 0 => Ok(Some(*n as usize)),
+        Some(arg) => Err(contract_violation(
+            name,
+            "exact-nonnegative-integer?",
+            arg,
+            index,
+        )),
+    }
+}
+
+fn eof_or(val: Option<LispVal>) -> LispVal {
+    val.unwrap_or(LispVal::Eof)
+}
+
+// `write`, `write-shared` and `write-simple` differ only in how they label
+// shared structure (see `printer::Mode`)
+fn write_with(env: &Env, name: &str, mode: Mode, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let port = output_port_arg(env, name, &args, 1)?;
+    port.write_str(&print(&args[0], mode))?;
+    Ok(LispVal::Void)
+}
+
+fn write(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    write_with(env, "write", Mode::Write, args)
+}
+
+fn write_shared(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    write_with(env, "write-shared", Mode::WriteShared, args)
+}
+
+fn write_simple(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    write_with(env, "write-simple", Mode::WriteSimple, args)
+}
+
+fn display(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let port = output_port_arg(env, "display", &args, 1)?;
+    port.write_str(&print(&args[0], Mode::Display))?;
+    Ok(LispVal::Void)
+}
+
+/// The width `pretty-print` fits its output to
+const PRETTY_WIDTH: usize = 80;
+
+// `(pretty-print obj [port])`: writes `obj` over as many lines as it takes to
+// fit the width, followed by a newline
+fn pretty_print(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let port = output_port_arg(env, "pretty-print", &args, 1)?;
+    port.write_str(&format!(
+        "{}\n",
+        printer::pretty_print(&args[0], PRETTY_WIDTH)
+    ))?;
+    Ok(LispVal::Void)
+}
+
+fn newline(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = output_port_arg(env, "newline", &args, 0)?;
+    port.write_str("\n")?;
+    Ok(LispVal::Void)
+}
+
+// `(write-string string [port [start [end]]])`
+fn write_string(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 4))?;
+    let s = match &args[0] {
+        LispVal::String(s) => s,
+        arg => return Err(contract_violation("write-string", "string?", arg, 0)),
+    };
+    let port = output_port_arg(env, "write-string", &args, 1)?;
+    let chars = s.chars().collect::<Vec<char>>();
+    let start = index_arg("write-string", &args, 2)?.unwrap_or(0);
+    let end = index_arg("write-string", &args, 3)?.unwrap_or(chars.len());
+    if start > end || end > chars.len() {
+        return Err(LispError::GenericError(format!(
+            "write-string: index is out of range\nstarting index: {}\nending index: {}\nvalid range: [0, {}]\nstring: {}",
+            start,
+            end,
+            chars.len(),
+            args[0]
+        )));
+    }
+    port.write_str(&chars[start..end].iter().collect::<String>())?;
+    Ok(LispVal::Void)
+}
+
+fn write_char(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let c = match &args[0] {
+        LispVal::Char(c) => *c,
+        arg => return Err(contract_violation("write-char", "char?", arg, 0)),
+    };
+    let port = output_port_arg(env, "write-char", &args, 1)?;
+    port.write_str(&c.to_string())?;
+    Ok(LispVal::Void)
+}
+
+fn read_char(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = input_port_arg(env, "read-char", &args, 0)?;
+    Ok(eof_or(port.read_char()?.map(LispVal::Char)))
+}
+
+fn peek_char(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = input_port_arg(env, "peek-char", &args, 0)?;
+    Ok(eof_or(port.peek_char()?.map(LispVal::Char)))
+}
+
+fn read_line(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = input_port_arg(env, "read-line", &args, 0)?;
+    Ok(eof_or(port.read_line()?.map(LispVal::String)))
+}
+
+// `(read-string k [port])`
+fn read_string(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let k = index_arg("read-string", &args, 0)?.unwrap_or_default();
+    let port = input_port_arg(env, "read-string", &args, 1)?;
+    Ok(eof_or(port.read_string(k)?.map(LispVal::String)))
+}
+
+// `(read [port])`: the next datum from the port, or the eof object
+fn read(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = input_port_arg(env, "read", &args, 0)?;
+    port.read_with("read", |text, more| {
+        let (rest, val) = match read_datum(text) {
+            Ok(read) => read,
+            // An unclosed list or string might be finished by more input
+            Err(err) if more && err.starts_with("read: expected") => return Ok(None),
+            Err(err) => return Err(LispError::GenericError(err)),
+        };
+        // A datum running right up to the end, e.g. `12`, might go on in
+        // more input, unless it ends in a delimiter
+        if more && (val.is_none() || (rest.is_empty() && !text.ends_with([')', ']', '}', '"']))) {
+            return Ok(None);
+        }
+        let used = text.len() - rest.len();
+        Ok(Some((used, eof_or(val))))
+    })
+}
+
+fn char_ready(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let port = input_port_arg(env, "char-ready?", &args, 0)?;
+    Ok(LispVal::Bool(port.char_ready()?))
+}
+
+fn read_u8(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let port = binary_input_port_arg("read-u8", &args, 0)?;
+    Ok(eof_or(
+        port.read_u8()?.map(|byte| LispVal::Integer(byte as i64)),
+    ))
+}
+
+fn peek_u8(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let port = binary_input_port_arg("peek-u8", &args, 0)?;
+    Ok(eof_or(
+        port.peek_u8()?.map(|byte| LispVal::Integer(byte as i64)),
+    ))
+}
+
+// Bytes are never waited on, just as with `char-ready?`
+fn u8_ready(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    binary_input_port_arg("u8-ready?", &args, 0)?;
+    Ok(LispVal::Bool(true))
+}
+
+// `(read-bytevector k port)`
+fn read_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let k = index_arg("read-bytevector", &args, 0)?.unwrap_or_default();
+    let port = binary_input_port_arg("read-bytevector", &args, 1)?;
+    Ok(eof_or(port.read_bytes(k)?.map(bytevector_val)))
+}
+
+fn write_u8(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let byte = match &args[0] {
+        LispVal::Integer(n) if (0..=255).contains(n) => *n as u8,
+        arg => return Err(contract_violation("write-u8", "byte?", arg, 0)),
+    };
+    let port = binary_output_port_arg("write-u8", &args, 1)?;
+    port.write_bytes(&[byte])?;
+    Ok(LispVal::Void)
+}
+
+// `(write-bytevector bytevector port [start [end]])`
+fn write_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 4))?;
+    let bytes = bytevector_arg("write-bytevector", &args, 0)?;
+    let port = binary_output_port_arg("write-bytevector", &args, 1)?;
+    let start = index_arg("write-bytevector", &args, 2)?.unwrap_or(0);
+    let end = index_arg("write-bytevector", &args, 3)?.unwrap_or(bytes.len());
+    if start > end || end > bytes.len() {
+        return Err(LispError::GenericError(format!(
+            "write-bytevector: index is out of range\nstarting index: {}\nending index: {}\nvalid range: [0, {}]\nbytevector: {}",
+            start,
+            end,
+            bytes.len(),
+            args[0]
+        )));
+    }
+    port.write_bytes(&bytes[start..end])?;
+    Ok(LispVal::Void)
+}
+
+fn open_input_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let bytes = bytevector_arg("open-input-bytevector", &args, 0)?;
+    Ok(LispVal::Port(Port::input_bytes(bytes)))
+}
+
+fn open_output_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Port(Port::output_bytes()))
+}
+
+fn get_output_bytevector(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) => match port.output_bytevector() {
+            Some(bytes) => Ok(bytevector_val(bytes)),
+            None => Err(contract_violation(
+                "get-output-bytevector",
+                "bytevector-port?",
+                &args[0],
+                0,
+            )),
+        },
+        arg => Err(contract_violation(
+            "get-output-bytevector",
+            "bytevector-port?",
+            arg,
+            0,
+        )),
+    }
+}
+
+fn open_input_string(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::String(s) => Ok(LispVal::Port(Port::input_string(s))),
+        arg => Err(contract_violation("open-input-string", "string?", arg, 0)),
+    }
+}
+
+fn open_output_string(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Port(Port::output_string()))
+}
+
+fn get_output_string(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) => match port.output() {
+            Some(s) => Ok(LispVal::String(s)),
+            None => Err(contract_violation(
+                "get-output-string",
+                "string-port?",
+                &args[0],
+                0,
+            )),
+        },
+        arg => Err(contract_violation(
+            "get-output-string",
+            "string-port?",
+            arg,
+            0,
+        )),
+    }
+}
+
+// `(with-output-to-string thunk)`: everything `thunk` writes to the current
+// output port, as a string
+fn with_output_to_string(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let port = Port::output_string();
+    env.ports
+        .with_output(port.clone(), || apply(env, args[0].clone(), vec![]))?;
+    Ok(LispVal::String(port.output().unwrap_or_default()))
+}
+
+/// Calls `procedure` with `port`, then closes the port even if the procedure
+/// raised an error. A procedure that's waiting for input will be run again
+/// from the start, so the port is left open for it.
+pub fn call_and_close(env: &Env, port: Port, procedure: LispVal) -> LispResult<LispVal> {
+    let result = apply(env, procedure, vec![LispVal::Port(port.clone())]);
+    if !matches!(result, Err(LispError::InputPending(_))) {
+        port.close();
+    }
+    result
+}
+
+// `(call-with-port port proc)`: calls `proc` with `port`, closing it afterwards
+fn call_with_port(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let port = port_arg("call-with-port", &args, 0)?;
+    call_and_close(env, port, args[1].clone())
+}
+
+fn close_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    port_arg("close-port", &args, 0)?.close();
+    Ok(LispVal::Void)
+}
+
+fn close_input_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) if port.is_input() => port.close(),
+        arg => {
+            return Err(contract_violation(
+                "close-input-port",
+                "input-port?",
+                arg,
+                0,
+            ))
+        }
+    }
+    Ok(LispVal::Void)
+}
+
+fn close_output_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) if port.is_output() => port.close(),
+        arg => {
+            return Err(contract_violation(
+                "close-output-port",
+                "output-port?",
+                arg,
+                0,
+            ))
+        }
+    }
+    Ok(LispVal::Void)
+}
+
+fn is_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::Port(_))))
+}
+
+fn is_input_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(
+        matches!(&args[0], LispVal::Port(port) if port.is_input()),
+    ))
+}
+
+fn is_output_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(
+        matches!(&args[0], LispVal::Port(port) if port.is_output()),
+    ))
+}
+
+fn is_textual_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(
+        matches!(&args[0], LispVal::Port(port) if !port.is_binary()),
+    ))
+}
+
+fn is_binary_port(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(
+        matches!(&args[0], LispVal::Port(port) if port.is_binary()),
+    ))
+}
+
+fn is_input_port_open(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) if port.is_input() => Ok(LispVal::Bool(port.is_open())),
+        arg => Err(contract_violation(
+            "input-port-open?",
+            "input-port?",
+            arg,
+            0,
+        )),
+    }
+}
+
+fn is_output_port_open(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Port(port) if port.is_output() => Ok(LispVal::Bool(port.is_open())),
+        arg => Err(contract_violation(
+            "output-port-open?",
+            "output-port?",
+            arg,
+            0,
+        )),
+    }
+}
+
+fn current_input_port(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Port(env.ports.current_input()))
+}
+
+fn current_output_port(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Port(env.ports.current_output()))
+}
+
+fn current_error_port(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Port(env.ports.current_error()))
+}
+
+// Output is never buffered, so there's nothing to flush
+fn flush_output_port(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    match args.first() {
+        Some(LispVal::Port(port)) if port.is_binary() => {
+            binary_output_port_arg("flush-output-port", &args, 0)?
+        }
+        _ => output_port_arg(env, "flush-output-port", &args, 0)?,
+    };
+    Ok(LispVal::Void)
+}
+
+fn eof_object(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Eof)
+}
+
+fn is_eof_object(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::Eof)))
+}
+
+pub fn port_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("newline", newline),
+        mk_io_fn_binding("write-string", write_string),
+        mk_io_fn_binding("write-char", write_char),
+        mk_io_fn_binding("read-char", read_char),
+        mk_io_fn_binding("peek-char", peek_char),
+        mk_io_fn_binding("read-line", read_line),
+        mk_io_fn_binding("read-string", read_string),
+        mk_io_fn_binding("char-ready?", char_ready),
+        mk_prim_fn_binding("open-input-string", open_input_string),
+        mk_prim_fn_binding("open-output-string", open_output_string),
+        mk_prim_fn_binding("get-output-string", get_output_string),
+        mk_io_fn_binding("with-output-to-string", with_output_to_string),
+        mk_io_fn_binding("call-with-port", call_with_port),
+        mk_prim_fn_binding("close-port", close_port),
+        mk_prim_fn_binding("close-input-port", close_input_port),
+        mk_prim_fn_binding("close-output-port", close_output_port),
+        mk_prim_fn_binding("port?", is_port),
+        mk_prim_fn_binding("input-port?", is_input_port),
+        mk_prim_fn_binding("output-port?", is_output_port),
+        mk_prim_fn_binding("textual-port?", is_textual_port),
+        mk_prim_fn_binding("binary-port?", is_binary_port),
+        mk_prim_fn_binding("read-u8", read_u8),
+        mk_prim_fn_binding("peek-u8", peek_u8),
+        mk_prim_fn_binding("u8-ready?", u8_ready),
+        mk_prim_fn_binding("read-bytevector", read_bytevector),
+        mk_prim_fn_binding("write-u8", write_u8),
+        mk_prim_fn_binding("write-bytevector", write_bytevector),
+        mk_prim_fn_binding("open-input-bytevector", open_input_bytevector),
+        mk_prim_fn_binding("open-output-bytevector", open_output_bytevector),
+        mk_prim_fn_binding("get-output-bytevector", get_output_bytevector),
+        mk_prim_fn_binding("input-port-open?", is_input_port_open),
+        mk_prim_fn_binding("output-port-open?", is_output_port_open),
+        mk_io_fn_binding("current-input-port", current_input_port),
+        mk_io_fn_binding("current-output-port", current_output_port),
+        mk_io_fn_binding("current-error-port", current_error_port),
+        mk_io_fn_binding("flush-output-port", flush_output_port),
+        mk_prim_fn_binding("eof-object", eof_object),
+        mk_prim_fn_binding("eof-object?", is_eof_object),
+    ])
+}
+
+pub fn read_primitives() -> Bindings {
+    HashMap::from([mk_io_fn_binding("read", read)])
+}
+
+/// Output helpers, which aren't part of any R7RS library
+pub fn pretty_primitives() -> Bindings {
+    HashMap::from([mk_io_fn_binding("pretty-print", pretty_print)])
+}
+
+pub fn write_primitives() -> Bindings {
+    HashMap::from([
+        mk_io_fn_binding("write", write),
+        mk_io_fn_binding("write-shared", write_shared),
+        mk_io_fn_binding("write-simple", write_simple),
+        mk_io_fn_binding("display", display),
+    ])
+}
@@ -8,7 +8,7 @@ use itertools::Itertools;
 use crate::environment::{Bindings, Env, Ports};
 use crate::error::{Arity, LispError, LispResult};
 use crate::lisp_val::{Func, LispVal};
-use crate::primitive_functions::boolean::{and, not};
+use crate::primitive_functions::boolean::not;
 use crate::primitive_functions::util::check_arity;
 use crate::primitive_functions::util::mk_prim_fn_binding;
 
@@ -146,7 +146,9 @@ mod tests {
 fn atom(args: Vec<LispVal>) -> LispResult<LispVal> {
     let not_pair = not(vec![is_pair(args.clone())?])?;
     let not_empty = not(vec![is_empty(args)?])?;
-    and(vec![not_pair, not_empty])
+    Ok(LispVal::Bool(
+        not_pair == LispVal::Bool(true) && not_empty == LispVal::Bool(true),
+    ))
 }
 
 fn length(args: Vec<LispVal>) -> LispResult<LispVal> {
@@ -305,7 +307,7 @@ fn make_accessor(accessors: &[char]) -> Vec<LispVal> {
 }
 
 pub fn accessors() -> Bindings {
-    fn noop(_port: &mut Vec<LispVal>) {}
+    fn noop(_output: &mut String) {}
 
     let mut caaaaars = vec![];
     caaaaars.append(&mut replicate_m(2, vec!['a', 'd']));
@@ -1,12 +1,21 @@
 mod boolean;
+mod bytevector;
 mod character;
+mod environment;
+mod file;
+mod fixnum;
+mod flonum;
+mod higher_order;
+mod io;
 mod list;
 mod numeric;
 mod primitive_functions;
 mod procedure;
+mod random;
+mod record;
 mod string;
 mod symbol;
 mod util;
 mod vector;
 
-pub use primitive_functions::primitive_functions;
+pub use primitive_functions::{primitive_functions, standard_library};
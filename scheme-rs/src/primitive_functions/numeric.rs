@@ -1,23 +1,38 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::rc::Rc;
 
-use num::rational::Ratio;
-use num::Zero;
-use radix_fmt::radix;
+use num::complex::{Complex, Complex64};
+use num::{BigInt, BigRational, FromPrimitive, Integer, One, Signed, ToPrimitive, Zero};
 
 use crate::environment::Bindings;
 use crate::error::{Arity, LispError, LispResult};
 use crate::lisp_val::LispVal;
-use crate::numbers::cast;
+use crate::numbers::{
+    cast, float_to_exact, from_big_rational, from_bigint, from_exact_complex, is_exact, is_num,
+    make_polar, make_rectangular, to_big_rational, to_bigint, to_complex64, to_exact_complex,
+    to_f64,
+};
+use crate::parser::string_to_number;
 use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::contract_violation;
 use crate::primitive_functions::util::mk_prim_fn_binding;
+use crate::printer::number_to_string;
 
 fn num_add(args: Vec<LispVal>) -> LispResult<LispVal> {
     args.iter().fold(Ok(LispVal::Integer(0)), |res, y| {
         if let Ok(m) = res {
             match cast(&m, y)? {
-                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Integer(m + n)),
+                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(match m.checked_add(n) {
+                    Some(sum) => LispVal::Integer(sum),
+                    None => from_bigint(BigInt::from(m) + n),
+                }),
+                (LispVal::BigInteger(m), LispVal::BigInteger(n)) => Ok(from_bigint(&*m + &*n)),
                 (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Float(m + n)),
-                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Rational(m + n)),
+                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(from_big_rational(&*m + &*n)),
+                (LispVal::ExactComplex(m), LispVal::ExactComplex(n)) => {
+                    Ok(from_exact_complex(&*m + &*n))
+                }
                 (LispVal::Complex(m), LispVal::Complex(n)) => Ok(LispVal::Complex(m + n)),
                 _ => Err(LispError::GenericError("Unexpected error in +".to_string())),
             }
@@ -29,13 +44,24 @@ fn num_add(args: Vec<LispVal>) -> LispResult<LispVal> {
 
 fn num_sub(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::Min(1))?;
+    // `(- n)` is the negation of `n`
+    if args.len() == 1 {
+        return num_sub(vec![LispVal::Integer(0), args[0].clone()]);
+    }
     let first = args.first().unwrap();
     args.iter().skip(1).fold(Ok(first.clone()), |res, y| {
         if let Ok(m) = res {
             match cast(&m, y)? {
-                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Integer(m - n)),
+                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(match m.checked_sub(n) {
+                    Some(difference) => LispVal::Integer(difference),
+                    None => from_bigint(BigInt::from(m) - n),
+                }),
+                (LispVal::BigInteger(m), LispVal::BigInteger(n)) => Ok(from_bigint(&*m - &*n)),
                 (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Float(m - n)),
-                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Rational(m - n)),
+                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(from_big_rational(&*m - &*n)),
+                (LispVal::ExactComplex(m), LispVal::ExactComplex(n)) => {
+                    Ok(from_exact_complex(&*m - &*n))
+                }
                 (LispVal::Complex(m), LispVal::Complex(n)) => Ok(LispVal::Complex(m - n)),
                 _ => Err(LispError::GenericError("Unexpected error in -".to_string())),
             }
@@ -48,10 +74,24 @@ fn num_sub(args: Vec<LispVal>) -> LispResult<LispVal> {
 fn num_mul(args: Vec<LispVal>) -> LispResult<LispVal> {
     args.iter().fold(Ok(LispVal::Integer(1)), |res, y| {
         if let Ok(m) = res {
+            // An exact zero times anything is exactly zero, even an inexact number
+            match (&m, y) {
+                (LispVal::Integer(0), n) | (n, LispVal::Integer(0)) if is_num(n) => {
+                    return Ok(LispVal::Integer(0))
+                }
+                _ => {}
+            }
             match cast(&m, y)? {
-                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Integer(m * n)),
+                (LispVal::Integer(m), LispVal::Integer(n)) => Ok(match m.checked_mul(n) {
+                    Some(product) => LispVal::Integer(product),
+                    None => from_bigint(BigInt::from(m) * n),
+                }),
+                (LispVal::BigInteger(m), LispVal::BigInteger(n)) => Ok(from_bigint(&*m * &*n)),
                 (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Float(m * n)),
-                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Rational(m * n)),
+                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(from_big_rational(&*m * &*n)),
+                (LispVal::ExactComplex(m), LispVal::ExactComplex(n)) => {
+                    Ok(from_exact_complex(&*m * &*n))
+                }
                 (LispVal::Complex(m), LispVal::Complex(n)) => Ok(LispVal::Complex(m * n)),
                 _ => Err(LispError::GenericError("Unexpected error in +".to_string())),
             }
@@ -63,38 +103,36 @@ fn num_mul(args: Vec<LispVal>) -> LispResult<LispVal> {
 
 fn num_div(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::Min(1))?;
+    // `(/ n)` is the reciprocal of `n`
+    if args.len() == 1 {
+        return num_div(vec![LispVal::Integer(1), args[0].clone()]);
+    }
     let first = args.first().unwrap();
     args.iter().skip(1).fold(Ok(first.clone()), |res, y| {
         if let Ok(m) = res {
+            // Only an exact zero is an error to divide by; an inexact one
+            // gives an infinity or NaN. An exact zero divided by anything is
+            // exactly zero.
+            match (&m, y) {
+                (_, LispVal::Integer(0)) => {
+                    return Err(LispError::GenericError("Divide by zero".to_owned()))
+                }
+                (LispVal::Integer(0), y) if is_num(y) => return Ok(LispVal::Integer(0)),
+                _ => {}
+            }
             match cast(&m, y)? {
                 (LispVal::Integer(m), LispVal::Integer(n)) => {
-                    if n == 0 {
-                        Err(LispError::GenericError("Divide by zero".to_owned()))
-                    } else {
-                        Ok(LispVal::Rational(Ratio::new(m, n)))
-                    }
+                    Ok(from_big_rational(BigRational::new(m.into(), n.into())))
                 }
-                (LispVal::Float(m), LispVal::Float(n)) => {
-                    if n == 0.0 {
-                        Err(LispError::GenericError("Divide by zero".to_owned()))
-                    } else {
-                        Ok(LispVal::Float(m / n))
-                    }
-                }
-                (LispVal::Rational(m), LispVal::Rational(n)) => {
-                    if n.is_zero() {
-                        Err(LispError::GenericError("Divide by zero".to_owned()))
-                    } else {
-                        Ok(LispVal::Rational(m / n))
-                    }
-                }
-                (LispVal::Complex(m), LispVal::Complex(n)) => {
-                    if n.is_zero() {
-                        Err(LispError::GenericError("Divide by zero".to_owned()))
-                    } else {
-                        Ok(LispVal::Complex(m / n))
-                    }
+                (LispVal::BigInteger(m), LispVal::BigInteger(n)) => Ok(from_big_rational(
+                    BigRational::new((*m).clone(), (*n).clone()),
+                )),
+                (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Float(m / n)),
+                (LispVal::Rational(m), LispVal::Rational(n)) => Ok(from_big_rational(&*m / &*n)),
+                (LispVal::ExactComplex(m), LispVal::ExactComplex(n)) => {
+                    Ok(from_exact_complex(&*m / &*n))
                 }
+                (LispVal::Complex(m), LispVal::Complex(n)) => Ok(LispVal::Complex(m / n)),
                 _ => Err(LispError::GenericError("Unexpected error in -".to_string())),
             }
         } else {
@@ -103,14 +141,37 @@ fn num_div(args: Vec<LispVal>) -> LispResult<LispVal> {
     })
 }
 
+// The exact value of a number, where floats are converted exactly, or None
+// for one with an infinite or NaN part
+fn exact_value(n: &LispVal) -> Option<Complex<BigRational>> {
+    match n {
+        LispVal::Float(f) => Some(Complex::new(BigRational::from_float(*f)?, Zero::zero())),
+        LispVal::Complex(c) => Some(Complex::new(
+            BigRational::from_float(c.re)?,
+            BigRational::from_float(c.im)?,
+        )),
+        n => to_exact_complex(n),
+    }
+}
+
 fn num_eq(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
     if let [m, n] = &args[..] {
+        // An exact number is compared with an inexact one exactly, since
+        // converting it to a float could round it, e.g. 2^53 + 1 to 2^53
+        if is_num(m) && is_num(n) && is_exact(m) != is_exact(n) {
+            return Ok(LispVal::Bool(match (exact_value(m), exact_value(n)) {
+                (Some(m), Some(n)) => m == n,
+                _ => false,
+            }));
+        }
         match cast(m, n)? {
             (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Bool(m == n)),
+            (LispVal::BigInteger(m), LispVal::BigInteger(n)) => Ok(LispVal::Bool(m == n)),
             (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Bool(m == n)),
             (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Bool(m == n)),
             (LispVal::Complex(m), LispVal::Complex(n)) => Ok(LispVal::Bool(m == n)),
+            (LispVal::ExactComplex(m), LispVal::ExactComplex(n)) => Ok(LispVal::Bool(m == n)),
             _ => Err(LispError::GenericError("Unexpected error in =".to_string())),
         }
     } else {
@@ -126,193 +187,731 @@ fn num_neq(args: Vec<LispVal>) -> LispResult<LispVal> {
     }
 }
 
-fn num_gt(args: Vec<LispVal>) -> LispResult<LispVal> {
+// `<`, `>`, `<=` or `>=`, which are false whenever either argument is NaN
+fn num_order(name: &str, args: Vec<LispVal>, holds: fn(Ordering) -> bool) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
-    if let [m, n] = &args[..] {
-        match cast(m, n)? {
-            (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Bool(m > n)),
-            (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Bool(m > n)),
-            (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Bool(m > n)),
-            (LispVal::Complex(_), LispVal::Complex(_)) => Err(LispError::GenericError(
-                "> not defined for complex numbers".to_string(),
-            )),
-            _ => Err(LispError::GenericError("Unexpected error in =".to_string())),
+    if args
+        .iter()
+        .any(|n| matches!(n, LispVal::Complex(_) | LispVal::ExactComplex(_)))
+    {
+        return Err(LispError::GenericError(format!(
+            "{} not defined for complex numbers",
+            name
+        )));
+    }
+    Ok(LispVal::Bool(
+        compare(&args[0], &args[1])?.is_some_and(holds),
+    ))
+}
+
+// Converts an exact result to a float if any of the arguments were inexact
+fn inexact_if(n: LispVal, inexact: bool) -> LispVal {
+    match (inexact, to_f64(&n)) {
+        (true, Some(f)) => LispVal::Float(f),
+        _ => n,
+    }
+}
+
+fn real_arg<'a>(name: &str, args: &'a [LispVal], index: usize) -> LispResult<&'a LispVal> {
+    match &args[index] {
+        n @ (LispVal::Integer(_)
+        | LispVal::BigInteger(_)
+        | LispVal::Rational(_)
+        | LispVal::Float(_)) => Ok(n),
+        n => Err(contract_violation(name, "real?", n, index)),
+    }
+}
+
+// An argument to an integer division, which may be an inexact integer like
+// 2.0, along with whether it was exact
+fn integer_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<(BigInt, bool)> {
+    match &args[index] {
+        n @ (LispVal::Integer(_) | LispVal::BigInteger(_)) => Ok((to_bigint(n).unwrap(), true)),
+        LispVal::Float(f) if f.fract() == 0.0 => Ok((BigInt::from_f64(*f).unwrap(), false)),
+        n => Err(contract_violation(name, "integer?", n, index)),
+    }
+}
+
+// The exact value of a rational argument, where floats count as rationals
+fn rational_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<BigRational> {
+    match &args[index] {
+        LispVal::Float(f) => BigRational::from_float(*f),
+        n => to_big_rational(n),
+    }
+    .ok_or_else(|| contract_violation(name, "rational?", &args[index], index))
+}
+
+#[derive(Clone, Copy)]
+enum Rounding {
+    Floor,
+    Truncate,
+}
+
+// The quotient and remainder of dividing two integers, with the quotient
+// rounded towards negative infinity or towards zero
+fn divide(name: &str, args: &[LispVal], rounding: Rounding) -> LispResult<(LispVal, LispVal)> {
+    check_arity(args, Arity::MinMax(2, 2))?;
+    // Fixnums are divided without allocating, except in the one case that overflows
+    if let [LispVal::Integer(m), LispVal::Integer(n)] = args {
+        if *n != 0 && !(*m == i64::MIN && *n == -1) {
+            let (q, r) = match rounding {
+                Rounding::Floor => m.div_mod_floor(n),
+                Rounding::Truncate => m.div_rem(n),
+            };
+            return Ok((LispVal::Integer(q), LispVal::Integer(r)));
         }
-    } else {
-        unreachable!()
     }
+    let (m, m_exact) = integer_arg(name, args, 0)?;
+    let (n, n_exact) = integer_arg(name, args, 1)?;
+    if n.is_zero() {
+        return Err(LispError::GenericError(format!(
+            "{}: undefined for {}",
+            name, args[1]
+        )));
+    }
+    let (q, r) = match rounding {
+        Rounding::Floor => m.div_mod_floor(&n),
+        Rounding::Truncate => m.div_rem(&n),
+    };
+    let inexact = !(m_exact && n_exact);
+    Ok((
+        inexact_if(from_bigint(q), inexact),
+        inexact_if(from_bigint(r), inexact),
+    ))
 }
 
-fn num_lt(args: Vec<LispVal>) -> LispResult<LispVal> {
-    check_arity(&args, Arity::MinMax(2, 2))?;
-    if let [m, n] = &args[..] {
-        match cast(m, n)? {
-            (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Bool(m < n)),
-            (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Bool(m < n)),
-            (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Bool(m < n)),
-            (LispVal::Complex(_), LispVal::Complex(_)) => Err(LispError::GenericError(
-                "< not defined for complex numbers".to_string(),
-            )),
-            _ => Err(LispError::GenericError("Unexpected error in =".to_string())),
+// `floor/` and `truncate/` return both the quotient and remainder. There are
+// no multiple values yet, so they're returned as a list.
+fn divide_both(name: &str, args: Vec<LispVal>, rounding: Rounding) -> LispResult<LispVal> {
+    let (q, r) = divide(name, &args, rounding)?;
+    Ok(LispVal::List(Rc::new(vec![q, r])))
+}
+
+fn gcd_or_lcm(name: &str, args: Vec<LispVal>, lcm: bool) -> LispResult<LispVal> {
+    let mut result = BigInt::from(if lcm { 1 } else { 0 });
+    let mut inexact = false;
+    for index in 0..args.len() {
+        let (n, exact) = integer_arg(name, &args, index)?;
+        inexact |= !exact;
+        result = if lcm { result.lcm(&n) } else { result.gcd(&n) };
+    }
+    Ok(inexact_if(from_bigint(result), inexact))
+}
+
+fn abs(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match real_arg("abs", &args, 0)? {
+        LispVal::Integer(n) => Ok(match n.checked_abs() {
+            Some(n) => LispVal::Integer(n),
+            None => from_bigint(BigInt::from(*n).abs()),
+        }),
+        LispVal::BigInteger(n) => Ok(from_bigint(n.abs())),
+        LispVal::Rational(r) => Ok(from_big_rational(r.abs())),
+        LispVal::Float(f) => Ok(LispVal::Float(f.abs())),
+        _ => unreachable!(),
+    }
+}
+
+// Compares a float with an exact real exactly, rather than rounding the
+// exact number to a float
+fn compare_float(f: f64, n: &LispVal) -> Option<Ordering> {
+    let n = to_big_rational(n)?;
+    match BigRational::from_float(f) {
+        Some(f) => Some(f.cmp(&n)),
+        None if f.is_nan() => None,
+        None if f > 0.0 => Some(Ordering::Greater),
+        None => Some(Ordering::Less),
+    }
+}
+
+fn compare(m: &LispVal, n: &LispVal) -> LispResult<Option<Ordering>> {
+    match (m, n) {
+        (LispVal::Float(f), n) if is_exact(n) => return Ok(compare_float(*f, n)),
+        (m, LispVal::Float(f)) if is_exact(m) => {
+            return Ok(compare_float(*f, m).map(Ordering::reverse))
         }
-    } else {
-        unreachable!()
+        _ => {}
     }
+    Ok(match cast(m, n)? {
+        (LispVal::Integer(m), LispVal::Integer(n)) => m.partial_cmp(&n),
+        (LispVal::BigInteger(m), LispVal::BigInteger(n)) => m.partial_cmp(&n),
+        (LispVal::Rational(m), LispVal::Rational(n)) => m.partial_cmp(&n),
+        (LispVal::Float(m), LispVal::Float(n)) => m.partial_cmp(&n),
+        _ => None,
+    })
 }
 
-fn num_gte(args: Vec<LispVal>) -> LispResult<LispVal> {
-    check_arity(&args, Arity::MinMax(2, 2))?;
-    if let [m, n] = &args[..] {
-        match cast(m, n)? {
-            (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Bool(m >= n)),
-            (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Bool(m >= n)),
-            (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Bool(m >= n)),
-            (LispVal::Complex(_), LispVal::Complex(_)) => Err(LispError::GenericError(
-                ">= not defined for complex numbers".to_string(),
-            )),
-            _ => Err(LispError::GenericError("Unexpected error in =".to_string())),
+// `min` or `max`. The result is inexact if any of the arguments are, e.g.
+// `(max 1 2.0)` is 2.0.
+fn extremum(name: &str, args: Vec<LispVal>, keep: Ordering) -> LispResult<LispVal> {
+    check_arity(&args, Arity::Min(1))?;
+    let mut result = real_arg(name, &args, 0)?.clone();
+    let mut inexact = !is_exact(&result);
+    for index in 1..args.len() {
+        let n = real_arg(name, &args, index)?;
+        inexact |= !is_exact(n);
+        let nan = matches!(n, LispVal::Float(f) if f.is_nan());
+        if nan || compare(n, &result)? == Some(keep) {
+            result = n.clone();
         }
+    }
+    Ok(inexact_if(result, inexact))
+}
+
+// Rounds to the nearest integer, or to the even one when halfway between two
+fn round_half_even(r: &BigRational) -> BigRational {
+    let floor = r.floor();
+    let half = BigRational::new(1.into(), 2.into());
+    match (r - &floor).cmp(&half) {
+        Ordering::Less => floor,
+        Ordering::Equal if floor.to_integer().is_even() => floor,
+        _ => floor + BigRational::one(),
+    }
+}
+
+// `floor`, `ceiling`, `round` or `truncate`, which leave integers as they are
+fn round_real(
+    name: &str,
+    args: Vec<LispVal>,
+    float: fn(f64) -> f64,
+    rational: fn(&BigRational) -> BigRational,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match real_arg(name, &args, 0)? {
+        LispVal::Rational(r) => Ok(from_big_rational(rational(r))),
+        LispVal::Float(f) => Ok(LispVal::Float(float(*f))),
+        n => Ok(n.clone()),
+    }
+}
+
+// `numerator` or `denominator`, which are inexact for floats, e.g.
+// `(denominator 0.5)` is 2.0
+fn rational_part(name: &str, args: Vec<LispVal>, denominator: bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let r = rational_arg(name, &args, 0)?;
+    let part = if denominator { r.denom() } else { r.numer() };
+    Ok(inexact_if(from_bigint(part.clone()), !is_exact(&args[0])))
+}
+
+// The simplest rational in the range [lo, hi], i.e. the one with the
+// smallest denominator, and of those the one with the smallest numerator
+fn simplest_rational(lo: BigRational, hi: BigRational) -> BigRational {
+    if lo.is_positive() {
+        simplest_positive_rational(lo, hi)
+    } else if hi.is_negative() {
+        -simplest_positive_rational(-hi, -lo)
     } else {
-        unreachable!()
+        BigRational::zero()
     }
 }
 
-fn num_lte(args: Vec<LispVal>) -> LispResult<LispVal> {
+// `simplest_rational` for 0 < lo <= hi. Where lo and hi share an integer
+// part, the fractional parts are handled by the same search on their
+// reciprocals, building up a continued fraction.
+fn simplest_positive_rational(mut lo: BigRational, mut hi: BigRational) -> BigRational {
+    let mut terms = vec![];
+    let last = loop {
+        let floor = lo.floor();
+        if floor == lo {
+            break floor;
+        }
+        if floor != hi.floor() {
+            break floor + BigRational::one();
+        }
+        let (next_lo, next_hi) = ((&hi - &floor).recip(), (&lo - &floor).recip());
+        terms.push(floor);
+        lo = next_lo;
+        hi = next_hi;
+    };
+    terms
+        .into_iter()
+        .rev()
+        .fold(last, |fraction, term| term + fraction.recip())
+}
+
+// `(rationalize x y)`: the simplest rational within `y` of `x`
+fn rationalize(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
-    if let [m, n] = &args[..] {
-        match cast(m, n)? {
-            (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Bool(m <= n)),
-            (LispVal::Float(m), LispVal::Float(n)) => Ok(LispVal::Bool(m <= n)),
-            (LispVal::Rational(m), LispVal::Rational(n)) => Ok(LispVal::Bool(m <= n)),
-            (LispVal::Complex(_), LispVal::Complex(_)) => Err(LispError::GenericError(
-                "<= not defined for complex numbers".to_string(),
-            )),
-            _ => Err(LispError::GenericError("Unexpected error in =".to_string())),
+    let x = rational_arg("rationalize", &args, 0)?;
+    let y = rational_arg("rationalize", &args, 1)?.abs();
+    let result = simplest_rational(&x - &y, &x + &y);
+    let inexact = !is_exact(&args[0]) || !is_exact(&args[1]);
+    Ok(inexact_if(from_big_rational(result), inexact))
+}
+
+// A function of one number which is real for real arguments in `domain` and
+// complex otherwise, e.g. `log` of a negative number
+fn transcendental(
+    name: &str,
+    args: Vec<LispVal>,
+    domain: fn(f64) -> bool,
+    real: fn(f64) -> f64,
+    complex: fn(Complex64) -> Complex64,
+) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        z @ (LispVal::Complex(_) | LispVal::ExactComplex(_)) => {
+            Ok(LispVal::Complex(complex(to_complex64(z).unwrap())))
         }
-    } else {
-        unreachable!()
+        n => match to_f64(n) {
+            Some(x) if domain(x) => Ok(LispVal::Float(real(x))),
+            Some(x) => Ok(LispVal::Complex(complex(Complex64::new(x, 0.0)))),
+            None => Err(contract_violation(name, "number?", n, 0)),
+        },
     }
 }
 
-fn num_to_int(n: &LispVal) -> LispResult<i64> {
-    match n {
-        LispVal::Integer(n) => Ok(*n),
-        LispVal::Float(n) => {
-            if n.fract() == 0.0 {
-                Ok(*n as i64)
+fn everywhere(_: f64) -> bool {
+    true
+}
+
+// NaN is in every domain, so e.g. `(log +nan.0)` is a real NaN
+fn non_negative(x: f64) -> bool {
+    x >= 0.0 || x.is_nan()
+}
+
+fn unit_interval(x: f64) -> bool {
+    x.abs() <= 1.0 || x.is_nan()
+}
+
+// The natural logarithm of a positive integer, which may be too big to
+// convert to a float first. Its top 64 bits are converted, and the bits
+// shifted off are added back as a multiple of ln 2.
+fn big_ln(n: &BigInt) -> f64 {
+    let shift = n.bits().saturating_sub(64);
+    (n >> shift).to_f64().unwrap().ln() + shift as f64 * std::f64::consts::LN_2
+}
+
+// `(log z)` is the natural logarithm, and `(log z b)` the logarithm to base b
+fn log(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let ln = |n: &LispVal| match n {
+        LispVal::BigInteger(_) | LispVal::Rational(_) => {
+            let r = to_big_rational(n).unwrap();
+            let ln = big_ln(&r.numer().abs()) - big_ln(r.denom());
+            Ok(if r.is_negative() {
+                LispVal::Complex(Complex64::new(ln, std::f64::consts::PI))
             } else {
-                Err(LispError::GenericError(
-                    "Could not convert float to integer".to_string(),
-                ))
-            }
+                LispVal::Float(ln)
+            })
         }
-        LispVal::Rational(n) => {
-            if *n.denom() == 1 {
-                Ok(*n.numer())
+        _ => transcendental("log", vec![n.clone()], non_negative, f64::ln, Complex64::ln),
+    };
+    match &args[..] {
+        [z] => ln(z),
+        [z, b] => num_div(vec![ln(z)?, ln(b)?]),
+        _ => unreachable!(),
+    }
+}
+
+// `(atan z)` is the arctangent, and `(atan y x)` the angle of the point (x, y)
+fn atan(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    if args.len() == 1 {
+        return transcendental("atan", args, everywhere, f64::atan, Complex64::atan);
+    }
+    let y = to_f64(real_arg("atan", &args, 0)?).unwrap();
+    let x = to_f64(real_arg("atan", &args, 1)?).unwrap();
+    Ok(LispVal::Float(y.atan2(x)))
+}
+
+// The square root of an integer, if it's a perfect square
+fn exact_root(n: &BigInt) -> Option<BigInt> {
+    let root = n.sqrt();
+    if &root * &root == *n {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+// The float nearest the square root of a non-negative rational, which may be
+// too big or small to convert to a float first. It's scaled up by 4^k until
+// its integer part has enough bits for a float's precision, and the integer
+// square root of that is scaled back down by 2^k.
+fn big_sqrt(r: &BigRational) -> f64 {
+    let (p, q) = (r.numer(), r.denom());
+    let k = (256 + q.bits()).saturating_sub(p.bits()) / 2 + 1;
+    let root = ((p << (2 * k)) / q).sqrt();
+    root.to_f64().unwrap_or(f64::INFINITY) / 2f64.powi(i32::try_from(k).unwrap_or(i32::MAX))
+}
+
+// The square roots of exact squares are exact, e.g. `(sqrt 9/4)` is 3/2 and
+// `(sqrt -4)` is +2i
+fn sqrt(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    if let Some(r) = to_big_rational(&args[0]) {
+        if let (Some(numer), Some(denom)) = (exact_root(&r.numer().abs()), exact_root(r.denom())) {
+            let root = BigRational::new(numer, denom);
+            return Ok(if r.is_negative() {
+                from_exact_complex(Complex::new(BigRational::zero(), root))
             } else {
-                Err(LispError::GenericError(
-                    "Could not convert rational to integer".to_string(),
-                ))
-            }
+                from_big_rational(root)
+            });
         }
-        LispVal::Complex(n) => {
-            if n.im == 0.0 && n.re.fract() == 0.0 {
-                Ok(n.re as i64)
+    }
+    match &args[0] {
+        LispVal::BigInteger(_) | LispVal::Rational(_) => {
+            let r = to_big_rational(&args[0]).unwrap();
+            let root = big_sqrt(&r.abs());
+            Ok(if r.is_negative() {
+                LispVal::Complex(Complex64::new(0.0, root))
             } else {
-                Err(LispError::GenericError(
-                    "Could not convert complex to integer".to_string(),
-                ))
-            }
+                LispVal::Float(root)
+            })
+        }
+        _ => transcendental("sqrt", args, non_negative, f64::sqrt, Complex64::sqrt),
+    }
+}
+
+// `(exact-integer-sqrt k)` is the largest s with s² <= k, and k - s². There
+// are no multiple values yet, so they're returned as a list, as `floor/` does.
+fn exact_integer_sqrt(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match to_bigint(&args[0]) {
+        Some(k) if !k.is_negative() => {
+            let s = k.sqrt();
+            let rest = &k - &s * &s;
+            Ok(LispVal::List(Rc::new(vec![
+                from_bigint(s),
+                from_bigint(rest),
+            ])))
         }
-        _ => Err(LispError::GenericError(
-            "Could not convert non-number to integer".to_string(),
+        _ => Err(contract_violation(
+            "exact-integer-sqrt",
+            "exact-nonnegative-integer?",
+            &args[0],
+            0,
         )),
     }
 }
 
-fn num_mod(args: Vec<LispVal>) -> LispResult<LispVal> {
-    // TODO: Other number types
+fn square(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    if !is_num(&args[0]) {
+        return Err(contract_violation("square", "number?", &args[0], 0));
+    }
+    num_mul(vec![args[0].clone(), args[0].clone()])
+}
+
+// An exact base to an exact integer power, which is exact unless the power is
+// too big to compute
+fn exact_expt(base: Complex<BigRational>, power: BigInt) -> LispResult<Option<LispVal>> {
+    if base.is_zero() && power.is_negative() {
+        return Err(LispError::GenericError("Divide by zero".to_owned()));
+    }
+    // Roughly the bits in the largest part of the result: half the bits of
+    // the base's squared magnitude for each power. Only 0, 1, -1, i and -i
+    // come out as 0, and their powers stay small.
+    let norm = base.norm_sqr();
+    let bits = norm.numer().bits().max(norm.denom().bits()) / 2;
+    check_bits("expt", &(BigInt::from(bits) * power.abs()))?;
+    if let Some(power) = power.to_i32() {
+        return Ok(Some(from_exact_complex(base.powi(power))));
+    }
+    // Only 0, 1 and -1 have huge powers small enough to represent
+    if base.is_zero() || base.is_one() {
+        Ok(Some(from_exact_complex(base)))
+    } else if base == -Complex::one() {
+        Ok(Some(LispVal::Integer(if power.is_even() { 1 } else { -1 })))
+    } else {
+        Ok(None)
+    }
+}
+
+fn expt(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
-    match &args[..] {
-        [m, n] => {
-            let m = num_to_int(m)?;
-            let n = num_to_int(n)?;
-            Ok(LispVal::Integer(m % n))
+    for (index, n) in args.iter().enumerate() {
+        if !is_num(n) {
+            return Err(contract_violation("expt", "number?", n, index));
         }
-        _ =>
-        // TODO: Typeerror?
-        {
-            Err(LispError::GenericError(
-                "Unexpected error in modulo".to_string(),
-            ))
+    }
+    let (base, power) = (&args[0], &args[1]);
+    if let (Some(b), Some(p)) = (to_exact_complex(base), to_bigint(power)) {
+        if let Some(n) = exact_expt(b, p)? {
+            return Ok(n);
+        }
+    }
+    // Anything to the exact power 0 is exactly 1
+    if let LispVal::Integer(0) = power {
+        return Ok(LispVal::Integer(1));
+    }
+    match (to_f64(base), to_f64(power)) {
+        // A negative number to a fractional power is complex
+        (Some(b), Some(p)) if b >= 0.0 || p.fract() == 0.0 || p.is_nan() => {
+            Ok(LispVal::Float(b.powf(p)))
+        }
+        _ => {
+            let (b, p) = (to_complex64(base).unwrap(), to_complex64(power).unwrap());
+            Ok(LispVal::Complex(b.powc(p)))
         }
     }
 }
 
-fn num_to_string(args: Vec<LispVal>) -> LispResult<LispVal> {
-    check_arity(&args, Arity::MinMax(1, 2))?;
-    match &args[..] {
-        [n@LispVal::Integer(_)] => Ok(LispVal::String(format!("{}", n))),
-        [LispVal::Integer(n), LispVal::Integer(base)] => {
-            match base {
-                2 | 8 | 10 | 16 => {
-                    let base = u8::try_from(*base).map_err(|_| LispError::GenericError("Unexpected error in number->string".to_string()))?;
-                    Ok(
-                        LispVal::String(format!("{}", radix(*n, base)))
-                    )
-                }
-                _ => Err(LispError::GenericError(
-                    format!("number->string: contract violation\nexpected: (or/c 2 8 10 16)\ngiven: {}\nargument position: 2nd", base),
-                ))
-            }
+// `(make-rectangular x y)` is x + yi, which is exact if x and y are
+fn rectangular(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let x = real_arg("make-rectangular", &args, 0)?;
+    let y = real_arg("make-rectangular", &args, 1)?;
+    Ok(make_rectangular(x, y).unwrap())
+}
+
+// `(make-polar m a)` is the complex number with magnitude m and angle a
+fn polar(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let m = real_arg("make-polar", &args, 0)?;
+    let a = real_arg("make-polar", &args, 1)?;
+    Ok(make_polar(m, a).unwrap())
+}
+
+// `real-part` or `imag-part`. Real numbers have an exact imaginary part of 0.
+fn complex_part(name: &str, args: Vec<LispVal>, imaginary: bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::ExactComplex(c) if imaginary => Ok(from_big_rational(c.im.clone())),
+        LispVal::ExactComplex(c) => Ok(from_big_rational(c.re.clone())),
+        LispVal::Complex(c) => Ok(LispVal::Float(if imaginary { c.im } else { c.re })),
+        n if is_num(n) && imaginary => Ok(LispVal::Integer(0)),
+        n if is_num(n) => Ok(n.clone()),
+        n => Err(contract_violation(name, "number?", n, 0)),
+    }
+}
+
+// The magnitude of an exact complex number is exact where it can be, e.g.
+// `(magnitude 3+4i)` is 5
+fn magnitude(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::ExactComplex(c) => sqrt(vec![from_big_rational(c.norm_sqr())]),
+        LispVal::Complex(c) => Ok(LispVal::Float(c.norm())),
+        n if is_num(n) => abs(args),
+        n => Err(contract_violation("magnitude", "number?", n, 0)),
+    }
+}
+
+// The angle of a positive exact number is exactly 0, and of exact 0 undefined
+fn angle(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Integer(0) => Err(LispError::GenericError("angle: undefined for 0".to_owned())),
+        z @ (LispVal::Complex(_) | LispVal::ExactComplex(_)) => {
+            Ok(LispVal::Float(to_complex64(z).unwrap().arg()))
         }
-        [n@LispVal::Float(_)] => Ok(LispVal::String(format!("{}", n))),
-        [LispVal::Float(_), LispVal::Integer(_)] => {
-            Err(LispError::GenericError(
-                "number->string: inexact numbers can only be printed in base 10".to_string(),
-            ))
+        LispVal::Float(x) => Ok(LispVal::Float(0.0f64.atan2(*x))),
+        n => match to_big_rational(n) {
+            Some(r) if r.is_negative() => Ok(LispVal::Float(std::f64::consts::PI)),
+            Some(_) => Ok(LispVal::Integer(0)),
+            None => Err(contract_violation("angle", "number?", n, 0)),
         },
-        [n@LispVal::Rational(_)] => Ok(LispVal::String(format!("{}", n))),
-        [LispVal::Rational(r), LispVal::Integer(base)] => {
-            match base {
-                2 | 8 | 10 | 16 => {
-                    let base = u8::try_from(*base).map_err(|_| LispError::GenericError("Unexpected error in number->string".to_string()))?;
-                    if *r.denom() == 0 {
-                        Ok(
-                            LispVal::String(format!("{}", radix(*r.numer(), base)))
-                        )
-                    } else {
-                        Ok(
-                            LispVal::String(format!("{}/{}", radix(*r.numer(), base), radix(*r.denom(), base)))
-                        )
-                    }
-                }
-                _ => Err(LispError::GenericError(
-                    format!("number->string: contract violation\nexpected: (or/c 2 8 10 16)\ngiven: {}\nargument position: 2nd", base),
-                ))
-            }
+    }
+}
+
+fn radix_arg(name: &str, base: &LispVal) -> LispResult<u32> {
+    match base {
+        LispVal::Integer(base @ (2 | 8 | 10 | 16)) => Ok(*base as u32),
+        _ => Err(contract_violation(name, "(or/c 2 8 10 16)", base, 1)),
+    }
+}
+
+fn num_to_string(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let radix = match args.get(1) {
+        Some(base) => radix_arg("number->string", base)?,
+        None => 10,
+    };
+    match &args[0] {
+        n if is_num(n) => Ok(LispVal::String(number_to_string(n, radix))),
+        n => Err(contract_violation("number->string", "number?", n, 0)),
+    }
+}
+
+// `(string->number string [radix])` reads a number as the reader would, or
+// gives #f if `string` isn't one
+fn string_to_num(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let radix = match args.get(1) {
+        Some(base) => radix_arg("string->number", base)?,
+        None => 10,
+    };
+    match &args[0] {
+        LispVal::String(s) => Ok(string_to_number(s, radix).unwrap_or(LispVal::Bool(false))),
+        s => Err(contract_violation("string->number", "string?", s, 0)),
+    }
+}
+
+fn exact_integer_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<BigInt> {
+    to_bigint(&args[index])
+        .ok_or_else(|| contract_violation(name, "exact-integer?", &args[index], index))
+}
+
+// The most bits an integer built by the bit operations or `expt` may have, so
+// that e.g. a huge shift is an error rather than exhausting memory
+const MAX_BITS: u64 = 1 << 26;
+
+fn check_bits(name: &str, bits: &BigInt) -> LispResult<usize> {
+    match bits.to_u64() {
+        Some(bits) if bits <= MAX_BITS => Ok(bits as usize),
+        _ => Err(LispError::GenericError(format!(
+            "{}: result is too large\nbits: {}",
+            name, bits
+        ))),
+    }
+}
+
+fn bit_index_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<BigInt> {
+    match to_bigint(&args[index]) {
+        Some(n) if !n.is_negative() => Ok(n),
+        _ => Err(contract_violation(
+            name,
+            "exact-nonnegative-integer?",
+            &args[index],
+            index,
+        )),
+    }
+}
+
+// Folds a bitwise operation over its arguments. BigInt's operators already
+// treat negative numbers as infinite two's-complement bit strings.
+fn bitwise(
+    name: &str,
+    args: Vec<LispVal>,
+    identity: i64,
+    fixnum: fn(i64, i64) -> i64,
+    bignum: fn(BigInt, BigInt) -> BigInt,
+) -> LispResult<LispVal> {
+    args.iter()
+        .enumerate()
+        .try_fold(LispVal::Integer(identity), |m, (index, n)| match (&m, n) {
+            (LispVal::Integer(m), LispVal::Integer(n)) => Ok(LispVal::Integer(fixnum(*m, *n))),
+            _ => Ok(from_bigint(bignum(
+                to_bigint(&m).unwrap(),
+                exact_integer_arg(name, &args, index)?,
+            ))),
+        })
+}
+
+fn bitwise_not(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        LispVal::Integer(n) => Ok(LispVal::Integer(!n)),
+        _ => Ok(from_bigint(!exact_integer_arg("bitwise-not", &args, 0)?)),
+    }
+}
+
+// The bits of a negative number that differ from its sign, i.e. its zero bits
+fn magnitude_bits(n: &BigInt) -> BigInt {
+    if n.is_negative() {
+        !n
+    } else {
+        n.clone()
+    }
+}
+
+// The number of bits of `n` before it's all sign bits, i.e. `integer-length`.
+// Every bit from here up is 1 for a negative number and 0 otherwise.
+fn integer_width(n: &BigInt) -> u64 {
+    magnitude_bits(n).bits()
+}
+
+// `(arithmetic-shift n count)` shifts left for a positive count and right,
+// rounding towards negative infinity, for a negative one
+fn arithmetic_shift(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let n = exact_integer_arg("arithmetic-shift", &args, 0)?;
+    let count = exact_integer_arg("arithmetic-shift", &args, 1)?;
+    if count.is_negative() {
+        // Shifting past the width leaves only the sign
+        let shift = (-count).min(BigInt::from(integer_width(&n)));
+        Ok(from_bigint(n >> shift.to_usize().unwrap()))
+    } else if n.is_zero() {
+        Ok(LispVal::Integer(0))
+    } else {
+        check_bits("arithmetic-shift", &(&count + n.bits()))?;
+        Ok(from_bigint(n << count.to_usize().unwrap()))
+    }
+}
+
+fn bit_count(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let n = magnitude_bits(&exact_integer_arg("bit-count", &args, 0)?);
+    Ok(from_bigint(BigInt::from(
+        n.iter_u64_digits().map(u64::count_ones).sum::<u32>(),
+    )))
+}
+
+fn integer_length(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let n = exact_integer_arg("integer-length", &args, 0)?;
+    Ok(from_bigint(BigInt::from(integer_width(&n))))
+}
+
+fn is_bit_set(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let index = bit_index_arg("bit-set?", &args, 0)?;
+    let n = exact_integer_arg("bit-set?", &args, 1)?;
+    match index.to_u64() {
+        Some(index) if index < integer_width(&n) => {
+            Ok(LispVal::Bool(!((n >> index) & BigInt::one()).is_zero()))
         }
-        [n@LispVal::Complex(_)] => Ok(LispVal::String(format!("{}", n))),
-        [LispVal::Complex(_), LispVal::Integer(_)] => {
-            // TODO: Technically we should be able to format exact complex #s
-            Err(LispError::GenericError(
-                "number->string: inexact numbers can only be printed in base 10".to_string(),
-            ))
-        },
-        _ =>
-        // TODO: Typeerror?
-            {
-                Err(LispError::GenericError(
-                    "Unexpected error in number->string".to_string(),
-                ))
-            }
+        _ => Ok(LispVal::Bool(n.is_negative())),
+    }
+}
+
+// `(copy-bit index n bit)` is `n` with the bit at `index` set if `bit` is #t
+// and cleared if it is #f
+fn copy_bit(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(3, 3))?;
+    let index = bit_index_arg("copy-bit", &args, 0)?;
+    let n = exact_integer_arg("copy-bit", &args, 1)?;
+    let bit = match &args[2] {
+        LispVal::Bool(bit) => *bit,
+        bit => return Err(contract_violation("copy-bit", "boolean?", bit, 2)),
+    };
+    // Past the width, every bit is already the sign bit
+    if bit == n.is_negative() && index >= BigInt::from(integer_width(&n)) {
+        return Ok(from_bigint(n));
+    }
+    let mask = BigInt::one() << check_bits("copy-bit", &index)?;
+    if bit {
+        Ok(from_bigint(n | mask))
+    } else {
+        Ok(from_bigint(n & !mask))
+    }
+}
+
+// `(bit-field n start end)` is bits `start` (inclusive) to `end` (exclusive)
+// of `n`, shifted down to bit 0
+fn bit_field(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(3, 3))?;
+    let n = exact_integer_arg("bit-field", &args, 0)?;
+    let start = bit_index_arg("bit-field", &args, 1)?;
+    let end = bit_index_arg("bit-field", &args, 2)?;
+    if end < start {
+        return Err(LispError::GenericError(format!(
+            "bit-field: ending index is smaller than starting index\nstarting index: {}\nending index: {}",
+            start, end
+        )));
     }
+    // Bits past the width are copies of the sign, so a non-negative number's
+    // field stops there, and a negative number's is filled with ones
+    let width = BigInt::from(integer_width(&n));
+    let field = if n.is_negative() {
+        &end - &start
+    } else {
+        end.min(width.clone()) - start.clone().min(width.clone())
+    };
+    let mask = (BigInt::one() << check_bits("bit-field", &field)?) - 1;
+    let shift = start.min(width).to_usize().unwrap();
+    Ok(from_bigint((n >> shift) & mask))
 }
 
+// These test the value rather than the representation, so e.g. 2.0 is an
+// integer and every finite float is rational
 fn is_integer(args: &[LispVal]) -> LispResult<LispVal> {
     check_arity(args, Arity::MinMax(1, 1))?;
     match args {
-        [LispVal::Integer(_)] => Ok(LispVal::Bool(true)),
+        [LispVal::Integer(_) | LispVal::BigInteger(_)] => Ok(LispVal::Bool(true)),
+        [LispVal::Float(f)] => Ok(LispVal::Bool(f.is_finite() && f.fract() == 0.0)),
         [_] => Ok(LispVal::Bool(false)),
         _ => unreachable!(),
     }
@@ -322,6 +921,7 @@ fn is_rational(args: &[LispVal]) -> LispResult<LispVal> {
     check_arity(args, Arity::MinMax(1, 1))?;
     match args {
         [LispVal::Rational(_)] => Ok(LispVal::Bool(true)),
+        [LispVal::Float(f)] => Ok(LispVal::Bool(f.is_finite())),
         args => is_integer(args),
     }
 }
@@ -337,7 +937,7 @@ fn is_real(args: &[LispVal]) -> LispResult<LispVal> {
 fn is_complex(args: &[LispVal]) -> LispResult<LispVal> {
     check_arity(args, Arity::MinMax(1, 1))?;
     match args {
-        [LispVal::Complex(_)] => Ok(LispVal::Bool(true)),
+        [LispVal::Complex(_) | LispVal::ExactComplex(_)] => Ok(LispVal::Bool(true)),
         args => is_real(args),
     }
 }
@@ -352,6 +952,9 @@ fn is_zero(args: Vec<LispVal>) -> LispResult<LispVal> {
         match cast(n, &LispVal::Integer(0))? {
             (LispVal::Integer(0), _) => Ok(LispVal::Bool(true)),
             (LispVal::Float(n), _) => Ok(LispVal::Bool(n == 0.0)),
+            (LispVal::Complex(c), _) => Ok(LispVal::Bool(c.re == 0.0 && c.im == 0.0)),
+            // Exact complex numbers always have a non-zero imaginary part
+            (LispVal::ExactComplex(c), _) => Ok(LispVal::Bool(c.is_zero())),
             _ => Ok(LispVal::Bool(false)),
         }
     } else {
@@ -359,6 +962,84 @@ fn is_zero(args: Vec<LispVal>) -> LispResult<LispVal> {
     }
 }
 
+fn is_exact_number(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        n if is_num(n) => Ok(LispVal::Bool(is_exact(n))),
+        n => Err(contract_violation("exact?", "number?", n, 0)),
+    }
+}
+
+fn is_inexact_number(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        n if is_num(n) => Ok(LispVal::Bool(!is_exact(n))),
+        n => Err(contract_violation("inexact?", "number?", n, 0)),
+    }
+}
+
+fn is_exact_integer(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(
+        args[0],
+        LispVal::Integer(_) | LispVal::BigInteger(_)
+    )))
+}
+
+// The exact number nearest to `n`. Floats are converted exactly, e.g. 0.1 is
+// 3602879701896397/36028797018963968.
+fn to_exact(name: &str, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let no_exact = |n: &LispVal| {
+        LispError::GenericError(format!("{}: no exact representation\nnumber: {}", name, n))
+    };
+    match &args[0] {
+        n if is_exact(n) => Ok(n.clone()),
+        n @ LispVal::Float(f) => float_to_exact(*f).ok_or_else(|| no_exact(n)),
+        n @ LispVal::Complex(c) => {
+            match (BigRational::from_float(c.re), BigRational::from_float(c.im)) {
+                (Some(re), Some(im)) => Ok(from_exact_complex(Complex::new(re, im))),
+                _ => Err(no_exact(n)),
+            }
+        }
+        n => Err(contract_violation(name, "number?", n, 0)),
+    }
+}
+
+fn to_inexact(name: &str, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        n @ (LispVal::Float(_) | LispVal::Complex(_)) => Ok(n.clone()),
+        n @ LispVal::ExactComplex(_) => Ok(LispVal::Complex(to_complex64(n).unwrap())),
+        n => match to_f64(n) {
+            Some(f) => Ok(LispVal::Float(f)),
+            None => Err(contract_violation(name, "number?", n, 0)),
+        },
+    }
+}
+
+// Whether `test` holds for a float, or either part of a complex number.
+// It never holds for exact numbers.
+fn float_test(name: &str, args: Vec<LispVal>, test: fn(f64) -> bool) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        n if is_exact(n) => Ok(LispVal::Bool(false)),
+        LispVal::Float(f) => Ok(LispVal::Bool(test(*f))),
+        LispVal::Complex(c) => Ok(LispVal::Bool(test(c.re) || test(c.im))),
+        n => Err(contract_violation(name, "number?", n, 0)),
+    }
+}
+
+fn is_finite(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[0] {
+        n if is_exact(n) => Ok(LispVal::Bool(true)),
+        LispVal::Float(f) => Ok(LispVal::Bool(f.is_finite())),
+        LispVal::Complex(c) => Ok(LispVal::Bool(c.is_finite())),
+        n => Err(contract_violation("finite?", "number?", n, 0)),
+    }
+}
+
 pub fn numeric_primitives() -> Bindings {
     HashMap::from([
         mk_prim_fn_binding("+", num_add),
@@ -367,22 +1048,170 @@ pub fn numeric_primitives() -> Bindings {
         mk_prim_fn_binding("/", num_div),
         mk_prim_fn_binding("=", num_eq),
         mk_prim_fn_binding("/=", num_neq),
-        mk_prim_fn_binding(">", num_gt),
-        mk_prim_fn_binding("<", num_lt),
-        mk_prim_fn_binding(">=", num_gte),
-        mk_prim_fn_binding("<=", num_lte),
-        mk_prim_fn_binding("modulo", num_mod),
+        mk_prim_fn_binding(">", |args| num_order(">", args, Ordering::is_gt)),
+        mk_prim_fn_binding("<", |args| num_order("<", args, Ordering::is_lt)),
+        mk_prim_fn_binding(">=", |args| num_order(">=", args, Ordering::is_ge)),
+        mk_prim_fn_binding("<=", |args| num_order("<=", args, Ordering::is_le)),
+        mk_prim_fn_binding("quotient", |args| {
+            Ok(divide("quotient", &args, Rounding::Truncate)?.0)
+        }),
+        mk_prim_fn_binding("remainder", |args| {
+            Ok(divide("remainder", &args, Rounding::Truncate)?.1)
+        }),
+        mk_prim_fn_binding("modulo", |args| {
+            Ok(divide("modulo", &args, Rounding::Floor)?.1)
+        }),
+        mk_prim_fn_binding("floor/", |args| {
+            divide_both("floor/", args, Rounding::Floor)
+        }),
+        mk_prim_fn_binding("floor-quotient", |args| {
+            Ok(divide("floor-quotient", &args, Rounding::Floor)?.0)
+        }),
+        mk_prim_fn_binding("floor-remainder", |args| {
+            Ok(divide("floor-remainder", &args, Rounding::Floor)?.1)
+        }),
+        mk_prim_fn_binding("truncate/", |args| {
+            divide_both("truncate/", args, Rounding::Truncate)
+        }),
+        mk_prim_fn_binding("truncate-quotient", |args| {
+            Ok(divide("truncate-quotient", &args, Rounding::Truncate)?.0)
+        }),
+        mk_prim_fn_binding("truncate-remainder", |args| {
+            Ok(divide("truncate-remainder", &args, Rounding::Truncate)?.1)
+        }),
+        mk_prim_fn_binding("gcd", |args| gcd_or_lcm("gcd", args, false)),
+        mk_prim_fn_binding("lcm", |args| gcd_or_lcm("lcm", args, true)),
+        mk_prim_fn_binding("abs", abs),
+        mk_prim_fn_binding("min", |args| extremum("min", args, Ordering::Less)),
+        mk_prim_fn_binding("max", |args| extremum("max", args, Ordering::Greater)),
+        mk_prim_fn_binding("floor", |args| {
+            round_real("floor", args, f64::floor, BigRational::floor)
+        }),
+        mk_prim_fn_binding("ceiling", |args| {
+            round_real("ceiling", args, f64::ceil, BigRational::ceil)
+        }),
+        mk_prim_fn_binding("round", |args| {
+            round_real("round", args, f64::round_ties_even, round_half_even)
+        }),
+        mk_prim_fn_binding("truncate", |args| {
+            round_real("truncate", args, f64::trunc, BigRational::trunc)
+        }),
+        mk_prim_fn_binding("numerator", |args| rational_part("numerator", args, false)),
+        mk_prim_fn_binding("denominator", |args| {
+            rational_part("denominator", args, true)
+        }),
+        mk_prim_fn_binding("rationalize", rationalize),
         mk_prim_fn_binding("zero?", is_zero),
         mk_prim_fn_binding("number->string", num_to_string),
+        mk_prim_fn_binding("string->number", string_to_num),
         mk_prim_fn_binding("integer?", |args| is_integer(&args)),
         mk_prim_fn_binding("rational?", |args| is_rational(&args)),
         mk_prim_fn_binding("real?", |args| is_real(&args)),
         mk_prim_fn_binding("complex?", |args| is_complex(&args)),
         mk_prim_fn_binding("number?", |args| is_number(&args)),
-        // TODO!
-        // mk_prim_fn_binding("quotient", num_quotient),
-        // mk_prim_fn_binding("remainder", num_rem),
-        // mk_prim_fn_binding("sin", num_sine),
-        // mk_prim_fn_binding("cos", num_cos),
+        mk_prim_fn_binding("exact?", is_exact_number),
+        mk_prim_fn_binding("inexact?", is_inexact_number),
+        mk_prim_fn_binding("exact-integer?", is_exact_integer),
+        mk_prim_fn_binding("exact", |args| to_exact("exact", args)),
+        mk_prim_fn_binding("inexact->exact", |args| to_exact("inexact->exact", args)),
+        mk_prim_fn_binding("inexact", |args| to_inexact("inexact", args)),
+        mk_prim_fn_binding("exact->inexact", |args| to_inexact("exact->inexact", args)),
+        mk_prim_fn_binding("nan?", |args| float_test("nan?", args, f64::is_nan)),
+        mk_prim_fn_binding("infinite?", |args| {
+            float_test("infinite?", args, f64::is_infinite)
+        }),
+        mk_prim_fn_binding("finite?", is_finite),
+        mk_prim_fn_binding("exp", |args| {
+            transcendental("exp", args, everywhere, f64::exp, Complex64::exp)
+        }),
+        mk_prim_fn_binding("log", log),
+        mk_prim_fn_binding("sin", |args| {
+            transcendental("sin", args, everywhere, f64::sin, Complex64::sin)
+        }),
+        mk_prim_fn_binding("cos", |args| {
+            transcendental("cos", args, everywhere, f64::cos, Complex64::cos)
+        }),
+        mk_prim_fn_binding("tan", |args| {
+            transcendental("tan", args, everywhere, f64::tan, Complex64::tan)
+        }),
+        mk_prim_fn_binding("asin", |args| {
+            transcendental("asin", args, unit_interval, f64::asin, Complex64::asin)
+        }),
+        mk_prim_fn_binding("acos", |args| {
+            transcendental("acos", args, unit_interval, f64::acos, Complex64::acos)
+        }),
+        mk_prim_fn_binding("atan", atan),
+        mk_prim_fn_binding("sqrt", sqrt),
+        mk_prim_fn_binding("exact-integer-sqrt", exact_integer_sqrt),
+        mk_prim_fn_binding("square", square),
+        mk_prim_fn_binding("expt", expt),
+        mk_prim_fn_binding("make-rectangular", rectangular),
+        mk_prim_fn_binding("make-polar", polar),
+        mk_prim_fn_binding("real-part", |args| complex_part("real-part", args, false)),
+        mk_prim_fn_binding("imag-part", |args| complex_part("imag-part", args, true)),
+        mk_prim_fn_binding("magnitude", magnitude),
+        mk_prim_fn_binding("angle", angle),
+    ])
+}
+
+/// The procedures of `(scheme inexact)`, which are also in `(scheme base)`
+pub fn inexact_primitives() -> Bindings {
+    let mut bindings = numeric_primitives();
+    bindings.retain(|name, _| {
+        [
+            "exp",
+            "log",
+            "sin",
+            "cos",
+            "tan",
+            "asin",
+            "acos",
+            "atan",
+            "sqrt",
+            "finite?",
+            "infinite?",
+            "nan?",
+        ]
+        .contains(&name.as_str())
+    });
+    bindings
+}
+
+/// The procedures of `(scheme complex)`, which are also in `(scheme base)`
+pub fn complex_primitives() -> Bindings {
+    let mut bindings = numeric_primitives();
+    bindings.retain(|name, _| {
+        [
+            "make-rectangular",
+            "make-polar",
+            "real-part",
+            "imag-part",
+            "magnitude",
+            "angle",
+        ]
+        .contains(&name.as_str())
+    });
+    bindings
+}
+
+/// The bitwise operations on exact integers from SRFI 151
+pub fn bitwise_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("bitwise-and", |args| {
+            bitwise("bitwise-and", args, -1, |m, n| m & n, |m, n| m & n)
+        }),
+        mk_prim_fn_binding("bitwise-ior", |args| {
+            bitwise("bitwise-ior", args, 0, |m, n| m | n, |m, n| m | n)
+        }),
+        mk_prim_fn_binding("bitwise-xor", |args| {
+            bitwise("bitwise-xor", args, 0, |m, n| m ^ n, |m, n| m ^ n)
+        }),
+        mk_prim_fn_binding("bitwise-not", bitwise_not),
+        mk_prim_fn_binding("arithmetic-shift", arithmetic_shift),
+        mk_prim_fn_binding("bit-count", bit_count),
+        mk_prim_fn_binding("integer-length", integer_length),
+        mk_prim_fn_binding("bit-set?", is_bit_set),
+        mk_prim_fn_binding("copy-bit", copy_bit),
+        mk_prim_fn_binding("bit-field", bit_field),
     ])
 }
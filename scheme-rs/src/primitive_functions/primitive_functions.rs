@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::environment::Bindings;
@@ -6,10 +7,23 @@ use crate::error::{Arity, LispResult};
 use crate::lisp_val::LispVal;
 
 use super::boolean::boolean_primitives;
+use super::bytevector::bytevector_primitives;
 use super::character::character_primitives;
-use super::list::list_primitives;
-use super::numeric::numeric_primitives;
+use super::environment::{
+    environment_primitives, eval_primitives, load_primitives, repl_primitives,
+};
+use super::file::{directory_primitives, file_primitives};
+use super::fixnum::fixnum_primitives;
+use super::flonum::flonum_primitives;
+use super::higher_order::higher_order_primitives;
+use super::io::{port_primitives, pretty_primitives, read_primitives, write_primitives};
+use super::list::{accessors, list_primitives};
+use super::numeric::{
+    bitwise_primitives, complex_primitives, inexact_primitives, numeric_primitives,
+};
 use super::procedure::procedure_primitives;
+use super::random::random_primitives;
+use super::record::record_primitives;
 use super::string::string_primitives;
 use super::symbol::symbol_primitives;
 use super::util::{check_arity, mk_prim_fn_binding};
@@ -19,43 +33,80 @@ fn void(_args: Vec<LispVal>) -> LispResult<LispVal> {
     Ok(LispVal::Void)
 }
 
-fn equal(args: Vec<LispVal>) -> LispResult<LispVal> {
+pub fn equal(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
-    match &args[..] {
-        [LispVal::DottedList(xs, x), LispVal::DottedList(ys, y)] => {
-            if let LispVal::Bool(false) = equal(vec![(**x).clone(), (**y).clone()])? {
-                return Ok(LispVal::Bool(false));
-            }
-            if xs.len() != ys.len() {
-                return Ok(LispVal::Bool(false));
-            }
-            for (x, y) in xs.iter().zip(ys.iter()) {
-                if let LispVal::Bool(false) = equal(vec![x.clone(), y.clone()])? {
-                    return Ok(LispVal::Bool(false));
-                }
-            }
-            Ok(LispVal::Bool(true))
+    Ok(LispVal::Bool(is_equal(
+        &args[0],
+        &args[1],
+        &mut HashSet::new(),
+    )?))
+}
+
+type VectorPtr = *const RefCell<Vec<LispVal>>;
+
+// Vectors can contain themselves, so each pair of vectors is compared only
+// once. A pair that's already being compared is taken to be equal, and any
+// difference shows up in the comparison that's still in progress.
+fn is_equal(
+    x: &LispVal,
+    y: &LispVal,
+    seen: &mut HashSet<(VectorPtr, VectorPtr)>,
+) -> LispResult<bool> {
+    match (x, y) {
+        (LispVal::DottedList(xs, x), LispVal::DottedList(ys, y)) => {
+            Ok(is_equal(x, y, seen)? && all_equal(xs, ys, seen)?)
         }
-        [LispVal::List(xs), LispVal::List(ys)] => {
-            if xs.len() != ys.len() {
-                return Ok(LispVal::Bool(false));
-            }
-            for (x, y) in xs.iter().zip(ys.iter()) {
-                if let LispVal::Bool(false) = equal(vec![x.clone(), y.clone()])? {
-                    return Ok(LispVal::Bool(false));
-                }
+        (LispVal::List(xs), LispVal::List(ys)) => all_equal(xs, ys, seen),
+        (LispVal::Vector(xs), LispVal::Vector(ys)) => {
+            if !seen.insert((Rc::as_ptr(xs), Rc::as_ptr(ys))) {
+                return Ok(true);
             }
-            Ok(LispVal::Bool(true))
+            // Clone the elements so comparing doesn't hold a borrow of
+            // either vector
+            let xs = xs.borrow().clone();
+            let ys = ys.borrow().clone();
+            all_equal(&xs, &ys, seen)
         }
-        _ => eq(args),
+        (LispVal::Bytevector(xs), LispVal::Bytevector(ys)) => Ok(*xs.borrow() == *ys.borrow()),
+        _ => Ok(matches!(
+            eq(vec![x.clone(), y.clone()])?,
+            LispVal::Bool(true)
+        )),
     }
 }
 
+fn all_equal(
+    xs: &[LispVal],
+    ys: &[LispVal],
+    seen: &mut HashSet<(VectorPtr, VectorPtr)>,
+) -> LispResult<bool> {
+    if xs.len() != ys.len() {
+        return Ok(false);
+    }
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        if !is_equal(x, y, seen)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
 fn eq(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(2, 2))?;
     match &args[..] {
         [LispVal::Bool(arg1), LispVal::Bool(arg2)] => Ok(LispVal::Bool(arg1 == arg2)),
         [LispVal::Integer(arg1), LispVal::Integer(arg2)] => Ok(LispVal::Bool(arg1 == arg2)),
+        // Numbers are always in their simplest representation, so equal
+        // numbers of the same exactness have the same variant
+        [LispVal::BigInteger(m), LispVal::BigInteger(n)] => Ok(LispVal::Bool(m == n)),
+        [LispVal::Rational(m), LispVal::Rational(n)] => Ok(LispVal::Bool(m == n)),
+        [LispVal::ExactComplex(m), LispVal::ExactComplex(n)] => Ok(LispVal::Bool(m == n)),
+        // Floats are compared bit for bit, so 0.0 and -0.0 differ but +nan.0
+        // is eqv? to itself
+        [LispVal::Float(m), LispVal::Float(n)] => Ok(LispVal::Bool(m.to_bits() == n.to_bits())),
+        [LispVal::Complex(m), LispVal::Complex(n)] => Ok(LispVal::Bool(
+            m.re.to_bits() == n.re.to_bits() && m.im.to_bits() == n.im.to_bits(),
+        )),
         [LispVal::String(arg1), LispVal::String(arg2)] => Ok(LispVal::Bool(arg1 == arg2)),
         [LispVal::Char(arg1), LispVal::Char(arg2)] => Ok(LispVal::Bool(arg1 == arg2)),
         [LispVal::Atom(arg1), LispVal::Atom(arg2)] => Ok(LispVal::Bool(arg1 == arg2)),
@@ -63,29 +114,103 @@ fn eq(args: Vec<LispVal>) -> LispResult<LispVal> {
             Ok(LispVal::Bool(Rc::ptr_eq(xs, ys) && Rc::ptr_eq(x, y)))
         }
         [LispVal::List(xs), LispVal::List(ys)] => Ok(LispVal::Bool(Rc::ptr_eq(xs, ys))),
+        [LispVal::Vector(xs), LispVal::Vector(ys)] => Ok(LispVal::Bool(Rc::ptr_eq(xs, ys))),
+        [LispVal::Bytevector(xs), LispVal::Bytevector(ys)] => Ok(LispVal::Bool(Rc::ptr_eq(xs, ys))),
         [LispVal::Func(f), LispVal::Func(g)] => Ok(LispVal::Bool(f == g)),
         [LispVal::PrimitiveFunc(f), LispVal::PrimitiveFunc(g)] => Ok(LispVal::Bool(f == g)),
+        [LispVal::IOFunc(f), LispVal::IOFunc(g)] => Ok(LispVal::Bool(f == g)),
+        [LispVal::Environment(e), LispVal::Environment(f)] => Ok(LispVal::Bool(e == f)),
+        [LispVal::Port(p), LispVal::Port(q)] => Ok(LispVal::Bool(p == q)),
+        [LispVal::RandomSource(r), LispVal::RandomSource(s)] => Ok(LispVal::Bool(r == s)),
+        [LispVal::Eof, LispVal::Eof] => Ok(LispVal::Bool(true)),
+        [LispVal::Record(r), LispVal::Record(s)] => Ok(LispVal::Bool(r == s)),
+        [LispVal::RecordType(r), LispVal::RecordType(s)] => Ok(LispVal::Bool(Rc::ptr_eq(r, s))),
+        [LispVal::RecordProc(f), LispVal::RecordProc(g)] => Ok(LispVal::Bool(f == g)),
+        [LispVal::RandomProc(f), LispVal::RandomProc(g)] => Ok(LispVal::Bool(f == g)),
         [_, _] => Ok(LispVal::Bool(false)),
 
         _ => unreachable!(),
     }
 }
 
-pub fn primitive_functions() -> Bindings {
+fn equivalence_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("void", void),
+        mk_prim_fn_binding("eq?", eq),
+        mk_prim_fn_binding("eqv?", eq),
+        mk_prim_fn_binding("equal?", equal),
+    ])
+}
+
+fn base_primitives() -> Bindings {
     let mut bindings = HashMap::new();
     bindings.extend(boolean_primitives());
     bindings.extend(character_primitives());
     bindings.extend(numeric_primitives());
     bindings.extend(list_primitives());
+    bindings.extend(higher_order_primitives());
+    bindings.extend(port_primitives());
     bindings.extend(vector_primitives());
+    bindings.extend(bytevector_primitives());
     bindings.extend(procedure_primitives());
+    bindings.extend(record_primitives());
     bindings.extend(string_primitives());
     bindings.extend(symbol_primitives());
-    bindings.extend([
-        mk_prim_fn_binding("void", void),
-        mk_prim_fn_binding("eq?", eq),
-        mk_prim_fn_binding("eqv?", eq),
-        mk_prim_fn_binding("equal?", equal),
-    ]);
+    bindings.extend(equivalence_primitives());
+    // Only the two-level accessors (caar ... cddr) are part of (scheme base)
+    bindings.retain(|name, _| !is_cxr(name) || name.len() == 4);
+    bindings
+}
+
+fn is_cxr(name: &str) -> bool {
+    name.len() > 3
+        && name.starts_with('c')
+        && name.ends_with('r')
+        && name[1..name.len() - 1]
+            .chars()
+            .all(|c| c == 'a' || c == 'd')
+}
+
+/// The built-in R7RS libraries, e.g. `(scheme base)`, available to `import`.
+pub fn standard_library(name: &[String]) -> Option<Bindings> {
+    let name = name.iter().map(String::as_str).collect::<Vec<&str>>();
+    match name[..] {
+        ["scheme", "base"] => Some(base_primitives()),
+        ["scheme", "char"] => Some(character_primitives()),
+        ["scheme", "eval"] => Some(eval_primitives()),
+        ["scheme", "repl"] => Some(repl_primitives()),
+        ["scheme", "cxr"] => {
+            let mut bindings = accessors();
+            bindings.retain(|name, _| name.len() > 4);
+            Some(bindings)
+        }
+        ["scheme", "read"] => Some(read_primitives()),
+        ["scheme", "write"] => Some(write_primitives()),
+        ["scheme", "load"] => Some(load_primitives()),
+        ["scheme", "file"] => Some(file_primitives()),
+        ["scheme", "inexact"] => Some(inexact_primitives()),
+        ["scheme", "complex"] => Some(complex_primitives()),
+        ["rnrs", "arithmetic", "fixnums"] => Some(fixnum_primitives()),
+        ["rnrs", "arithmetic", "flonums"] => Some(flonum_primitives()),
+        ["srfi", "27"] => Some(random_primitives()),
+        ["srfi", "151"] => Some(bitwise_primitives()),
+        _ => None,
+    }
+}
+
+pub fn primitive_functions() -> Bindings {
+    let mut bindings = base_primitives();
+    bindings.extend(accessors());
+    bindings.extend(bitwise_primitives());
+    bindings.extend(fixnum_primitives());
+    bindings.extend(flonum_primitives());
+    bindings.extend(environment_primitives());
+    bindings.extend(load_primitives());
+    bindings.extend(random_primitives());
+    bindings.extend(read_primitives());
+    bindings.extend(write_primitives());
+    bindings.extend(pretty_primitives());
+    bindings.extend(file_primitives());
+    bindings.extend(directory_primitives());
     bindings
 }
@@ -6,13 +6,21 @@ use crate::lisp_val::LispVal;
 use crate::primitive_functions::util::check_arity;
 use crate::primitive_functions::util::mk_prim_fn_binding;
 
+/// Whether `val` can be applied, i.e. what `procedure?` tests
+pub fn is_procedure_value(val: &LispVal) -> bool {
+    matches!(
+        val,
+        LispVal::Func(_)
+            | LispVal::PrimitiveFunc(_)
+            | LispVal::IOFunc(_)
+            | LispVal::RecordProc(_)
+            | LispVal::RandomProc(_)
+    )
+}
+
 fn is_procedure(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(1, 1))?;
-    match &args[..] {
-        [LispVal::Func(_)] => Ok(LispVal::Bool(true)),
-        [LispVal::PrimitiveFunc(_)] => Ok(LispVal::Bool(true)),
-        _ => Ok(LispVal::Bool(false)),
-    }
+    Ok(LispVal::Bool(is_procedure_value(&args[0])))
 }
 
 pub fn procedure_primitives() -> Bindings {
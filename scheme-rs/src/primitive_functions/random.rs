@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use num::{BigInt, Signed, ToPrimitive};
+
+use crate::environment::{Bindings, Env};
+use crate::error::{Arity, LispError, LispResult};
+use crate::lisp_val::{LispVal, RandomProc, RandomProcKind};
+use crate::numbers::{from_bigint, to_bigint, to_f64};
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::contract_violation;
+use crate::primitive_functions::util::{mk_io_fn_binding, mk_prim_fn_binding};
+use crate::random::RandomSource;
+
+fn random_source_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<RandomSource> {
+    match &args[index] {
+        LispVal::RandomSource(source) => Ok(source.clone()),
+        source => Err(contract_violation(name, "random-source?", source, index)),
+    }
+}
+
+// `random-integer`, `random-real` and `random` draw from whatever
+// `default-random-source` is at the top level of the calling environment, so
+// each interpreter (or imported copy of SRFI 27) has its own stream
+fn default_source(name: &str, env: &Env) -> LispResult<RandomSource> {
+    match env.global().lookup("default-random-source") {
+        Some(LispVal::RandomSource(source)) => Ok(source),
+        _ => Err(LispError::GenericError(format!(
+            "{}: default-random-source is not a random source",
+            name
+        ))),
+    }
+}
+
+fn positive_integer_arg(name: &str, args: &[LispVal], index: usize) -> LispResult<BigInt> {
+    match to_bigint(&args[index]) {
+        Some(n) if n.is_positive() => Ok(n),
+        _ => Err(contract_violation(
+            name,
+            "exact-positive-integer?",
+            &args[index],
+            index,
+        )),
+    }
+}
+
+fn make_random_source(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::RandomSource(RandomSource::new()))
+}
+
+fn is_random_source(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    Ok(LispVal::Bool(matches!(args[0], LispVal::RandomSource(_))))
+}
+
+// The state is a list of four integers, which can be written out and read
+// back in to resume the same stream later
+fn random_source_state_ref(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let source = random_source_arg("random-source-state-ref", &args, 0)?;
+    Ok(LispVal::List(
+        source
+            .state()
+            .iter()
+            .map(|word| from_bigint(BigInt::from(*word)))
+            .collect::<Vec<LispVal>>()
+            .into(),
+    ))
+}
+
+fn random_source_state_set(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(2, 2))?;
+    let source = random_source_arg("random-source-state-set!", &args, 0)?;
+    let invalid = || {
+        LispError::GenericError(format!(
+            "random-source-state-set!: invalid state\nstate: {}",
+            args[1]
+        ))
+    };
+    let words = match &args[1] {
+        LispVal::List(words) if words.len() == 4 => words
+            .iter()
+            .map(|word| to_bigint(word).and_then(|word| word.to_u64()))
+            .collect::<Option<Vec<u64>>>()
+            .ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+    if source.set_state([words[0], words[1], words[2], words[3]]) {
+        Ok(LispVal::Void)
+    } else {
+        Err(invalid())
+    }
+}
+
+// `(random-source-pseudo-randomize! source i j)` puts `source` in a state
+// determined by the non-negative integers `i` and `j`
+fn random_source_pseudo_randomize(args: Vec<LispVal>) -> LispResult<LispVal> {
+    let name = "random-source-pseudo-randomize!";
+    check_arity(&args, Arity::MinMax(3, 3))?;
+    let source = random_source_arg(name, &args, 0)?;
+    let index = |index: usize| match to_bigint(&args[index]) {
+        Some(n) if !n.is_negative() => Ok(n),
+        _ => Err(contract_violation(
+            name,
+            "exact-nonnegative-integer?",
+            &args[index],
+            index,
+        )),
+    };
+    source.pseudo_randomize(&index(1)?, &index(2)?);
+    Ok(LispVal::Void)
+}
+
+fn random_source_randomize(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    random_source_arg("random-source-randomize!", &args, 0)?.randomize();
+    Ok(LispVal::Void)
+}
+
+// `(random-source-make-integers source)` is a procedure like `random-integer`
+// that draws from `source`
+fn random_source_make_integers(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let source = random_source_arg("random-source-make-integers", &args, 0)?;
+    Ok(LispVal::RandomProc(RandomProc::new(
+        "random-integer".to_string(),
+        source,
+        RandomProcKind::Integers,
+    )))
+}
+
+// `(random-source-make-reals source [unit])` is a procedure like `random-real`
+// that draws from `source`. Reals are always as fine-grained as a float
+// allows, so `unit` only has to be between 0 and 1.
+fn random_source_make_reals(args: Vec<LispVal>) -> LispResult<LispVal> {
+    let name = "random-source-make-reals";
+    check_arity(&args, Arity::MinMax(1, 2))?;
+    let source = random_source_arg(name, &args, 0)?;
+    if let Some(unit) = args.get(1) {
+        match to_f64(unit) {
+            Some(u) if u > 0.0 && u < 1.0 => {}
+            _ => {
+                return Err(contract_violation(
+                    name,
+                    "(and/c real? (>/c 0) (</c 1))",
+                    unit,
+                    1,
+                ))
+            }
+        }
+    }
+    Ok(LispVal::RandomProc(RandomProc::new(
+        "random-real".to_string(),
+        source,
+        RandomProcKind::Reals,
+    )))
+}
+
+fn random_integer(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    let n = positive_integer_arg("random-integer", &args, 0)?;
+    Ok(from_bigint(
+        default_source("random-integer", env)?.integer(&n),
+    ))
+}
+
+fn random_real(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 0))?;
+    Ok(LispVal::Float(default_source("random-real", env)?.real()))
+}
+
+// `(random)` is a real between 0 and 1, `(random n)` an integer in [0, n) for
+// an exact `n`, or a real in (0, n) for an inexact one
+fn random(env: &Env, args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(0, 1))?;
+    let source = default_source("random", env)?;
+    match args.first() {
+        None => Ok(LispVal::Float(source.real())),
+        Some(LispVal::Float(f)) if *f > 0.0 => Ok(LispVal::Float(f * source.real())),
+        Some(_) => Ok(from_bigint(
+            source.integer(&positive_integer_arg("random", &args, 0)?),
+        )),
+    }
+}
+
+pub fn random_primitives() -> Bindings {
+    HashMap::from([
+        (
+            "default-random-source".to_string(),
+            LispVal::RandomSource(RandomSource::new()),
+        ),
+        mk_prim_fn_binding("make-random-source", make_random_source),
+        mk_prim_fn_binding("random-source?", is_random_source),
+        mk_prim_fn_binding("random-source-state-ref", random_source_state_ref),
+        mk_prim_fn_binding("random-source-state-set!", random_source_state_set),
+        mk_prim_fn_binding(
+            "random-source-pseudo-randomize!",
+            random_source_pseudo_randomize,
+        ),
+        mk_prim_fn_binding("random-source-randomize!", random_source_randomize),
+        mk_prim_fn_binding("random-source-make-integers", random_source_make_integers),
+        mk_prim_fn_binding("random-source-make-reals", random_source_make_reals),
+        mk_io_fn_binding("random-integer", random_integer),
+        mk_io_fn_binding("random-real", random_real),
+        mk_io_fn_binding("random", random),
+    ])
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::environment::Bindings;
+use crate::error::{Arity, LispError, LispResult};
+use crate::lisp_val::LispVal;
+use crate::primitive_functions::util::check_arity;
+use crate::primitive_functions::util::mk_prim_fn_binding;
+
+fn is_record(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match args[..] {
+        [LispVal::Record(_)] => Ok(LispVal::Bool(true)),
+        _ => Ok(LispVal::Bool(false)),
+    }
+}
+
+fn record_type_descriptor(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[..] {
+        [LispVal::Record(r)] => Ok(LispVal::RecordType(r.record_type_descriptor().clone())),
+        [arg] => Err(LispError::GenericError(format!(
+            "record-type-descriptor: contract violation\nexpected: record?\ngiven: {}",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+fn record_type_name(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[..] {
+        [LispVal::RecordType(t)] => Ok(LispVal::Atom(t.name().to_string())),
+        [arg] => Err(LispError::GenericError(format!(
+            "record-type-name: contract violation\nexpected: record-type?\ngiven: {}",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+fn record_type_field_names(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(1, 1))?;
+    match &args[..] {
+        [LispVal::RecordType(t)] => Ok(LispVal::List(Rc::new(
+            t.field_names()
+                .iter()
+                .map(|name| LispVal::Atom(name.to_string()))
+                .collect(),
+        ))),
+        [arg] => Err(LispError::GenericError(format!(
+            "record-type-field-names: contract violation\nexpected: record-type?\ngiven: {}",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+pub fn record_primitives() -> Bindings {
+    HashMap::from([
+        mk_prim_fn_binding("record?", is_record),
+        mk_prim_fn_binding("record-type-descriptor", record_type_descriptor),
+        mk_prim_fn_binding("record-type-name", record_type_name),
+        mk_prim_fn_binding("record-type-field-names", record_type_field_names),
+    ])
+}
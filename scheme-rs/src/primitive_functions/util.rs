@@ -1,5 +1,6 @@
+use crate::environment::Env;
 use crate::error::{Arity, LispError, LispResult};
-use crate::lisp_val::{prim_func, LispVal};
+use crate::lisp_val::{io_func, prim_func, LispVal};
 
 pub fn mk_prim_fn_binding(
     name: &str,
@@ -8,6 +9,32 @@ pub fn mk_prim_fn_binding(
     (name.to_string(), prim_func(name.to_string(), func))
 }
 
+pub fn mk_io_fn_binding(
+    name: &str,
+    func: fn(&Env, Vec<LispVal>) -> LispResult<LispVal>,
+) -> (String, LispVal) {
+    (name.to_string(), io_func(name.to_string(), func))
+}
+
+fn ordinal(index: usize) -> String {
+    match index + 1 {
+        1 => "1st".to_string(),
+        2 => "2nd".to_string(),
+        3 => "3rd".to_string(),
+        n => format!("{}th", n),
+    }
+}
+
+pub fn contract_violation(name: &str, expected: &str, given: &LispVal, index: usize) -> LispError {
+    LispError::GenericError(format!(
+        "{}: contract violation\nexpected: {}\ngiven: {}\nargument position: {}",
+        name,
+        expected,
+        given,
+        ordinal(index)
+    ))
+}
+
 pub fn check_arity(args: &[LispVal], arity: Arity) -> LispResult<()> {
     let len = i8::try_from(args.len())
         .map_err(|_| LispError::GenericError("weird argument length".to_string()))?;
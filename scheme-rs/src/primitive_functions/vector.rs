@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::iter;
 use std::rc::Rc;
@@ -9,7 +10,7 @@ use crate::primitive_functions::util::check_arity;
 use crate::primitive_functions::util::mk_prim_fn_binding;
 
 fn vector(args: Vec<LispVal>) -> LispResult<LispVal> {
-    Ok(LispVal::Vector(Rc::new(args)))
+    Ok(LispVal::Vector(Rc::new(RefCell::new(args))))
 }
 
 fn is_vector(args: Vec<LispVal>) -> LispResult<LispVal> {
@@ -24,9 +25,9 @@ fn vector_length(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(1, 1))?;
     match &args[..] {
         [LispVal::Vector(xs)] => {
-            Ok(LispVal::Integer(xs.len().try_into().map_err(|_| {
-                LispError::GenericError("weird list length".to_string())
-            })?))
+            Ok(LispVal::Integer(xs.borrow().len().try_into().map_err(
+                |_| LispError::GenericError("weird list length".to_string()),
+            )?))
         }
         [arg] => Err(LispError::GenericError(format!(
             "vector-length: contract violation\nexpected: vector?\ngiven: {}",
@@ -42,6 +43,7 @@ fn vector_ref(args: Vec<LispVal>) -> LispResult<LispVal> {
         [v @ LispVal::Vector(xs), LispVal::Integer(n)] => {
             let index = usize::try_from(*n)
                 .map_err(|_| LispError::GenericError("weird list length".to_string()))?;
+            let xs = xs.borrow();
             match xs.get(index) {
                 Some(val) => Ok(val.clone()),
                 None => Err(LispError::GenericError(format!("vector-ref: index is out of range\nindex: {}\nvalid range: [0, {}]\nvector: {}", index, xs.len(), v))),
@@ -59,22 +61,47 @@ fn vector_ref(args: Vec<LispVal>) -> LispResult<LispVal> {
     }
 }
 
+fn vector_set(args: Vec<LispVal>) -> LispResult<LispVal> {
+    check_arity(&args, Arity::MinMax(3, 3))?;
+    match &args[..] {
+        [v @ LispVal::Vector(xs), LispVal::Integer(n), val] => {
+            let len = xs.borrow().len();
+            match usize::try_from(*n) {
+                Ok(index) if index < len => {
+                    xs.borrow_mut()[index] = val.clone();
+                    Ok(LispVal::Void)
+                }
+                _ => Err(LispError::GenericError(format!("vector-set!: index is out of range\nindex: {}\nvalid range: [0, {}]\nvector: {}", n, len, v))),
+            }
+        }
+        [LispVal::Vector(_), arg, _] => Err(LispError::GenericError(format!(
+            "vector-set!: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}\nargument position: 2nd",
+            arg
+        ))),
+        [arg, _, _] => Err(LispError::GenericError(format!(
+            "vector-set!: contract violation\nexpected: vector?\ngiven: {}\nargument position: 1st",
+            arg
+        ))),
+        _ => unreachable!(),
+    }
+}
+
 fn make_vector(args: Vec<LispVal>) -> LispResult<LispVal> {
     check_arity(&args, Arity::MinMax(1, 2))?;
     match &args[..] {
         [LispVal::Integer(n)] => {
             let n = usize::try_from(*n)
                 .map_err(|_| LispError::GenericError("weird vector length".to_string()))?;
-            Ok(LispVal::Vector(Rc::new(
+            Ok(LispVal::Vector(Rc::new(RefCell::new(
                 iter::repeat(LispVal::Void).take(n).collect(),
-            )))
+            ))))
         }
         [LispVal::Integer(n), arg] => {
             let n = usize::try_from(*n)
                 .map_err(|_| LispError::GenericError("weird vector length".to_string()))?;
-            Ok(LispVal::Vector(Rc::new(
+            Ok(LispVal::Vector(Rc::new(RefCell::new(
                 iter::repeat(arg.clone()).take(n).collect(),
-            )))
+            ))))
         }
         [arg, ..] => Err(LispError::GenericError(format!(
             "make-vector: contract violation\nexpected: exact-nonnegative-integer?\ngiven: {}",
@@ -90,6 +117,7 @@ pub fn vector_primitives() -> Bindings {
         mk_prim_fn_binding("vector?", is_vector),
         mk_prim_fn_binding("vector-length", vector_length),
         mk_prim_fn_binding("vector-ref", vector_ref),
+        mk_prim_fn_binding("vector-set!", vector_set),
         mk_prim_fn_binding("make-vector", make_vector),
     ])
 }
@@ -0,0 +1,7 @@
+mod pretty;
+mod printer;
+#[cfg(test)]
+mod tests;
+
+pub use pretty::pretty_print;
+pub use printer::{number_to_string, print, Mode};
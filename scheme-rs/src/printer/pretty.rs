@@ -0,0 +1,135 @@
+use crate::lisp_val::LispVal;
+
+use super::printer::{abbreviation, cyclic, print, Mode};
+
+/// The number of arguments which stay on the first line of a form, before
+/// its body, e.g. the name and parameters of a `define`
+fn body_start(xs: &[LispVal]) -> Option<usize> {
+    let LispVal::Atom(head) = &xs[0] else {
+        return None;
+    };
+    match head.as_str() {
+        "begin" => Some(0),
+        // Named let
+        "let" if matches!(xs.get(1), Some(LispVal::Atom(_))) => Some(2),
+        "define" | "define-values" | "define-syntax" | "lambda" | "case-lambda" | "let"
+        | "let*" | "letrec" | "letrec*" | "let-values" | "let*-values" | "let-syntax"
+        | "letrec-syntax" | "syntax-rules" | "when" | "unless" | "case" | "parameterize"
+        | "guard" | "define-library" => Some(1),
+        "do" | "define-record-type" => Some(2),
+        _ => None,
+    }
+}
+
+// Heads longer than this start their arguments on the next line rather
+// than lining them up after the head
+const MAX_HANGING_HEAD: usize = 12;
+
+struct Pretty {
+    width: usize,
+    out: String,
+}
+
+impl Pretty {
+    fn column(&self) -> usize {
+        let line = match self.out.rfind('\n') {
+            Some(i) => &self.out[i + 1..],
+            None => &self.out,
+        };
+        line.chars().count()
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.out.push('\n');
+        self.out.extend(std::iter::repeat_n(' ', indent));
+    }
+
+    // Lays out each of `xs` on its own line at `indent`, the first on the
+    // current line, followed by `trailing` closing brackets
+    fn lines(&mut self, xs: &[LispVal], indent: usize, trailing: usize) {
+        for (i, x) in xs.iter().enumerate() {
+            if i > 0 {
+                self.newline(indent);
+            }
+            self.layout(x, if i + 1 == xs.len() { trailing } else { 0 });
+        }
+    }
+
+    // Lays out `val` from the current column. `trailing` is the number of
+    // closing brackets which will follow it on the same line.
+    fn layout(&mut self, val: &LispVal, trailing: usize) {
+        let column = self.column();
+        let flat = print(val, Mode::Write);
+        // Once the indentation reaches the edge there's no room left to gain
+        // by breaking lines, which also bounds how deep this recurses
+        if column + flat.chars().count() + trailing <= self.width || column >= self.width {
+            self.out.push_str(&flat);
+            return;
+        }
+        match val {
+            LispVal::List(xs) if !xs.is_empty() => {
+                if let Some(prefix) = abbreviation(xs) {
+                    self.out.push_str(prefix);
+                    self.layout(&xs[1], trailing);
+                    return;
+                }
+                self.out.push('(');
+                match (body_start(xs), &xs[0]) {
+                    (Some(n), _) => {
+                        let n = n.min(xs.len() - 1);
+                        for (i, x) in xs[..=n].iter().enumerate() {
+                            if i > 0 {
+                                self.out.push(' ');
+                            }
+                            let last = i + 1 == xs.len();
+                            self.layout(x, if last { trailing + 1 } else { 0 });
+                        }
+                        if n + 1 < xs.len() {
+                            self.newline(column + 2);
+                            self.lines(&xs[n + 1..], column + 2, trailing + 1);
+                        }
+                    }
+                    (None, LispVal::Atom(head))
+                        if xs.len() > 1 && head.chars().count() <= MAX_HANGING_HEAD =>
+                    {
+                        self.layout(&xs[0], 0);
+                        self.out.push(' ');
+                        let indent = self.column();
+                        self.lines(&xs[1..], indent, trailing + 1);
+                    }
+                    _ => self.lines(xs, column + 1, trailing + 1),
+                }
+                self.out.push(')');
+            }
+            LispVal::DottedList(xs, x) => {
+                self.out.push('(');
+                self.lines(xs, column + 1, 0);
+                self.newline(column + 1);
+                self.out.push_str(". ");
+                self.layout(x, trailing + 1);
+                self.out.push(')');
+            }
+            LispVal::Vector(xs) => {
+                self.out.push_str("#(");
+                self.lines(&xs.borrow(), column + 2, trailing + 1);
+                self.out.push(')');
+            }
+            _ => self.out.push_str(&flat),
+        }
+    }
+}
+
+/// Writes `val` as `write` would, but broken over lines and indented so that
+/// it fits in `width` columns where possible. Cyclic data is written on one
+/// line, with datum labels.
+pub fn pretty_print(val: &LispVal, width: usize) -> String {
+    if !cyclic(val).is_empty() {
+        return print(val, Mode::Write);
+    }
+    let mut pretty = Pretty {
+        width,
+        out: String::new(),
+    };
+    pretty.layout(val, 0);
+    pretty.out
+}
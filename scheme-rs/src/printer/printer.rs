@@ -0,0 +1,422 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+use num::{BigInt, BigRational, ToPrimitive, Zero};
+
+use crate::lisp_val::LispVal;
+use crate::parser::expression;
+
+/// How `print` writes values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// As `write` does: strings, characters and symbols are escaped so the
+    /// text reads back as the same datum, and cycles are shown with datum labels
+    Write,
+    /// As `write-shared` does: like `Write`, but every list, vector or record
+    /// that appears more than once is labelled, not only cyclic ones
+    WriteShared,
+    /// As `write-simple` does: like `Write` without labels, so it never
+    /// finishes on cyclic data
+    WriteSimple,
+    /// As `display` does: strings, characters and symbols are written as is
+    Display,
+}
+
+// Something left to print. Values are printed from an explicit stack rather
+// than recursively, so deeply nested data can't overflow the Rust stack.
+enum Item {
+    Val(LispVal),
+    Text(&'static str),
+}
+
+// The identity of a value that could appear in more than one place
+fn identity(val: &LispVal) -> Option<usize> {
+    match val {
+        LispVal::List(xs) if !xs.is_empty() => Some(Rc::as_ptr(xs) as usize),
+        LispVal::DottedList(xs, _) => Some(Rc::as_ptr(xs) as usize),
+        LispVal::Vector(xs) if !xs.borrow().is_empty() => Some(Rc::as_ptr(xs) as usize),
+        LispVal::Record(r) => Some(Rc::as_ptr(&r.fields) as usize),
+        _ => None,
+    }
+}
+
+// The values directly inside `val`
+fn children(val: &LispVal) -> Vec<LispVal> {
+    match val {
+        LispVal::List(xs) => xs.to_vec(),
+        LispVal::DottedList(xs, x) => {
+            let mut children = xs.to_vec();
+            children.push((**x).clone());
+            children
+        }
+        LispVal::Vector(xs) => xs.borrow().clone(),
+        LispVal::Record(r) => r.values(),
+        LispVal::Quote(x)
+        | LispVal::QuasiQuote(x)
+        | LispVal::Unquote(x)
+        | LispVal::UnquoteSplicing(x) => vec![(**x).clone()],
+        _ => vec![],
+    }
+}
+
+// The values reachable from `val` by more than one path
+fn shared(val: &LispVal) -> HashSet<usize> {
+    let mut seen = HashSet::new();
+    let mut shared = HashSet::new();
+    let mut stack = vec![val.clone()];
+    while let Some(val) = stack.pop() {
+        if let Some(id) = identity(&val) {
+            if !seen.insert(id) {
+                shared.insert(id);
+                continue;
+            }
+        }
+        stack.extend(children(&val));
+    }
+    shared
+}
+
+// The values which contain themselves
+pub(super) fn cyclic(val: &LispVal) -> HashSet<usize> {
+    enum Visit {
+        Enter(LispVal),
+        Exit(usize),
+    }
+    let mut path = HashSet::new();
+    let mut done = HashSet::new();
+    let mut cyclic = HashSet::new();
+    let mut stack = vec![Visit::Enter(val.clone())];
+    while let Some(visit) = stack.pop() {
+        let val = match visit {
+            Visit::Enter(val) => val,
+            Visit::Exit(id) => {
+                path.remove(&id);
+                done.insert(id);
+                continue;
+            }
+        };
+        if let Some(id) = identity(&val) {
+            if path.contains(&id) {
+                cyclic.insert(id);
+                continue;
+            }
+            if done.contains(&id) {
+                continue;
+            }
+            path.insert(id);
+            stack.push(Visit::Exit(id));
+        }
+        stack.extend(children(&val).into_iter().map(Visit::Enter));
+    }
+    cyclic
+}
+
+// Pushes `xs` so they're printed in order, separated by spaces
+fn push_all(stack: &mut Vec<Item>, xs: &[LispVal]) {
+    for (i, x) in xs.iter().enumerate().rev() {
+        stack.push(Item::Val(x.clone()));
+        if i > 0 {
+            stack.push(Item::Text(" "));
+        }
+    }
+}
+
+// Quote forms are abbreviated, as the reader would accept them
+pub(super) fn abbreviation(xs: &[LispVal]) -> Option<&'static str> {
+    match xs {
+        [LispVal::Atom(s), _] => match s.as_str() {
+            "quote" => Some("'"),
+            "quasiquote" => Some("`"),
+            "unquote" => Some(","),
+            "unquote-splicing" => Some(",@"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Writes `val` as text, in the given mode
+pub fn print(val: &LispVal, mode: Mode) -> String {
+    let labelled = match mode {
+        Mode::Write | Mode::Display => cyclic(val),
+        Mode::WriteShared => shared(val),
+        Mode::WriteSimple => HashSet::new(),
+    };
+    let mut labels = HashMap::new();
+    let mut out = String::new();
+    let mut stack = vec![Item::Val(val.clone())];
+    while let Some(item) = stack.pop() {
+        let val = match item {
+            Item::Val(val) => val,
+            Item::Text(text) => {
+                out.push_str(text);
+                continue;
+            }
+        };
+        if let Some(id) = identity(&val).filter(|id| labelled.contains(id)) {
+            match labels.get(&id) {
+                Some(n) => {
+                    let _ = write!(out, "#{}#", n);
+                    continue;
+                }
+                None => {
+                    let n = labels.len();
+                    labels.insert(id, n);
+                    let _ = write!(out, "#{}=", n);
+                }
+            }
+        }
+        match &val {
+            LispVal::List(xs) => match abbreviation(xs) {
+                Some(prefix) => {
+                    out.push_str(prefix);
+                    stack.push(Item::Val(xs[1].clone()));
+                }
+                None => {
+                    out.push('(');
+                    stack.push(Item::Text(")"));
+                    push_all(&mut stack, xs);
+                }
+            },
+            LispVal::DottedList(xs, x) => {
+                out.push('(');
+                stack.push(Item::Text(")"));
+                stack.push(Item::Val((**x).clone()));
+                stack.push(Item::Text(" . "));
+                push_all(&mut stack, xs);
+            }
+            LispVal::Vector(xs) => {
+                out.push_str("#(");
+                stack.push(Item::Text(")"));
+                push_all(&mut stack, &xs.borrow());
+            }
+            LispVal::Record(r) => {
+                let _ = write!(out, "#<record {}", r.record_type.display_name());
+                stack.push(Item::Text(">"));
+                for value in r.values().into_iter().rev() {
+                    stack.push(Item::Val(value));
+                    stack.push(Item::Text(" "));
+                }
+            }
+            LispVal::Quote(x) => {
+                out.push('\'');
+                stack.push(Item::Val((**x).clone()));
+            }
+            LispVal::QuasiQuote(x) => {
+                out.push('`');
+                stack.push(Item::Val((**x).clone()));
+            }
+            LispVal::Unquote(x) => {
+                out.push(',');
+                stack.push(Item::Val((**x).clone()));
+            }
+            LispVal::UnquoteSplicing(x) => {
+                out.push_str(",@");
+                stack.push(Item::Val((**x).clone()));
+            }
+            LispVal::DatumLabel(n, x) => {
+                let _ = write!(out, "#{}=", n);
+                stack.push(Item::Val((**x).clone()));
+            }
+            LispVal::DatumRef(n) => {
+                let _ = write!(out, "#{}#", n);
+            }
+            LispVal::Atom(s) if mode == Mode::Display => out.push_str(s),
+            LispVal::Atom(s) => write_symbol(&mut out, s),
+            LispVal::String(s) if mode == Mode::Display => out.push_str(s),
+            LispVal::String(s) => write_escaped(&mut out, s, '"'),
+            LispVal::Char(c) if mode == Mode::Display => out.push(*c),
+            LispVal::Char(c) => write_char(&mut out, *c),
+            val => out.push_str(&format_atomic(val)),
+        }
+    }
+    out
+}
+
+// Writes `s` between `delimiter`s, escaping the delimiter, backslashes and
+// control characters
+fn write_escaped(out: &mut String, s: &str, delimiter: char) {
+    out.push(delimiter);
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\u{7}' => out.push_str("\\a"),
+            '\u{8}' => out.push_str("\\b"),
+            c if c == delimiter => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{:x};", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(delimiter);
+}
+
+// Symbols which the reader wouldn't read back as the same symbol, e.g. `|a b|`
+// or `|1|`, are written between bars
+fn write_symbol(out: &mut String, name: &str) {
+    match expression(name) {
+        Ok(("", LispVal::Atom(s))) if s == name => out.push_str(name),
+        _ => write_escaped(out, name, '|'),
+    }
+}
+
+fn write_char(out: &mut String, c: char) {
+    let name = match c {
+        '\u{7}' => "alarm",
+        '\u{8}' => "backspace",
+        '\u{7f}' => "delete",
+        '\u{1b}' => "escape",
+        '\n' => "newline",
+        '\0' => "null",
+        '\r' => "return",
+        ' ' => "space",
+        '\t' => "tab",
+        c if c.is_control() => {
+            let _ = write!(out, "#\\x{:x}", c as u32);
+            return;
+        }
+        c => {
+            out.push_str("#\\");
+            out.push(c);
+            return;
+        }
+    };
+    out.push_str("#\\");
+    out.push_str(name);
+}
+
+fn format_rational(r: &BigRational, radix: u32) -> String {
+    if r.is_integer() {
+        r.numer().to_str_radix(radix)
+    } else {
+        format!(
+            "{}/{}",
+            r.numer().to_str_radix(radix),
+            r.denom().to_str_radix(radix)
+        )
+    }
+}
+
+fn format_float(f: f64, radix: u32) -> String {
+    if f.is_nan() {
+        return "+nan.0".to_owned();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "+inf.0" } else { "-inf.0" }.to_owned();
+    }
+    if radix == 10 {
+        // The fewest digits which read back as the same float, always with a
+        // `.` or an exponent so it can't be mistaken for an exact integer
+        return format!("{:?}", f);
+    }
+    // Floats are binary fractions, so in bases 2, 8 and 16 they're written
+    // out exactly
+    let r = BigRational::from_float(f.abs()).unwrap();
+    let mut out = r.trunc().to_integer().to_str_radix(radix);
+    out.push('.');
+    let mut fraction = r.fract();
+    if fraction.is_zero() {
+        out.push('0');
+    }
+    while !fraction.is_zero() {
+        fraction *= BigRational::from_integer(radix.into());
+        let digit = fraction.trunc().to_integer().to_u32().unwrap();
+        out.push(char::from_digit(digit, radix).unwrap());
+        fraction = fraction.fract();
+    }
+    if f.is_sign_negative() {
+        out.insert(0, '-');
+    }
+    out
+}
+
+// The imaginary part of a complex number, which always has a sign, e.g. `+2i`
+// or `-2.5i`
+fn format_imaginary(im: &str) -> String {
+    if im.starts_with(['+', '-']) {
+        format!("{}i", im)
+    } else {
+        format!("+{}i", im)
+    }
+}
+
+/// Writes a number in the given radix, which is 2, 8, 10 or 16, as
+/// `number->string` does
+pub fn number_to_string(n: &LispVal, radix: u32) -> String {
+    match n {
+        LispVal::Integer(n) => BigInt::from(*n).to_str_radix(radix),
+        LispVal::BigInteger(n) => n.to_str_radix(radix),
+        LispVal::Float(n) => format_float(*n, radix),
+        LispVal::Rational(r) => format_rational(r, radix),
+        LispVal::Complex(c) => {
+            // A real part of +0.0 is left out, as it is when reading, e.g.
+            // `-2.5i`, but -0.0 is kept so that it reads back the same
+            let im = format_imaginary(&format_float(c.im, radix));
+            if c.re == 0.0 && c.re.is_sign_positive() {
+                im
+            } else {
+                format!("{}{}", format_float(c.re, radix), im)
+            }
+        }
+        LispVal::ExactComplex(c) => {
+            // An exact imaginary part of 1 is just `i`, and an exact real
+            // part of 0 is left out, e.g. `+i` or `-1/2i`
+            let im = match format_rational(&c.im, radix).as_str() {
+                "1" => "+".to_owned(),
+                "-1" => "-".to_owned(),
+                im => im.to_owned(),
+            };
+            if c.re.is_zero() {
+                format_imaginary(&im)
+            } else {
+                format!("{}{}", format_rational(&c.re, radix), format_imaginary(&im))
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Values which are printed the same in every mode
+fn format_atomic(val: &LispVal) -> String {
+    match val {
+        LispVal::Bytevector(bytes) => format!(
+            "#u8({})",
+            bytes
+                .borrow()
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<String>>()
+                .join(" ")
+        ),
+        n @ LispVal::Integer(_) => number_to_string(n, 10),
+        n @ LispVal::BigInteger(_) => number_to_string(n, 10),
+        n @ LispVal::Float(_) => number_to_string(n, 10),
+        n @ LispVal::Complex(_) => number_to_string(n, 10),
+        n @ LispVal::Rational(_) => number_to_string(n, 10),
+        n @ LispVal::ExactComplex(_) => number_to_string(n, 10),
+        LispVal::PrimitiveFunc(f) => format!("#<procedure:{}>", f.name),
+        LispVal::IOFunc(f) => format!("#<procedure:{}>", f.name),
+        LispVal::Func(f) => format!("#<procedure:{}>", f.name),
+        LispVal::Environment(_) => "#<environment>".to_owned(),
+        LispVal::Port(p) => format!("{}", p),
+        LispVal::RandomSource(s) => format!("{}", s),
+        LispVal::RecordType(t) => format!("#<record-type {}>", t.display_name()),
+        LispVal::RecordProc(p) => format!("#<procedure:{}>", p.name),
+        LispVal::RandomProc(p) => format!("#<procedure:{}>", p.name),
+        LispVal::Nil => "Nil".to_owned(),
+        LispVal::Eof => "#<eof>".to_owned(),
+        LispVal::Bool(true) => "#t".to_owned(),
+        LispVal::Bool(false) => "#f".to_owned(),
+        LispVal::Void => "#<void>".to_owned(),
+        // Compound values are taken apart by `print`
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,193 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use nom::Parser;
+
+use super::*;
+use crate::lisp_val::LispVal;
+use crate::parser::expression;
+
+fn string(s: &str) -> LispVal {
+    LispVal::String(s.to_string())
+}
+
+fn atom(s: &str) -> LispVal {
+    LispVal::Atom(s.to_string())
+}
+
+#[test]
+fn write_escapes() {
+    assert_eq!(
+        print(&string("a\"b\\c\nd"), Mode::Write),
+        "\"a\\\"b\\\\c\\nd\""
+    );
+    assert_eq!(print(&string("\u{7}\u{1}"), Mode::Write), "\"\\a\\x1;\"");
+    assert_eq!(print(&LispVal::Char(' '), Mode::Write), "#\\space");
+    assert_eq!(print(&LispVal::Char('\u{7f}'), Mode::Write), "#\\delete");
+    assert_eq!(print(&LispVal::Char('\u{1}'), Mode::Write), "#\\x1");
+    assert_eq!(print(&LispVal::Char('('), Mode::Write), "#\\(");
+    assert_eq!(print(&atom("foo"), Mode::Write), "foo");
+    assert_eq!(print(&atom("a b"), Mode::Write), "|a b|");
+    assert_eq!(print(&atom("1"), Mode::Write), "|1|");
+    assert_eq!(print(&atom("#t"), Mode::Write), "|#t|");
+    assert_eq!(print(&atom(""), Mode::Write), "||");
+    assert_eq!(print(&atom("|a|"), Mode::Write), "|\\|a\\||");
+    assert_eq!(print(&LispVal::Void, Mode::Write), "#<void>");
+}
+
+fn float(f: f64) -> String {
+    print(&LispVal::Float(f), Mode::Write)
+}
+
+#[test]
+fn write_numbers() {
+    assert_eq!(float(2.0), "2.0");
+    assert_eq!(float(-0.0), "-0.0");
+    assert_eq!(float(0.1), "0.1");
+    assert_eq!(float(1.0 / 3.0), "0.3333333333333333");
+    assert_eq!(float(1e21), "1e21");
+    assert_eq!(float(1.5e-7), "1.5e-7");
+    assert_eq!(float(f64::INFINITY), "+inf.0");
+    assert_eq!(float(f64::NEG_INFINITY), "-inf.0");
+    assert_eq!(float(f64::NAN), "+nan.0");
+    let complex = |re, im| {
+        print(
+            &LispVal::Complex(num::complex::Complex64::new(re, im)),
+            Mode::Write,
+        )
+    };
+    assert_eq!(complex(1.0, -2.0), "1.0-2.0i");
+    assert_eq!(complex(1.0, 0.0), "1.0+0.0i");
+    // A pure imaginary number is written without its real part, unless it's -0.0
+    assert_eq!(complex(0.0, f64::INFINITY), "+inf.0i");
+    assert_eq!(complex(0.0, -2.5), "-2.5i");
+    assert_eq!(complex(0.0, std::f64::consts::PI), "+3.141592653589793i");
+    assert_eq!(complex(-0.0, 1.0), "-0.0+1.0i");
+    for (re, im) in [(0.0, -2.5), (0.0, 1.0), (-0.0, 1.0)] {
+        let text = complex(re, im);
+        match expression(&text) {
+            Ok(("", LispVal::Complex(c))) => {
+                assert_eq!((c.re.to_bits(), c.im), (re.to_bits(), im), "{}", text)
+            }
+            other => panic!("{} read back as {:?}", text, other),
+        }
+    }
+    // Every float reads back as itself
+    for f in [
+        0.1,
+        2.0,
+        1e21,
+        1.5e-7,
+        123456.789,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        -1e-300,
+    ] {
+        assert_eq!(expression(&float(f)), Ok(("", LispVal::Float(f))));
+    }
+}
+
+#[test]
+fn number_to_string_in_radix() {
+    assert_eq!(number_to_string(&LispVal::Integer(-255), 16), "-ff");
+    assert_eq!(number_to_string(&LispVal::Float(255.5), 16), "ff.8");
+    assert_eq!(number_to_string(&LispVal::Float(-0.625), 2), "-0.101");
+    assert_eq!(number_to_string(&LispVal::Float(8.0), 8), "10.0");
+    assert_eq!(number_to_string(&LispVal::Float(f64::NAN), 2), "+nan.0");
+}
+
+#[test]
+fn write_round_trips() {
+    let val = LispVal::List(Rc::new(vec![
+        string("say \"hi\"\n"),
+        atom("a b"),
+        atom("."),
+        atom("+1"),
+        LispVal::Char('\n'),
+        LispVal::Char(')'),
+        LispVal::Vector(Rc::new(RefCell::new(vec![string("\\"), atom("x|y")]))),
+    ]));
+    let text = print(&val, Mode::Write);
+    assert_eq!(expression.parse(text.as_str()), Ok(("", val)));
+}
+
+#[test]
+fn display_writes_as_is() {
+    let val = LispVal::List(Rc::new(vec![
+        string("a \"b\""),
+        LispVal::Char('c'),
+        atom("d e"),
+    ]));
+    assert_eq!(print(&val, Mode::Display), "(a \"b\" c d e)");
+}
+
+#[test]
+fn datum_labels() {
+    let shared = LispVal::List(Rc::new(vec![LispVal::Integer(1), LispVal::Integer(2)]));
+    let val = LispVal::List(Rc::new(vec![shared.clone(), shared]));
+    assert_eq!(print(&val, Mode::WriteShared), "(#0=(1 2) #0#)");
+    assert_eq!(print(&val, Mode::Write), "((1 2) (1 2))");
+
+    let cells = Rc::new(RefCell::new(vec![LispVal::Integer(1)]));
+    let cyclic = LispVal::Vector(cells.clone());
+    cells.borrow_mut().push(cyclic.clone());
+    assert_eq!(print(&cyclic, Mode::Write), "#0=#(1 #0#)");
+    assert_eq!(print(&cyclic, Mode::Display), "#0=#(1 #0#)");
+    assert_eq!(print(&cyclic, Mode::WriteShared), "#0=#(1 #0#)");
+    // Break the cycle so the vector can be dropped
+    cells.borrow_mut().pop();
+}
+
+#[test]
+fn deep_nesting() {
+    let mut val = LispVal::List(Rc::new(vec![]));
+    for _ in 0..100_000 {
+        val = LispVal::List(Rc::new(vec![val]));
+    }
+    let text = print(&val, Mode::Write);
+    assert_eq!(text.len(), 200_002);
+    // Dropping nested `Rc`s recurses, so unwrap the list one level at a time
+    while let LispVal::List(xs) = val {
+        val = match Rc::try_unwrap(xs) {
+            Ok(mut xs) => xs.pop().unwrap_or(LispVal::Nil),
+            Err(_) => break,
+        };
+    }
+}
+
+fn read(text: &str) -> LispVal {
+    crate::parser::datum.parse(text).unwrap().1
+}
+
+#[test]
+fn pretty_fits_width() {
+    let val =
+        read("(define (f x) (let ((y (* x x)) (z (+ x 1))) (if (> y z) (list y z) (vector z y))))");
+    assert_eq!(
+        pretty_print(&val, 40),
+        "(define (f x)
+  (let ((y (* x x)) (z (+ x 1)))
+    (if (> y z)
+        (list y z)
+        (vector z y))))"
+    );
+    assert_eq!(pretty_print(&val, 200), print(&val, Mode::Write));
+}
+
+#[test]
+fn pretty_data() {
+    let val = read("#((alpha beta gamma) (delta epsilon) '(zeta eta theta))");
+    assert_eq!(
+        pretty_print(&val, 20),
+        "#((alpha beta gamma)
+  (delta epsilon)
+  '(zeta eta theta))"
+    );
+    let val = read("((a . 1) (b . 2) (c . 3))");
+    assert_eq!(
+        pretty_print(&val, 10),
+        "((a . 1)
+ (b . 2)
+ (c . 3))"
+    );
+}
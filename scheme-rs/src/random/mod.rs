@@ -0,0 +1,5 @@
+mod random;
+#[cfg(test)]
+mod tests;
+
+pub use random::RandomSource;
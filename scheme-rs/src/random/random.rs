@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use num::{BigInt, One, Signed, Zero};
+
+// The state every new source starts from, so a program that doesn't seed its
+// sources gets the same numbers on every run and every platform
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// SplitMix64, which spreads a seed over the generator's state
+fn splitmix(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn seeded(mut seed: u64) -> [u64; 4] {
+    [
+        splitmix(&mut seed),
+        splitmix(&mut seed),
+        splitmix(&mut seed),
+        splitmix(&mut seed),
+    ]
+}
+
+/// A deterministic pseudo-random number generator (xoshiro256**) for SRFI 27.
+/// Sources are shared by reference, like ports.
+#[derive(Clone)]
+pub struct RandomSource(Rc<RefCell<[u64; 4]>>);
+
+impl Default for RandomSource {
+    fn default() -> Self {
+        RandomSource(Rc::new(RefCell::new(seeded(DEFAULT_SEED))))
+    }
+}
+
+impl RandomSource {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The generator's state, which `set_state` restores
+    pub fn state(&self) -> [u64; 4] {
+        *self.0.borrow()
+    }
+
+    /// Restores a state from `state`. The all-zero state is rejected, since
+    /// the generator would only ever produce 0 from it.
+    pub fn set_state(&self, state: [u64; 4]) -> bool {
+        if state == [0; 4] {
+            return false;
+        }
+        self.0.replace(state);
+        true
+    }
+
+    /// Resets the state to one determined by `i` and `j`, so that different
+    /// pairs give independent-looking streams
+    pub fn pseudo_randomize(&self, i: &BigInt, j: &BigInt) {
+        // Each word is mixed into the seed in turn. SplitMix64 is a
+        // bijection, so seeds that differ before a word still differ after.
+        let mut seed = DEFAULT_SEED;
+        let mut absorb = |word: u64| {
+            let mut x = seed ^ word;
+            seed = splitmix(&mut x);
+        };
+        for n in [i, j] {
+            n.iter_u64_digits().for_each(&mut absorb);
+            // The length ends each number, so that e.g. (1 0) and (0 1) differ
+            absorb(n.bits());
+        }
+        self.0.replace(seeded(seed));
+    }
+
+    /// Resets the state from the system's entropy source, so the stream
+    /// differs from run to run
+    pub fn randomize(&self) {
+        let (hi, lo) = uuid::Uuid::new_v4().as_u64_pair();
+        self.pseudo_randomize(&BigInt::from(hi), &BigInt::from(lo));
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        let mut s = self.0.borrow_mut();
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// A uniformly distributed integer in [0, `n`), for a positive `n`
+    pub fn integer(&self, n: &BigInt) -> BigInt {
+        debug_assert!(n.is_positive());
+        if n.is_one() {
+            return BigInt::zero();
+        }
+        // Draws as many bits as `n` - 1 needs until the result is below `n`,
+        // which takes fewer than two draws on average
+        let bits = (n - 1u32).bits();
+        loop {
+            let mut candidate = BigInt::zero();
+            let mut remaining = bits;
+            while remaining > 0 {
+                let take = remaining.min(64);
+                candidate = (candidate << take) | BigInt::from(self.next_u64() >> (64 - take));
+                remaining -= take;
+            }
+            if &candidate < n {
+                return candidate;
+            }
+        }
+    }
+
+    /// A uniformly distributed float strictly between 0 and 1
+    pub fn real(&self) -> f64 {
+        loop {
+            let x = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+            if x > 0.0 {
+                return x;
+            }
+        }
+    }
+}
+
+// Sources are compared by identity
+impl PartialEq for RandomSource {
+    fn eq(&self, other: &RandomSource) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for RandomSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RandomSource").field(&self.state()).finish()
+    }
+}
+
+impl fmt::Display for RandomSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<random-source>")
+    }
+}
@@ -0,0 +1,66 @@
+use num::BigInt;
+
+use super::*;
+
+#[test]
+fn sources_start_in_the_same_state() {
+    let (a, b) = (RandomSource::new(), RandomSource::new());
+    let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+    let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+    assert_eq!(xs, ys);
+    assert_ne!(a, b);
+    assert_eq!(a, a.clone());
+}
+
+#[test]
+fn restoring_state() {
+    let source = RandomSource::new();
+    let state = source.state();
+    let first = source.next_u64();
+    assert!(source.set_state(state));
+    assert_eq!(source.next_u64(), first);
+    assert!(!source.set_state([0; 4]));
+}
+
+#[test]
+fn pseudo_randomizing() {
+    let (a, b) = (RandomSource::new(), RandomSource::new());
+    a.pseudo_randomize(&BigInt::from(1), &BigInt::from(0));
+    b.pseudo_randomize(&BigInt::from(0), &BigInt::from(1));
+    assert_ne!(a.state(), b.state());
+    b.pseudo_randomize(&BigInt::from(1), &BigInt::from(0));
+    assert_eq!(a.state(), b.state());
+}
+
+#[test]
+fn integers_and_reals_are_in_range() {
+    let source = RandomSource::new();
+    let big = BigInt::from(1) << 100;
+    for _ in 0..1000 {
+        let n = source.integer(&BigInt::from(6));
+        assert!(n >= BigInt::from(0) && n < BigInt::from(6));
+        let n = source.integer(&big);
+        assert!(n >= BigInt::from(0) && n < big);
+        let x = source.real();
+        assert!(x > 0.0 && x < 1.0);
+    }
+    assert_eq!(source.integer(&BigInt::from(1)), BigInt::from(0));
+}
+
+#[test]
+fn distinct_seeds_give_distinct_streams() {
+    let mut firsts = Vec::new();
+    for i in 0..20 {
+        for j in 0..20 {
+            let source = RandomSource::new();
+            source.pseudo_randomize(&BigInt::from(i), &BigInt::from(j));
+            firsts.push(source.next_u64());
+        }
+    }
+    let big = RandomSource::new();
+    big.pseudo_randomize(&(BigInt::from(1) << 64), &BigInt::from(0));
+    firsts.push(big.next_u64());
+    firsts.sort();
+    firsts.dedup();
+    assert_eq!(firsts.len(), 401);
+}
@@ -0,0 +1,3 @@
+mod source;
+
+pub use source::{include, include_forms, load, Sources};
@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Env;
+use crate::error::{LispError, LispResult};
+use crate::eval::eval;
+use crate::file_system::{FileSystem, MemoryFileSystem};
+use crate::lisp_val::LispVal;
+use crate::parser;
+
+fn cannot_open(path: &str, err: impl std::fmt::Display) -> LispError {
+    LispError::GenericError(format!("cannot open file: {}\nsystem error: {}", path, err))
+}
+
+struct SourcesInner {
+    file_system: RefCell<Rc<dyn FileSystem>>,
+    // The files currently being loaded or included, innermost last
+    loading: RefCell<Vec<String>>,
+}
+
+/// The file system for an interpreter, and the files it's loading, shared by
+/// every frame of its environment.
+#[derive(Clone)]
+pub struct Sources(Rc<SourcesInner>);
+
+impl Default for Sources {
+    fn default() -> Self {
+        Sources::new(Rc::new(MemoryFileSystem::new()))
+    }
+}
+
+impl Sources {
+    pub fn new(file_system: Rc<dyn FileSystem>) -> Self {
+        Sources(Rc::new(SourcesInner {
+            file_system: RefCell::new(file_system),
+            loading: RefCell::new(vec![]),
+        }))
+    }
+
+    pub fn set_file_system(&self, file_system: Rc<dyn FileSystem>) {
+        *self.0.file_system.borrow_mut() = file_system;
+    }
+
+    pub fn file_system(&self) -> Rc<dyn FileSystem> {
+        self.0.file_system.borrow().clone()
+    }
+
+    /// The file currently being loaded, if any
+    pub fn current(&self) -> Option<String> {
+        self.0.loading.borrow().last().cloned()
+    }
+
+    /// Resolves `path` relative to the current file and reads it, returning
+    /// the resolved path along with its contents.
+    pub fn read(&self, path: &str) -> LispResult<(String, String)> {
+        let file_system = self.file_system();
+        let resolved = file_system.resolve(path, self.current().as_deref());
+        let contents = file_system
+            .read(&resolved)
+            .map_err(|err| cannot_open(&resolved, err))?;
+        let contents =
+            String::from_utf8(contents).map_err(|_| cannot_open(&resolved, "not valid UTF-8"))?;
+        Ok((resolved, contents))
+    }
+
+    /// Runs `f` with `path` as the current file, so that nested relative
+    /// paths resolve against it.
+    pub fn with_current<T>(&self, path: String, f: impl FnOnce() -> T) -> T {
+        self.0.loading.borrow_mut().push(path);
+        let result = f();
+        self.0.loading.borrow_mut().pop();
+        result
+    }
+}
+
+fn read_exprs(env: &Env, path: &str) -> LispResult<(String, Vec<LispVal>)> {
+    let (resolved, contents) = env.sources.read(path)?;
+    match parser::datum_list(&contents) {
+        Ok((rest, exprs)) if rest.trim().is_empty() => Ok((resolved, exprs)),
+        Ok((rest, _)) => Err(LispError::Parser(format!(
+            "{}: {}",
+            resolved,
+            rest.lines().next().unwrap_or_default()
+        ))),
+        Err(err) => Err(LispError::Parser(format!("{}: {}", resolved, err))),
+    }
+}
+
+fn source_path<'a>(form: &str, val: &'a LispVal) -> LispResult<&'a str> {
+    match val {
+        LispVal::String(path) => Ok(path),
+        _ => Err(LispError::TypeMismatch(
+            format!("{}: string", form),
+            val.clone(),
+        )),
+    }
+}
+
+/// Folds the case of every identifier in `val`, as `include-ci` requires.
+fn fold_case(val: LispVal) -> LispVal {
+    let fold_all = |xs: &[LispVal]| xs.iter().cloned().map(fold_case).collect::<Vec<_>>();
+    match val {
+        LispVal::Atom(s) => LispVal::Atom(s.to_lowercase()),
+        LispVal::List(xs) => LispVal::List(Rc::new(fold_all(&xs))),
+        LispVal::DottedList(xs, x) => {
+            LispVal::DottedList(Rc::new(fold_all(&xs)), Rc::new(fold_case((*x).clone())))
+        }
+        LispVal::Vector(xs) => LispVal::Vector(Rc::new(RefCell::new(fold_all(&xs.borrow())))),
+        LispVal::Quote(x) => LispVal::Quote(Rc::new(fold_case((*x).clone()))),
+        LispVal::QuasiQuote(x) => LispVal::QuasiQuote(Rc::new(fold_case((*x).clone()))),
+        LispVal::Unquote(x) => LispVal::Unquote(Rc::new(fold_case((*x).clone()))),
+        LispVal::UnquoteSplicing(x) => LispVal::UnquoteSplicing(Rc::new(fold_case((*x).clone()))),
+        val => val,
+    }
+}
+
+/// Reads the forms of each file for `include`/`include-ci`, in order
+pub fn include_forms(
+    env: &Env,
+    paths: &[LispVal],
+    fold: bool,
+) -> LispResult<Vec<(String, Vec<LispVal>)>> {
+    let form = if fold { "include-ci" } else { "include" };
+    paths
+        .iter()
+        .map(|path| {
+            let (resolved, exprs) = read_exprs(env, source_path(form, path)?)?;
+            let exprs = if fold {
+                exprs.into_iter().map(fold_case).collect()
+            } else {
+                exprs
+            };
+            Ok((resolved, exprs))
+        })
+        .collect()
+}
+
+/// `(include "file" ...)`: evaluates the contents of each file in place,
+/// returning the value of the last expression.
+pub fn include(env: &Env, paths: &[LispVal], fold: bool) -> LispResult<LispVal> {
+    let mut result = LispVal::Void;
+    for (resolved, exprs) in include_forms(env, paths, fold)? {
+        result = env
+            .sources
+            .with_current(resolved, || -> LispResult<LispVal> {
+                let mut result = LispVal::Void;
+                for expr in exprs.iter() {
+                    result = eval(env, expr)?;
+                }
+                Ok(result)
+            })?;
+    }
+    Ok(result)
+}
+
+/// `(load "file")`: evaluates every expression in the file.
+pub fn load(env: &Env, path: &LispVal) -> LispResult<LispVal> {
+    include(env, std::slice::from_ref(path), false)?;
+    Ok(LispVal::Void)
+}
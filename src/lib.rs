@@ -1,7 +1,9 @@
 mod utils;
 
-use scheme_rs::environment::Signal;
-use scheme_rs::lisp_val::LispVal;
+use std::rc::Rc;
+
+use scheme_rs::file_system::MemoryFileSystem;
+use scheme_rs::port::{Port, Signal};
 use utils::set_panic_hook;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
@@ -10,7 +12,11 @@ pub use scheme_rs::Thingus;
 
 // TODO: Any way to avoid re-wrapping this?
 #[wasm_bindgen]
-pub struct Thing(Thingus);
+pub struct Thing {
+    thingus: Thingus,
+    files: Rc<MemoryFileSystem>,
+    input: Port,
+}
 
 #[wasm_bindgen]
 extern "C" {
@@ -21,7 +27,7 @@ extern "C" {
 }
 
 fn wrap_signal(signal: js_sys::Function) -> Signal {
-    Box::new(move |vals| {
+    Box::new(move |output| {
         // TODO! HACK! This works around a refcell borrow issue :S
         // E.g. The port is borrowed in order to write to it, the port is
         // then signalled (i.e. the port callback is called). If an operation
@@ -30,13 +36,13 @@ fn wrap_signal(signal: js_sys::Function) -> Signal {
         // and we get a panic. The library
         // should handle this, the caller shouldn't have to know about this,
         // but this might need some further thinking...
-        let val = vals
-            .iter()
-            .filter(|val| **val != LispVal::Void)
-            .map(|val| format!("{}", val))
-            .collect::<Vec<String>>()
-            .join("\n");
-        setTimeout(&signal.bind1(&JsValue::NULL, &JsValue::from_str(&val)), 0)
+        let val = std::mem::take(output);
+        setTimeout(
+            signal
+                .bind1(&JsValue::NULL, &JsValue::from_str(&val))
+                .unchecked_ref(),
+            0,
+        )
     })
 }
 
@@ -46,23 +52,58 @@ impl Thing {
         set_panic_hook();
         let owned_signal = js_signal.to_owned();
         let wrapped_signal = wrap_signal(owned_signal);
-        Thing(Thingus::new(wrapped_signal))
+        let thingus = Thingus::new(wrapped_signal);
+        let files = Rc::new(MemoryFileSystem::new());
+        thingus.set_file_system(files.clone());
+        let input = Port::interactive();
+        thingus.ports.set_input(input.clone());
+        Thing {
+            thingus,
+            files,
+            input,
+        }
+    }
+    /// Makes library source available to `import`, e.g. `("foo/bar.sld", "(define-library (foo bar) ...)")`.
+    /// It's a file like any other, so it can also be loaded or included.
+    pub fn add_library(&self, path: String, source: String) {
+        self.files.insert(&path, &source)
+    }
+    /// Creates or replaces a file in the in-memory file system, e.g. for
+    /// `load`, `include`, `open-input-file` or, at a library's path (see
+    /// `add_library`), `import`
+    pub fn write_file(&self, path: String, contents: String) {
+        self.files.insert(&path, &contents)
     }
+    /// The contents of a file in the in-memory file system, e.g. one a script
+    /// wrote with `open-output-file`, or `undefined` if there isn't one
+    pub fn read_file(&self, path: String) -> Option<String> {
+        self.files.get(&path)
+    }
+    /// Lays out results over several lines to fit in `width` columns, or
+    /// writes each on one line if `width` is `undefined`
+    pub fn set_pretty_width(&self, width: Option<usize>) {
+        self.thingus.set_pretty_width(width)
+    }
+    /// Evaluates `input`. If the program reads more input than has been
+    /// pushed, evaluation pauses (see `is_waiting`) until `push_input`.
     pub fn eval(&self, input: String) -> String {
-        self.0.eval(&input)
+        self.thingus.eval(&input)
+    }
+    /// Adds text for the program to read from the current input port, e.g.
+    /// a line the user typed. If evaluation was waiting for input it carries
+    /// on, and the values of the remaining expressions are returned.
+    pub fn push_input(&self, text: String) -> String {
+        self.input.push_input(&text);
+        self.thingus.resume()
+    }
+    /// Whether evaluation is paused until more input is pushed
+    pub fn is_waiting(&self) -> bool {
+        self.thingus.is_waiting()
     }
     pub fn read_port(&self, port: String) -> String {
-        match self.0.ports.get(&port).take() {
+        match self.thingus.ports.get(&port).take() {
             None => "Port not found".to_string(),
-            Some(port) => {
-                let mut port = port.borrow_mut();
-                let vals = port.flush();
-                vals.iter()
-                    .filter(|val| **val != LispVal::Void)
-                    .map(|val| format!("{}", val))
-                    .collect::<Vec<String>>()
-                    .join("\n")
-            }
+            Some(port) => port.flush(),
         }
     }
 }
@@ -88,7 +129,7 @@ impl Thing {
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-fn noop(_port: &mut Vec<LispVal>) {}
+fn noop(_output: &mut String) {}
 
 #[wasm_bindgen]
 pub fn evaluate(input: &str) -> String {
@@ -1,20 +1,28 @@
 use std::{
-    env, fs,
+    env,
     io::{self},
+    rc::Rc,
 };
 
-use scheme_rs::{lisp_val::LispVal, Thingus};
+use scheme_rs::{file_system::OsFileSystem, Thingus};
 
 fn main() -> io::Result<()> {
     // TODO: Make this a lil' more sophisticated.
     // Currently it just executes whatever file it was passed
     let args: Vec<String> = env::args().collect();
     let file_name = &args[1];
-    let contents = fs::read_to_string(file_name).expect("Should have been able to read the file");
 
-    let signal = Box::new(move |_v: &mut Vec<LispVal>| {});
+    // Anything the program writes to the console is printed as it's written
+    let signal = Box::new(move |output: &mut String| {
+        print!("{}", output);
+        output.clear();
+    });
     let t = Thingus::new(signal);
-    let result = t.eval(&contents);
+    // Files are resolved relative to the working directory, and anything
+    // they `load` or `include` relative to themselves
+    t.set_file_system(Rc::new(OsFileSystem::new(env::current_dir()?)));
+    t.set_pretty_width(Some(80));
+    let result = t.eval_file(file_name);
     println!("{}", result);
     Ok(())
 }
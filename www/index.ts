@@ -12,6 +12,7 @@ const main = async () => {
     const input = document.querySelector("#scheme") as HTMLTextAreaElement;
     const output = document.querySelector("#output");
     const evalButton = document.querySelector("#eval");
+    const stdin = document.querySelector("#stdin") as HTMLInputElement;
 
     const doc = `(define (foldl fn acc ls)
     (if (null? ls)
@@ -21,8 +22,6 @@ const main = async () => {
     (if (null? ls)
         acc
         (fn (car ls) (foldr fn acc (cdr ls)))))
-(define (map fn ls)
-    (foldr (lambda (x xs) (cons (fn x) xs)) '() ls))
 (define (filter fn ls)
     (foldr (lambda (x xs) (if (fn x) (cons x xs) xs)) '() ls))
 (define (double n) (+ n n))
@@ -62,12 +61,33 @@ const main = async () => {
         parent: input
     });
 
-    const callback = () => {
+    // Called with whatever the program writes to the console
+    const callback = (text: string) => {
         // TODO!
-        console.log(t.read_port("default"));
+        console.log(text);
     }
 
     const t = Thing.new(callback);
+    // Large results are laid out over several lines
+    t.set_pretty_width(80);
+
+    // The input box is only enabled while the program is waiting to read
+    const showResult = (result: string) => {
+        const separator = output.textContent && result ? '\n' : '';
+        output.textContent += separator + result;
+        stdin.disabled = !t.is_waiting();
+        if (!stdin.disabled) {
+            stdin.focus();
+        }
+    };
+
+    stdin.addEventListener('keydown', (event) => {
+        if (event.key === 'Enter') {
+            const line = stdin.value;
+            stdin.value = '';
+            showResult(t.push_input(`${line}\n`));
+        }
+    });
 
     evalButton.addEventListener('click', () => {
         debugger
@@ -77,7 +97,8 @@ const main = async () => {
         const start = performance.now();
         const result = t.eval(input);
         console.log(`Time: ${performance.now() - start}`)
-        output.textContent = result;
+        output.textContent = '';
+        showResult(result);
     });
 };
 